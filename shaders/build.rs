@@ -5,6 +5,23 @@ fn main() {
     ensure_generated_dirs(&shader_folder).unwrap();
 
     compile_d2d_shader(&shader_folder, "ps_5_0", "ThresholdPixelShader");
+    compile_d2d_shader(&shader_folder, "ps_5_0", "XDoGPixelShader");
+    compile_d2d_shader(&shader_folder, "ps_5_0", "SignedDifferencePixelShader");
+    compile_d2d_shader(&shader_folder, "ps_5_0", "StructureTensorPixelShader");
+    compile_d2d_shader(&shader_folder, "ps_5_0", "FlowDoGPixelShader");
+    compile_d2d_shader(&shader_folder, "ps_5_0", "FlowBlurPixelShader");
+    compile_d2d_shader(&shader_folder, "ps_5_0", "FlowTangentPixelShader");
+    compile_d2d_shader(&shader_folder, "ps_5_0", "CoherencePixelShader");
+    compile_d2d_shader(&shader_folder, "ps_5_0", "ZeroCrossingPixelShader");
+    compile_d2d_shader(&shader_folder, "ps_5_0", "LuminancePixelShader");
+    compile_d2d_shader(&shader_folder, "ps_5_0", "UnpremultiplyPixelShader");
+    compile_d2d_shader(&shader_folder, "ps_5_0", "EdgesToAlphaPixelShader");
+    compile_d2d_shader(&shader_folder, "ps_5_0", "ColorizePixelShader");
+    compile_d2d_shader(&shader_folder, "ps_5_0", "QuantizePixelShader");
+    compile_d2d_shader(&shader_folder, "ps_5_0", "BilateralPixelShader");
+    compile_d2d_shader(&shader_folder, "ps_5_0", "AnisotropicKuwaharaPixelShader");
+    compile_d2d_shader(&shader_folder, "ps_5_0", "MorphologyPixelShader");
+    println!("cargo:rerun-if-changed=src/Flow.hlsli");
 }
 
 fn compile_d2d_shader(shader_folder: &str, profile: &str, file_stem: &str) {
@@ -0,0 +1,130 @@
+#define D2D_INPUT_COUNT 2
+#define D2D_INPUT0_COMPLEX
+#define D2D_INPUT1_COMPLEX
+
+#include "d2d1effecthelpers.hlsli"
+#include "Flow.hlsli"
+
+#define SECTORS 8
+
+cbuffer constants : register(b0)
+{
+    float Radius : packoffset(c0.x);
+    float Sharpness : packoffset(c0.y);
+    float Alpha : packoffset(c0.z);
+};
+
+// How much an offset inside the unit ellipse belongs to each sector. Even
+// sectors come from the offset itself, odd ones from the offset turned by
+// 45 degrees.
+void PolynomialWeights(float2 v, float zeta, float eta, out float weights[SECTORS])
+{
+    float2 rotated = 0.70710678f * float2(v.x - v.y, v.x + v.y);
+    [unroll]
+    for (int first = 0; first < 2; first++)
+    {
+        float2 u = first == 0 ? v : rotated;
+        float vxx = zeta - eta * u.x * u.x;
+        float vyy = zeta - eta * u.y * u.y;
+        float z;
+        z = max(0, u.y + vxx);
+        weights[first] = z * z;
+        z = max(0, -u.x + vyy);
+        weights[first + 2] = z * z;
+        z = max(0, -u.y + vxx);
+        weights[first + 4] = z * z;
+        z = max(0, u.x + vyy);
+        weights[first + 6] = z * z;
+    }
+}
+
+D2D_PS_ENTRY(main)
+{
+    if (Radius <= 0)
+    {
+        return D2DGetInput(0);
+    }
+
+    float3 tensor = UnpackTensor(D2DSampleInputAtOffset(1, float2(0, 0)));
+    float2 eigenvalues = Eigenvalues(tensor);
+    float anisotropy = 0;
+    if (eigenvalues.x + eigenvalues.y > 1e-8f)
+    {
+        anisotropy = (eigenvalues.x - eigenvalues.y) / (eigenvalues.x + eigenvalues.y);
+    }
+    float2 gradient = GradientDirection(tensor);
+    float cosPhi = -gradient.y;
+    float sinPhi = gradient.x;
+
+    // Semi-axes along and across the tangent
+    float a = Radius * clamp((Alpha + anisotropy) / Alpha, 0.1f, 2.0f);
+    float b = Radius * clamp(Alpha / (Alpha + anisotropy), 0.1f, 2.0f);
+    int maxX = int(sqrt(a * a * cosPhi * cosPhi + b * b * sinPhi * sinPhi));
+    int maxY = int(sqrt(a * a * sinPhi * sinPhi + b * b * cosPhi * cosPhi));
+
+    // Overlap of neighbouring sectors
+    float zeta = 2.0f / Radius;
+    float zeroCrossing = 3.0f * 3.14159265f / 8.0f;
+    float eta = (zeta + cos(zeroCrossing)) / (sin(zeroCrossing) * sin(zeroCrossing));
+
+    float4 means[SECTORS];
+    float3 squares[SECTORS];
+    float weights[SECTORS];
+    [unroll]
+    for (int k = 0; k < SECTORS; k++)
+    {
+        means[k] = 0;
+        squares[k] = 0;
+        weights[k] = 0;
+    }
+
+    [loop]
+    for (int j = -maxY; j <= maxY; j++)
+    {
+        [loop]
+        for (int i = -maxX; i <= maxX; i++)
+        {
+            // The offset in the frame of the ellipse, which maps it onto a
+            // disk with a radius of 0.5
+            float2 v = float2(
+                (cosPhi * i + sinPhi * j) * 0.5f / a,
+                (-sinPhi * i + cosPhi * j) * 0.5f / b);
+            float lengthSquared = dot(v, v);
+            if (lengthSquared > 0.25f)
+            {
+                continue;
+            }
+            float sectorWeights[SECTORS];
+            PolynomialWeights(v, zeta, eta, sectorWeights);
+            float sum = 0;
+            [unroll]
+            for (int s = 0; s < SECTORS; s++)
+            {
+                sum += sectorWeights[s];
+            }
+            float gaussian = exp(-3.125f * lengthSquared) / sum;
+            float4 sample = D2DSampleInputAtOffset(0, float2(i, j));
+            [unroll]
+            for (int t = 0; t < SECTORS; t++)
+            {
+                float weight = sectorWeights[t] * gaussian;
+                means[t] += sample * weight;
+                squares[t] += sample.rgb * sample.rgb * weight;
+                weights[t] += weight;
+            }
+        }
+    }
+
+    float4 result = 0;
+    float weightSum = 0;
+    [unroll]
+    for (int n = 0; n < SECTORS; n++)
+    {
+        float4 mean = means[n] / weights[n];
+        float3 variance = abs(squares[n] / weights[n] - mean.rgb * mean.rgb);
+        float weight = 1.0f / (1.0f + pow(255.0f * (variance.r + variance.g + variance.b), 0.5f * Sharpness));
+        result += mean * weight;
+        weightSum += weight;
+    }
+    return result / weightSum;
+}
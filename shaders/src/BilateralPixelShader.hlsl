@@ -0,0 +1,40 @@
+#define D2D_INPUT_COUNT 1
+#define D2D_INPUT0_COMPLEX
+
+#include "d2d1effecthelpers.hlsli"
+
+cbuffer constants : register(b0)
+{
+    float SpatialSigma : packoffset(c0.x);
+    float RangeSigma : packoffset(c0.y);
+    int Radius : packoffset(c0.z);
+};
+
+D2D_PS_ENTRY(main)
+{
+    float4 center = D2DGetInput(0);
+    if (Radius <= 0 || RangeSigma <= 0)
+    {
+        return center;
+    }
+    float spatial = -0.5f / (SpatialSigma * SpatialSigma);
+    float range = -0.5f / (RangeSigma * RangeSigma);
+
+    float4 sum = 0;
+    float weightSum = 0;
+    [loop]
+    for (int y = -Radius; y <= Radius; y++)
+    {
+        [loop]
+        for (int x = -Radius; x <= Radius; x++)
+        {
+            float4 sample = D2DSampleInputAtOffset(0, float2(x, y));
+            float4 difference = sample - center;
+            float weight = exp((x * x + y * y) * spatial + dot(difference, difference) * range);
+            sum += sample * weight;
+            weightSum += weight;
+        }
+    }
+
+    return sum / weightSum;
+}
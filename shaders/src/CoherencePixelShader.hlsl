@@ -0,0 +1,17 @@
+#define D2D_INPUT_COUNT 1
+#define D2D_INPUT0_SIMPLE
+
+#include "d2d1effecthelpers.hlsli"
+#include "Flow.hlsli"
+
+D2D_PS_ENTRY(main)
+{
+    float2 eigenvalues = Eigenvalues(UnpackTensor(D2DGetInput(0)));
+    float sum = eigenvalues.x + eigenvalues.y;
+    float coherence = 0;
+    if (sum > 1e-8f)
+    {
+        coherence = (eigenvalues.x - eigenvalues.y) / sum;
+    }
+    return float4(coherence, coherence, coherence, 1);
+}
@@ -0,0 +1,61 @@
+#define D2D_INPUT_COUNT 1
+#define D2D_INPUT0_SIMPLE
+
+#include "d2d1effecthelpers.hlsli"
+
+#define EDGE_POLARITY_LIGHT 0
+#define EDGE_POLARITY_DARK 1
+
+#define MAX_STOPS 8
+
+cbuffer constants : register(b0)
+{
+    float4 Colors[MAX_STOPS] : packoffset(c0);
+    float4 Positions[MAX_STOPS / 4] : packoffset(c8);
+    uint StopCount : packoffset(c10.x);
+    uint Polarity : packoffset(c10.y);
+};
+
+float Position(uint index)
+{
+    return Positions[index / 4][index % 4];
+}
+
+// Straight-alpha color at t, clamped to the first and last stops
+float4 SampleGradient(float t)
+{
+    if (t <= Position(0))
+    {
+        return Colors[0];
+    }
+    [unroll]
+    for (uint i = 1; i < MAX_STOPS; i++)
+    {
+        if (i < StopCount && t <= Position(i))
+        {
+            float span = Position(i) - Position(i - 1);
+            float f = span > 0 ? (t - Position(i - 1)) / span : 1;
+            return lerp(Colors[i - 1], Colors[i], f);
+        }
+    }
+    return Colors[StopCount - 1];
+}
+
+D2D_PS_ENTRY(main)
+{
+    float4 pixel = D2DGetInput(0);
+    if (pixel.w <= 0)
+    {
+        return float4(0, 0, 0, 0);
+    }
+
+    float coverage = dot(pixel.rgb, float3(0.299f, 0.587f, 0.114f)) / pixel.w;
+    if (Polarity == EDGE_POLARITY_DARK)
+    {
+        coverage = 1 - coverage;
+    }
+
+    float4 color = SampleGradient(saturate(coverage));
+    float alpha = color.a * pixel.w;
+    return float4(color.rgb * alpha, alpha);
+}
@@ -0,0 +1,30 @@
+#define D2D_INPUT_COUNT 1
+#define D2D_INPUT0_SIMPLE
+
+#include "d2d1effecthelpers.hlsli"
+
+#define EDGE_POLARITY_LIGHT 0
+#define EDGE_POLARITY_DARK 1
+
+cbuffer constants : register(b0)
+{
+    uint Polarity : packoffset(c0.x);
+};
+
+D2D_PS_ENTRY(main)
+{
+    float4 pixel = D2DGetInput(0);
+    if (pixel.w <= 0)
+    {
+        return float4(0, 0, 0, 0);
+    }
+
+    float coverage = dot(pixel.rgb, float3(0.299f, 0.587f, 0.114f)) / pixel.w;
+    if (Polarity == EDGE_POLARITY_DARK)
+    {
+        coverage = 1 - coverage;
+    }
+
+    // Black lines, so the premultiplied color stays zero
+    return float4(0, 0, 0, saturate(coverage) * pixel.w);
+}
@@ -0,0 +1,48 @@
+// Helpers shared by the flow-based DoG shaders. The structure tensor
+// [E F; F G] is packed as [E, 0.5 + 0.5 * F, G, 1] to fit in unorm buffers.
+
+static const float3 Luma = float3(0.299f, 0.587f, 0.114f);
+
+float3 UnpackTensor(float4 pixel)
+{
+    if (pixel.w <= 0)
+    {
+        return float3(0, 0, 0);
+    }
+    return float3(pixel.x / pixel.w, 2 * pixel.y / pixel.w - 1, pixel.z / pixel.w);
+}
+
+// Major and minor eigenvalues of the tensor
+float2 Eigenvalues(float3 tensor)
+{
+    float e = tensor.x;
+    float f = tensor.y;
+    float g = tensor.z;
+    float root = sqrt((e - g) * (e - g) + 4 * f * f);
+    return 0.5f * float2(e + g + root, e + g - root);
+}
+
+// Unit vector across the edge. The tangent is (-gradient.y, gradient.x).
+float2 GradientDirection(float3 tensor)
+{
+    float e = tensor.x;
+    float f = tensor.y;
+    float g = tensor.z;
+    float major = Eigenvalues(tensor).x;
+    float2 gradient = e >= g ? float2(major - g, f) : float2(f, major - e);
+    float len = length(gradient);
+    if (len > 1e-8f)
+    {
+        return gradient / len;
+    }
+    return float2(1, 0);
+}
+
+float Gaussian(float t, float standardDeviation)
+{
+    if (standardDeviation <= 0)
+    {
+        return t == 0 ? 1 : 0;
+    }
+    return exp(-(t * t) / (2 * standardDeviation * standardDeviation));
+}
@@ -0,0 +1,48 @@
+#define D2D_INPUT_COUNT 2
+#define D2D_INPUT0_COMPLEX
+#define D2D_INPUT1_COMPLEX
+
+#include "d2d1effecthelpers.hlsli"
+#include "Flow.hlsli"
+
+cbuffer constants : register(b0)
+{
+    float StandardDeviation : packoffset(c0.x);
+    int Steps : packoffset(c0.y);
+};
+
+float2 TangentAt(float2 uv)
+{
+    float2 gradient = GradientDirection(UnpackTensor(InputTexture1.SampleLevel(InputSampler1, uv, 0)));
+    return float2(-gradient.y, gradient.x);
+}
+
+D2D_PS_ENTRY(main)
+{
+    float4 imageCoordinate = D2DGetInputCoordinate(0);
+    float4 tensorCoordinate = D2DGetInputCoordinate(1);
+
+    float4 sum = InputTexture0.SampleLevel(InputSampler0, imageCoordinate.xy, 0);
+    float weightSum = 1;
+    float2 tangent = TangentAt(tensorCoordinate.xy);
+    [unroll]
+    for (int side = 0; side < 2; side++)
+    {
+        // Follow the streamline in pixels, starting from this pixel's center
+        float2 position = 0;
+        float2 direction = side == 0 ? tangent : -tangent;
+        [loop]
+        for (int i = 1; i <= Steps; i++)
+        {
+            float2 next = TangentAt(tensorCoordinate.xy + tensorCoordinate.zw * position);
+            // Tangents have no orientation, keep following the same way
+            direction = dot(next, direction) < 0 ? -next : next;
+            position += direction;
+            float weight = Gaussian(i, StandardDeviation);
+            sum += InputTexture0.SampleLevel(InputSampler0, imageCoordinate.xy + imageCoordinate.zw * position, 0) * weight;
+            weightSum += weight;
+        }
+    }
+
+    return sum / weightSum;
+}
@@ -0,0 +1,42 @@
+#define D2D_INPUT_COUNT 2
+#define D2D_INPUT0_COMPLEX
+#define D2D_INPUT1_COMPLEX
+
+#include "d2d1effecthelpers.hlsli"
+#include "Flow.hlsli"
+
+cbuffer constants : register(b0)
+{
+    float Sigma1 : packoffset(c0.x);
+    float Sigma2 : packoffset(c0.y);
+    int Radius : packoffset(c0.z);
+};
+
+D2D_PS_ENTRY(main)
+{
+    float4 imageCoordinate = D2DGetInputCoordinate(0);
+    float4 tensorCoordinate = D2DGetInputCoordinate(1);
+    float2 gradient = GradientDirection(
+        UnpackTensor(InputTexture1.SampleLevel(InputSampler1, tensorCoordinate.xy, 0)));
+
+    float3 narrow = 0;
+    float3 wide = 0;
+    float narrowWeight = 0;
+    float wideWeight = 0;
+    [loop]
+    for (int i = -Radius; i <= Radius; i++)
+    {
+        float t = i;
+        float2 uv = imageCoordinate.xy + imageCoordinate.zw * gradient * t;
+        float3 value = InputTexture0.SampleLevel(InputSampler0, uv, 0).xyz;
+        float weight1 = Gaussian(t, Sigma1);
+        float weight2 = Gaussian(t, Sigma2);
+        narrow += value * weight1;
+        wide += value * weight2;
+        narrowWeight += weight1;
+        wideWeight += weight2;
+    }
+
+    // Signed, the buffer precision decides whether negative responses survive
+    return float4(narrow / narrowWeight - wide / wideWeight, 1.0f);
+}
@@ -0,0 +1,38 @@
+#define D2D_INPUT_COUNT 1
+#define D2D_INPUT0_SIMPLE
+
+#include "d2d1effecthelpers.hlsli"
+#include "Flow.hlsli"
+
+#define TANGENT_ENCODING_HUE 0
+#define TANGENT_ENCODING_VECTOR 1
+
+static const float Pi = 3.14159265f;
+
+cbuffer constants : register(b0)
+{
+    uint Encoding : packoffset(c0.x);
+};
+
+D2D_PS_ENTRY(main)
+{
+    float2 gradient = GradientDirection(UnpackTensor(D2DGetInput(0)));
+    float2 tangent = float2(-gradient.y, gradient.x);
+
+    if (Encoding == TANGENT_ENCODING_VECTOR)
+    {
+        // Flip the tangent so that it never points up
+        if (tangent.y < 0 || (tangent.y == 0 && tangent.x < 0))
+        {
+            tangent = -tangent;
+        }
+        return float4(0.5f + 0.5f * tangent, 0, 1);
+    }
+
+    // Tangents have no orientation, so 180 degrees apart is the same hue
+    float hue = atan2(tangent.y, tangent.x) / Pi;
+    hue -= floor(hue);
+    float3 offset = hue * 6 + float3(0, 4, 2);
+    float3 rgb = saturate(abs(offset - 6 * floor(offset / 6) - 3) - 1);
+    return float4(rgb, 1);
+}
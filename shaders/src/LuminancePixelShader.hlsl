@@ -0,0 +1,58 @@
+#define D2D_INPUT_COUNT 1
+#define D2D_INPUT0_SIMPLE
+
+#include "d2d1effecthelpers.hlsli"
+
+#define LUMINANCE_MODEL_REC601 0
+#define LUMINANCE_MODEL_REC709 1
+#define LUMINANCE_MODEL_LAB 2
+
+cbuffer constants : register(b0)
+{
+    uint Model : packoffset(c0.x);
+};
+
+float SrgbToLinear(float value)
+{
+    if (value <= 0.04045f)
+    {
+        return value / 12.92f;
+    }
+    return pow((value + 0.055f) / 1.055f, 2.4f);
+}
+
+// CIELAB L* of a relative luminance, divided by 100
+float Lightness(float y)
+{
+    static const float Delta = 6.0f / 29.0f;
+    float f = y > Delta * Delta * Delta ? pow(y, 1.0f / 3.0f) : y / (3 * Delta * Delta) + 4.0f / 29.0f;
+    return (116 * f - 16) / 100;
+}
+
+D2D_PS_ENTRY(main)
+{
+    float4 pixel = D2DGetInput(0);
+    if (pixel.w <= 0)
+    {
+        return float4(0, 0, 0, 0);
+    }
+    float3 rgb = pixel.rgb / pixel.w;
+
+    float value;
+    if (Model == LUMINANCE_MODEL_REC709)
+    {
+        value = dot(rgb, float3(0.2126f, 0.7152f, 0.0722f));
+    }
+    else if (Model == LUMINANCE_MODEL_LAB)
+    {
+        float y = 0.2126f * SrgbToLinear(rgb.r) + 0.7152f * SrgbToLinear(rgb.g) + 0.0722f * SrgbToLinear(rgb.b);
+        value = Lightness(y);
+    }
+    else
+    {
+        value = dot(rgb, float3(0.299f, 0.587f, 0.114f));
+    }
+
+    value *= pixel.w;
+    return float4(value, value, value, pixel.w);
+}
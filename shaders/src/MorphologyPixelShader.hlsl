@@ -0,0 +1,71 @@
+#define D2D_INPUT_COUNT 1
+#define D2D_INPUT0_COMPLEX
+
+#include "d2d1effecthelpers.hlsli"
+
+#define OPERATION_ERODE 0
+#define OPERATION_DILATE 1
+
+#define SHAPE_SQUARE 0
+#define SHAPE_DISK 1
+#define SHAPE_DIAMOND 2
+
+cbuffer constants : register(b0)
+{
+    uint Operation : packoffset(c0.x);
+    uint Shape : packoffset(c0.y);
+    int Radius : packoffset(c0.z);
+};
+
+bool InsideElement(int x, int y)
+{
+    if (Shape == SHAPE_DISK)
+    {
+        float limit = Radius + 0.5f;
+        return x * x + y * y <= limit * limit;
+    }
+    if (Shape == SHAPE_DIAMOND)
+    {
+        return abs(x) + abs(y) <= Radius;
+    }
+    return true;
+}
+
+D2D_PS_ENTRY(main)
+{
+    float4 result = 0;
+    bool found = false;
+    [loop]
+    for (int y = -Radius; y <= Radius; y++)
+    {
+        [loop]
+        for (int x = -Radius; x <= Radius; x++)
+        {
+            if (!InsideElement(x, y))
+            {
+                continue;
+            }
+            // Transparent samples lie outside the image
+            float4 sample = D2DSampleInputAtOffset(0, float2(x, y));
+            if (sample.a <= 0)
+            {
+                continue;
+            }
+            if (!found)
+            {
+                result = sample;
+                found = true;
+            }
+            else if (Operation == OPERATION_ERODE)
+            {
+                result = min(result, sample);
+            }
+            else
+            {
+                result = max(result, sample);
+            }
+        }
+    }
+
+    return result;
+}
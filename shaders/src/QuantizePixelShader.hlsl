@@ -0,0 +1,32 @@
+#define D2D_INPUT_COUNT 1
+#define D2D_INPUT0_SIMPLE
+
+#include "d2d1effecthelpers.hlsli"
+
+cbuffer constants : register(b0)
+{
+    uint Levels : packoffset(c0.x);
+    float Sharpness : packoffset(c0.y);
+};
+
+D2D_PS_ENTRY(main)
+{
+    float4 pixel = D2DGetInput(0);
+    float scale = tanh(0.5f * Sharpness);
+    if (pixel.w <= 0 || scale <= 0)
+    {
+        return pixel;
+    }
+    float3 rgb = pixel.rgb / pixel.w;
+    float luma = dot(rgb, float3(0.299f, 0.587f, 0.114f));
+
+    // Ramp from one level to the next around the closest bin boundary,
+    // scaled to meet the levels exactly
+    float step = 1.0f / (max(Levels, 2) - 1);
+    float boundary = (round(luma / step - 0.5f) + 0.5f) * step;
+    float offset = tanh((luma - boundary) / step * Sharpness) / scale;
+    float quantized = boundary + 0.5f * step * clamp(offset, -1.0f, 1.0f);
+
+    rgb = luma > 1e-4f ? min(rgb * (quantized / luma), 1.0f) : quantized;
+    return float4(rgb * pixel.w, pixel.w);
+}
@@ -0,0 +1,23 @@
+#define D2D_INPUT_COUNT 2
+#define D2D_INPUT0_SIMPLE
+#define D2D_INPUT1_SIMPLE
+
+#include "d2d1effecthelpers.hlsli"
+
+cbuffer constants : register(b0)
+{
+    float Gain : packoffset(c0.x);
+    float Bias : packoffset(c0.y);
+};
+
+D2D_PS_ENTRY(main)
+{
+    float4 a = D2DGetInput(0);
+    float4 b = D2DGetInput(1);
+
+    // Not clamped, so negative differences survive in float buffers
+    float4 pixel = Bias + Gain * (a - b);
+    pixel.w = 1.0f;
+
+    return pixel;
+}
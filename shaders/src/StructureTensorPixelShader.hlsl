@@ -0,0 +1,28 @@
+#define D2D_INPUT_COUNT 1
+#define D2D_INPUT0_COMPLEX
+
+#include "d2d1effecthelpers.hlsli"
+#include "Flow.hlsli"
+
+float LumaAtOffset(float2 offset)
+{
+    return dot(D2DSampleInputAtOffset(0, offset).xyz, Luma);
+}
+
+D2D_PS_ENTRY(main)
+{
+    float topLeft = LumaAtOffset(float2(-1, -1));
+    float top = LumaAtOffset(float2(0, -1));
+    float topRight = LumaAtOffset(float2(1, -1));
+    float left = LumaAtOffset(float2(-1, 0));
+    float right = LumaAtOffset(float2(1, 0));
+    float bottomLeft = LumaAtOffset(float2(-1, 1));
+    float bottom = LumaAtOffset(float2(0, 1));
+    float bottomRight = LumaAtOffset(float2(1, 1));
+
+    // Sobel, scaled so every tensor component stays within [0, 1]
+    float gx = (topRight + 2 * right + bottomRight - topLeft - 2 * left - bottomLeft) / 4;
+    float gy = (bottomLeft + 2 * bottom + bottomRight - topLeft - 2 * top - topRight) / 4;
+
+    return float4(gx * gx, 0.5f + 0.5f * gx * gy, gy * gy, 1.0f);
+}
@@ -3,13 +3,32 @@
 
 #include "d2d1effecthelpers.hlsli"
 
+#define THRESHOLD_MODE_HARD 0
+#define THRESHOLD_MODE_SMOOTHSTEP 1
+#define THRESHOLD_MODE_TANH 2
+
 cbuffer constants : register(b0)
 {
     float ThresholdValue : packoffset(c0.x);
+    uint Mode : packoffset(c0.y);
+    float Width : packoffset(c0.z);
 };
 
 float ApplyThreshold(float value)
 {
+    if (Width > 0)
+    {
+        if (Mode == THRESHOLD_MODE_SMOOTHSTEP)
+        {
+            float halfWidth = Width * 0.5f;
+            return smoothstep(ThresholdValue - halfWidth, ThresholdValue + halfWidth, value);
+        }
+        if (Mode == THRESHOLD_MODE_TANH)
+        {
+            return 0.5f + 0.5f * tanh((value - ThresholdValue) / Width);
+        }
+    }
+
     if (value >= ThresholdValue)
     {
         return 1;
@@ -0,0 +1,16 @@
+#define D2D_INPUT_COUNT 1
+#define D2D_INPUT0_SIMPLE
+
+#include "d2d1effecthelpers.hlsli"
+
+D2D_PS_ENTRY(main)
+{
+    float4 pixel = D2DGetInput(0);
+
+    // Straight color on an opaque pixel, transparent pixels turn black
+    if (pixel.w <= 0)
+    {
+        return float4(0, 0, 0, 1);
+    }
+    return float4(pixel.rgb / pixel.w, 1);
+}
@@ -0,0 +1,35 @@
+#define D2D_INPUT_COUNT 2
+#define D2D_INPUT0_SIMPLE
+#define D2D_INPUT1_SIMPLE
+
+#include "d2d1effecthelpers.hlsli"
+
+cbuffer constants : register(b0)
+{
+    float Sharpness : packoffset(c0.x);
+    float Epsilon : packoffset(c0.y);
+    float Phi : packoffset(c0.z);
+};
+
+float SoftThreshold(float value)
+{
+    if (value >= Epsilon)
+    {
+        return 1;
+    }
+    return 1 + tanh(Phi * (value - Epsilon));
+}
+
+D2D_PS_ENTRY(main)
+{
+    float4 narrow = D2DGetInput(0);
+    float4 wide = D2DGetInput(1);
+
+    float4 pixel = (1 + Sharpness) * narrow - Sharpness * wide;
+    pixel.x = SoftThreshold(pixel.x);
+    pixel.y = SoftThreshold(pixel.y);
+    pixel.z = SoftThreshold(pixel.z);
+    pixel.w = 1.0f;
+
+    return pixel;
+}
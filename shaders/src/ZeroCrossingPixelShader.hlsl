@@ -0,0 +1,38 @@
+#define D2D_INPUT_COUNT 1
+#define D2D_INPUT0_COMPLEX
+
+#include "d2d1effecthelpers.hlsli"
+
+cbuffer constants : register(b0)
+{
+    float MinSlope : packoffset(c0.x);
+};
+
+static const float3 Luma = float3(0.299f, 0.587f, 0.114f);
+
+D2D_PS_ENTRY(main)
+{
+    float value = dot(D2DGetInput(0).rgb, Luma);
+    float2 offsets[4] = { float2(1, 0), float2(-1, 0), float2(0, 1), float2(0, -1) };
+
+    float edge = 0;
+    [unroll]
+    for (int i = 0; i < 4; i++)
+    {
+        float4 pixel = D2DSampleInputAtOffset(0, offsets[i]);
+        // Outside of the image
+        if (pixel.w <= 0)
+        {
+            continue;
+        }
+        float neighbour = dot(pixel.rgb, Luma);
+        // Only the side of the crossing closer to zero, so edges stay thin
+        bool closer = abs(value) < abs(neighbour) || (abs(value) == abs(neighbour) && value > 0);
+        if ((value > 0) != (neighbour > 0) && closer && abs(value - neighbour) >= MinSlope)
+        {
+            edge = 1;
+        }
+    }
+
+    return float4(edge, edge, edge, 1);
+}
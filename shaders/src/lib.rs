@@ -4,3 +4,94 @@ pub fn threshold_pixel_shader() -> &'static [u8] {
         "/shaders/ThresholdPixelShader.cso"
     ))
 }
+
+pub fn xdog_pixel_shader() -> &'static [u8] {
+    include_bytes!(concat!(env!("OUT_DIR"), "/shaders/XDoGPixelShader.cso"))
+}
+
+pub fn signed_difference_pixel_shader() -> &'static [u8] {
+    include_bytes!(concat!(
+        env!("OUT_DIR"),
+        "/shaders/SignedDifferencePixelShader.cso"
+    ))
+}
+
+pub fn structure_tensor_pixel_shader() -> &'static [u8] {
+    include_bytes!(concat!(
+        env!("OUT_DIR"),
+        "/shaders/StructureTensorPixelShader.cso"
+    ))
+}
+
+pub fn flow_dog_pixel_shader() -> &'static [u8] {
+    include_bytes!(concat!(env!("OUT_DIR"), "/shaders/FlowDoGPixelShader.cso"))
+}
+
+pub fn flow_blur_pixel_shader() -> &'static [u8] {
+    include_bytes!(concat!(env!("OUT_DIR"), "/shaders/FlowBlurPixelShader.cso"))
+}
+
+pub fn flow_tangent_pixel_shader() -> &'static [u8] {
+    include_bytes!(concat!(
+        env!("OUT_DIR"),
+        "/shaders/FlowTangentPixelShader.cso"
+    ))
+}
+
+pub fn coherence_pixel_shader() -> &'static [u8] {
+    include_bytes!(concat!(
+        env!("OUT_DIR"),
+        "/shaders/CoherencePixelShader.cso"
+    ))
+}
+
+pub fn zero_crossing_pixel_shader() -> &'static [u8] {
+    include_bytes!(concat!(
+        env!("OUT_DIR"),
+        "/shaders/ZeroCrossingPixelShader.cso"
+    ))
+}
+
+pub fn luminance_pixel_shader() -> &'static [u8] {
+    include_bytes!(concat!(
+        env!("OUT_DIR"),
+        "/shaders/LuminancePixelShader.cso"
+    ))
+}
+
+pub fn unpremultiply_pixel_shader() -> &'static [u8] {
+    include_bytes!(concat!(
+        env!("OUT_DIR"),
+        "/shaders/UnpremultiplyPixelShader.cso"
+    ))
+}
+
+pub fn edges_to_alpha_pixel_shader() -> &'static [u8] {
+    include_bytes!(concat!(
+        env!("OUT_DIR"),
+        "/shaders/EdgesToAlphaPixelShader.cso"
+    ))
+}
+
+pub fn colorize_pixel_shader() -> &'static [u8] {
+    include_bytes!(concat!(env!("OUT_DIR"), "/shaders/ColorizePixelShader.cso"))
+}
+
+pub fn quantize_pixel_shader() -> &'static [u8] {
+    include_bytes!(concat!(env!("OUT_DIR"), "/shaders/QuantizePixelShader.cso"))
+}
+
+pub fn bilateral_pixel_shader() -> &'static [u8] {
+    include_bytes!(concat!(env!("OUT_DIR"), "/shaders/BilateralPixelShader.cso"))
+}
+
+pub fn anisotropic_kuwahara_pixel_shader() -> &'static [u8] {
+    include_bytes!(concat!(
+        env!("OUT_DIR"),
+        "/shaders/AnisotropicKuwaharaPixelShader.cso"
+    ))
+}
+
+pub fn morphology_pixel_shader() -> &'static [u8] {
+    include_bytes!(concat!(env!("OUT_DIR"), "/shaders/MorphologyPixelShader.cso"))
+}
@@ -0,0 +1,233 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+/// File extensions picked up when an input names a directory.
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "bmp", "tif", "tiff", "webp"];
+
+/// A single image to process and where its result goes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Job {
+    pub input: PathBuf,
+    pub output: PathBuf,
+}
+
+impl Job {
+    /// A path next to the output for an additional image, e.g.
+    /// `out/photo_tangent.png` for the output `out/photo.png`.
+    pub fn extra_output(&self, suffix: &str) -> PathBuf {
+        let stem = self
+            .output
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy();
+        let mut name = format!("{}_{}", stem, suffix);
+        if let Some(extension) = self.output.extension() {
+            name.push('.');
+            name.push_str(&extension.to_string_lossy());
+        }
+        self.output.with_file_name(name)
+    }
+}
+
+/// Expands the inputs given on the command line into a list of files.
+/// Directories contribute the images directly inside them and patterns
+/// containing `*`, `?` or `[` are expanded as globs.
+pub fn expand_inputs(inputs: &[String]) -> Result<Vec<PathBuf>, String> {
+    let mut files = Vec::new();
+    for input in inputs {
+        let path = Path::new(input);
+        if path.is_dir() {
+            let mut entries = std::fs::read_dir(path)
+                .map_err(|error| format!("could not read directory '{}': {}", input, error))?
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.is_file() && is_image_path(path))
+                .collect::<Vec<_>>();
+            if entries.is_empty() {
+                return Err(format!("directory '{}' contains no images", input));
+            }
+            entries.sort();
+            files.extend(entries);
+        } else if input.contains(['*', '?', '[']) {
+            let mut matches = glob::glob(input)
+                .map_err(|error| format!("invalid pattern '{}': {}", input, error))?
+                .filter_map(|entry| entry.ok())
+                .filter(|path| path.is_file())
+                .collect::<Vec<_>>();
+            if matches.is_empty() {
+                return Err(format!("pattern '{}' matched no files", input));
+            }
+            matches.sort();
+            files.extend(matches);
+        } else if path.is_file() {
+            files.push(path.to_owned());
+        } else {
+            return Err(format!("input file '{}' does not exist", input));
+        }
+    }
+    Ok(files)
+}
+
+/// Pairs every input with an output path built from `template`.
+///
+/// A single input uses the template verbatim. With several inputs the
+/// template has to contain `{stem}`, `{name}` or `{index}` so that every
+/// image gets its own file. `{dir}` expands to the input's directory and
+/// `{index}` to the input's zero-padded position in the batch.
+pub fn create_jobs(inputs: Vec<PathBuf>, template: &str) -> Result<Vec<Job>, String> {
+    if inputs.len() > 1
+        && !["{stem}", "{name}", "{index}"]
+            .iter()
+            .any(|p| template.contains(p))
+    {
+        return Err(format!(
+            "output '{}' needs a {{stem}}, {{name}} or {{index}} placeholder when processing {} images",
+            template,
+            inputs.len()
+        ));
+    }
+
+    let width = inputs.len().to_string().len();
+    let mut seen = HashSet::new();
+    let mut jobs = Vec::with_capacity(inputs.len());
+    for (index, input) in inputs.into_iter().enumerate() {
+        let output = output_path(template, &input, index, width);
+        if !seen.insert(output.clone()) {
+            return Err(format!(
+                "more than one input would be written to '{}'",
+                output.display()
+            ));
+        }
+        jobs.push(Job { input, output });
+    }
+    Ok(jobs)
+}
+
+fn output_path(template: &str, input: &Path, index: usize, width: usize) -> PathBuf {
+    let dir = input
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let stem = input.file_stem().unwrap_or_default().to_string_lossy();
+    let name = input.file_name().unwrap_or_default().to_string_lossy();
+    let output = template
+        .replace("{dir}", &dir.to_string_lossy())
+        .replace("{stem}", &stem)
+        .replace("{name}", &name)
+        .replace("{index}", &format!("{:0width$}", index, width = width));
+    PathBuf::from(output)
+}
+
+fn is_image_path(path: &Path) -> bool {
+    path.extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .is_some_and(|extension| IMAGE_EXTENSIONS.contains(&extension.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(paths: &[&str]) -> Vec<PathBuf> {
+        paths.iter().map(PathBuf::from).collect()
+    }
+
+    fn outputs(jobs: &[Job]) -> Vec<PathBuf> {
+        jobs.iter().map(|job| job.output.clone()).collect()
+    }
+
+    #[test]
+    fn a_single_input_uses_the_template_verbatim() {
+        let jobs = create_jobs(paths(&["photos/a.jpg"]), "out.png").unwrap();
+        assert_eq!(
+            jobs,
+            [Job {
+                input: "photos/a.jpg".into(),
+                output: "out.png".into()
+            }]
+        );
+    }
+
+    #[test]
+    fn several_inputs_need_a_placeholder() {
+        let error = create_jobs(paths(&["a.png", "b.png"]), "{dir}/out.png").unwrap_err();
+        assert!(error.contains("processing 2 images"), "{}", error);
+        for template in ["{stem}.png", "{name}", "{index}.png"] {
+            assert!(create_jobs(paths(&["a.png", "b.png"]), template).is_ok());
+        }
+    }
+
+    #[test]
+    fn placeholders_are_substituted() {
+        let jobs = create_jobs(
+            paths(&["photos/a.jpg", "b.tar.png"]),
+            "{dir}/edges/{stem}-{name}.png",
+        )
+        .unwrap();
+        assert_eq!(
+            outputs(&jobs),
+            paths(&["photos/edges/a-a.jpg.png", "./edges/b.tar-b.tar.png.png"])
+        );
+    }
+
+    #[test]
+    fn index_is_zero_padded_to_the_batch_size() {
+        let inputs = (0..10)
+            .map(|i| PathBuf::from(format!("{}.png", i)))
+            .collect();
+        let jobs = create_jobs(inputs, "out/{index}.png").unwrap();
+        assert_eq!(jobs[0].output, Path::new("out/00.png"));
+        assert_eq!(jobs[9].output, Path::new("out/09.png"));
+        let jobs = create_jobs(paths(&["a.png", "b.png"]), "{index}.png").unwrap();
+        assert_eq!(outputs(&jobs), paths(&["0.png", "1.png"]));
+    }
+
+    #[test]
+    fn inputs_sharing_an_output_are_rejected() {
+        let error = create_jobs(paths(&["a/photo.png", "b/photo.png"]), "{stem}.png").unwrap_err();
+        assert!(error.contains("'photo.png'"), "{}", error);
+        assert!(create_jobs(
+            paths(&["a/photo.png", "b/photo.png"]),
+            "{dir}/{stem}_edges.png"
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn extra_outputs_keep_the_extension() {
+        let job = Job {
+            input: "in.png".into(),
+            output: "out/photo.png".into(),
+        };
+        assert_eq!(
+            job.extra_output("tangent"),
+            Path::new("out/photo_tangent.png")
+        );
+        let job = Job {
+            input: "in.png".into(),
+            output: "photo".into(),
+        };
+        assert_eq!(job.extra_output("gain"), Path::new("photo_gain"));
+    }
+
+    #[test]
+    fn directories_contribute_their_images_in_order() {
+        let dir = std::env::temp_dir().join(format!("dogfun_batch_test_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("nested")).unwrap();
+        for name in ["b.PNG", "a.jpg", "notes.txt", "nested/c.png"] {
+            std::fs::write(dir.join(name), []).unwrap();
+        }
+        let input = dir.to_string_lossy().into_owned();
+        let files = expand_inputs(std::slice::from_ref(&input));
+        let pattern = expand_inputs(&[format!("{}/*.txt", input)]);
+        let empty = expand_inputs(&[dir.join("nested/*.jpg").to_string_lossy().into_owned()]);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(files.unwrap(), [dir.join("a.jpg"), dir.join("b.PNG")]);
+        assert_eq!(pattern.unwrap(), [dir.join("notes.txt")]);
+        assert!(empty.unwrap_err().contains("matched no files"));
+        let error = expand_inputs(&[input]).unwrap_err();
+        assert!(error.contains("does not exist"), "{}", error);
+    }
+}
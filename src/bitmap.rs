@@ -0,0 +1,108 @@
+use image::RgbaImage;
+
+use crate::{cpu::Image, io::to_unorm8};
+
+/// A decoded image stored as tightly packed premultiplied BGRA8 pixels, the
+/// layout `create_texture_from_bitmap` uploads into a
+/// `DXGI_FORMAT_B8G8R8A8_UNORM` texture.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bitmap {
+    width: u32,
+    height: u32,
+    bytes: Vec<u8>,
+}
+
+impl Bitmap {
+    pub fn new(width: u32, height: u32, bytes: Vec<u8>) -> Self {
+        assert_eq!(bytes.len(), (width * height * 4) as usize);
+        Self {
+            width,
+            height,
+            bytes,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Bytes per row
+    pub fn stride(&self) -> u32 {
+        self.width * 4
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Premultiplies and swizzles straight RGBA8 pixels.
+    pub fn from_rgba8(source: &RgbaImage) -> Self {
+        let bytes = source
+            .pixels()
+            .flat_map(|pixel| {
+                let [r, g, b, a] = pixel.0;
+                let premultiply = |value: u8| ((value as u32 * a as u32 + 127) / 255) as u8;
+                [premultiply(b), premultiply(g), premultiply(r), a]
+            })
+            .collect();
+        Self::new(source.width(), source.height(), bytes)
+    }
+
+    /// Converts back to straight RGBA8 pixels for encoding.
+    pub fn to_rgba8(&self) -> RgbaImage {
+        let mut output = RgbaImage::new(self.width, self.height);
+        for (dest, pixel) in output.pixels_mut().zip(self.bytes.chunks_exact(4)) {
+            let a = pixel[3];
+            let unpremultiply = |value: u8| {
+                if a == 0 {
+                    0
+                } else {
+                    ((value as u32 * 255 + a as u32 / 2) / a as u32).min(255) as u8
+                }
+            };
+            dest.0 = [
+                unpremultiply(pixel[2]),
+                unpremultiply(pixel[1]),
+                unpremultiply(pixel[0]),
+                a,
+            ];
+        }
+        output
+    }
+
+    pub fn from_image(image: &Image) -> Self {
+        let bytes = image
+            .pixels()
+            .iter()
+            .flat_map(|pixel| {
+                [
+                    to_unorm8(pixel[2]),
+                    to_unorm8(pixel[1]),
+                    to_unorm8(pixel[0]),
+                    to_unorm8(pixel[3]),
+                ]
+            })
+            .collect();
+        Self::new(image.width(), image.height(), bytes)
+    }
+
+    pub fn to_image(&self) -> Image {
+        let pixels = self
+            .bytes
+            .chunks_exact(4)
+            .map(|pixel| {
+                [
+                    pixel[2] as f32 / 255.0,
+                    pixel[1] as f32 / 255.0,
+                    pixel[0] as f32 / 255.0,
+                    pixel[3] as f32 / 255.0,
+                ]
+            })
+            .collect();
+        Image::from_pixels(self.width, self.height, pixels)
+    }
+}
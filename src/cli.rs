@@ -0,0 +1,1206 @@
+use std::path::{Path, PathBuf};
+
+use clap::{error::ErrorKind, CommandFactory, Parser, ValueEnum};
+
+use crate::batch::{create_jobs, expand_inputs, Job};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Backend {
+    /// Run the effect graph with Direct2D
+    #[cfg(windows)]
+    #[default]
+    #[value(name = "d2d")]
+    D2D,
+    /// Run the effect graph on the portable CPU implementation
+    #[cfg_attr(not(windows), default)]
+    Cpu,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Mode {
+    /// Plain Difference of Gaussians followed by a hard threshold
+    #[default]
+    Dog,
+    /// Extended Difference of Gaussians with a soft threshold
+    Xdog,
+    /// Flow-based Difference of Gaussians, which follows the edge tangent
+    /// flow for more coherent lines
+    Fdog,
+    /// Thin edges where the signed DoG response crosses zero (Marr–Hildreth)
+    ZeroCrossing,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ThresholdMode {
+    /// Binary step, produces aliased edges
+    #[default]
+    Hard,
+    /// Smoothstep ramp --threshold-width wide centered on the threshold
+    Smoothstep,
+    /// Hyperbolic tangent falloff scaled by --threshold-width
+    Tanh,
+    /// Canny's hysteresis: responses above --threshold are edges, and so
+    /// are responses above --low-threshold connected to them
+    Hysteresis,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Png,
+    #[value(alias = "jpg")]
+    Jpeg,
+    #[value(alias = "tif")]
+    Tiff,
+    Bmp,
+    /// Lossless WebP
+    Webp,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum PngCompression {
+    /// Larger files, quicker to write
+    Fast,
+    #[default]
+    Balanced,
+    /// Smallest files, slowest to write
+    High,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputColor {
+    /// 8-bit color with alpha
+    #[default]
+    Rgba,
+    /// 8-bit grayscale
+    Gray,
+    /// 1-bit black and white, PNG only
+    Bilevel,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ColorMode {
+    /// Filter each color channel, then convert the edges to grayscale
+    #[default]
+    Grayscale,
+    /// Convert to luminance (see --luminance) before blurring
+    Luminance,
+    /// Filter each color channel and keep the colored edges
+    Color,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum AlphaMode {
+    /// Fully opaque output
+    #[default]
+    Opaque,
+    /// Keep the transparency of the input
+    Preserve,
+    /// Black lines on a transparent background
+    Transparent,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum CompositeMode {
+    /// Darken the image with the lines
+    #[default]
+    Multiply,
+    /// Lighten the image with the lines
+    Screen,
+    /// Keep the darker of the image and the lines
+    Darken,
+    /// Keep the lighter of the image and the lines
+    Lighten,
+    /// Absolute difference of the image and the lines
+    Difference,
+    /// Subtract the lines from the image
+    Subtract,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Prefilter {
+    /// Bilateral filter, see --bilateral-sigma and --bilateral-range
+    Bilateral,
+    /// Painterly anisotropic Kuwahara filter, see --kuwahara-radius
+    Kuwahara,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum AutoThreshold {
+    /// Otsu's method, which splits the DoG response into edges and
+    /// background
+    Otsu,
+    /// Keep the strongest --keep-top percent of the positive responses
+    Percentile,
+    /// Turn --edge-density percent of the image into edges
+    Density,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Morphology {
+    /// Thin the lines
+    Erode,
+    /// Thicken the lines
+    Dilate,
+    /// Erode, then dilate, which removes specks and spurs
+    Open,
+    /// Dilate, then erode, which bridges small gaps
+    Close,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum StructuringElement {
+    /// Every pixel within the radius along both axes
+    #[default]
+    Square,
+    /// Pixels within the radius of the center
+    Disk,
+    /// Pixels within the radius in steps along the axes
+    Diamond,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum LuminanceModel {
+    /// Rec. 601 luma
+    #[default]
+    Rec601,
+    /// Rec. 709 luma
+    Rec709,
+    /// CIELAB lightness L*
+    Lab,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Precision {
+    /// 8-bit buffers, negative responses are clamped to zero
+    Unorm8,
+    /// Half-float buffers
+    Float16,
+    /// Full float buffers
+    Float32,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum TangentEncoding {
+    /// Orientation as a fully saturated hue
+    #[default]
+    Hue,
+    /// Tangent vector in the red and green channels
+    Vector,
+}
+
+/// Applies a Difference of Gaussians filter to an image.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Args {
+    /// Images to process. Directories and glob patterns are expanded
+    #[arg(short, long, num_args = 1.., required = true)]
+    pub input: Vec<String>,
+
+    /// Where to write the result. When processing several images this is a
+    /// template where {dir}, {stem}, {name} and {index} are replaced for
+    /// each input
+    #[arg(short, long, default_value = "dog.png")]
+    pub output: String,
+
+    /// Fail instead of replacing an existing output file
+    #[arg(long)]
+    pub no_clobber: bool,
+
+    /// Encode the output in this format instead of inferring it from the
+    /// output file extension
+    #[arg(short, long, value_enum)]
+    pub format: Option<OutputFormat>,
+
+    /// Quality of JPEG output, from 1 to 100
+    #[arg(long, default_value_t = 90, value_parser = clap::value_parser!(u8).range(1..=100))]
+    pub jpeg_quality: u8,
+
+    /// Compression level of PNG output
+    #[arg(long, value_enum, default_value_t)]
+    pub png_compression: PngCompression,
+
+    /// Pixel layout of the output file
+    #[arg(long, value_enum, default_value_t)]
+    pub output_color: OutputColor,
+
+    /// Standard deviation of the narrow Gaussian
+    #[arg(
+        long,
+        default_value_t = 3.0,
+        value_parser = parse_standard_deviation,
+        allow_negative_numbers = true
+    )]
+    pub sigma_1: f32,
+
+    /// Standard deviation of the wide Gaussian, must be larger than
+    /// --sigma-1. Defaults to 5
+    #[arg(
+        long,
+        value_parser = parse_standard_deviation,
+        allow_negative_numbers = true
+    )]
+    pub sigma_2: Option<f32>,
+
+    /// Ratio between the wide and narrow standard deviations in XDoG mode,
+    /// which takes it in place of --sigma-2. Defaults to 1.6
+    #[arg(
+        short = 'k',
+        long = "k",
+        value_parser = parse_finite,
+        conflicts_with = "sigma_2",
+        allow_negative_numbers = true
+    )]
+    pub k: Option<f32>,
+
+    /// Minimum difference for a pixel to be considered an edge. Defaults to
+    /// 0.01
+    #[arg(
+        short,
+        long,
+        value_parser = parse_threshold,
+        allow_negative_numbers = true
+    )]
+    pub threshold: Option<f32>,
+
+    /// How the DoG response is turned into edges. Defaults to hard
+    #[arg(long, value_enum)]
+    pub threshold_mode: Option<ThresholdMode>,
+
+    /// Width of the transition for the smoothstep and tanh threshold modes.
+    /// Defaults to 0.01
+    #[arg(long, value_parser = parse_non_negative)]
+    pub threshold_width: Option<f32>,
+
+    /// Weakest response --threshold-mode hysteresis keeps where it connects
+    /// to a strong edge. Defaults to half of --threshold
+    #[arg(long, value_parser = parse_threshold, allow_negative_numbers = true)]
+    pub low_threshold: Option<f32>,
+
+    /// Pick --threshold for each image from the histogram of its DoG
+    /// response and print it, so it can be passed to --threshold later.
+    /// Hysteresis keeps responses down to half of the picked value
+    #[arg(long, value_enum, conflicts_with_all = ["threshold", "low_threshold"])]
+    pub auto_threshold: Option<AutoThreshold>,
+
+    /// Percentage of the positive responses --auto-threshold percentile
+    /// keeps. Defaults to 10
+    #[arg(long, value_parser = parse_percent, requires = "auto_threshold")]
+    pub keep_top: Option<f32>,
+
+    /// Percentage of the image --auto-threshold density turns into edges.
+    /// Defaults to 5
+    #[arg(long, value_parser = parse_percent, requires = "auto_threshold")]
+    pub edge_density: Option<f32>,
+
+    /// Effect graph description (TOML or JSON) to run instead of the
+    /// built-in graph
+    #[arg(
+        short,
+        long,
+        conflicts_with_all = [
+            "sigma_1", "sigma_2", "k", "threshold", "threshold_mode", "threshold_width",
+            "low_threshold", "auto_threshold", "keep_top", "edge_density",
+            "mode", "sharpness", "epsilon", "phi", "tensor_sigma", "flow_sigma",
+            "min_slope", "color_mode", "luminance", "alpha", "unpremultiply",
+            "ink", "paper", "gradient", "composite", "opacity",
+            "quantize", "quantize_sharpness", "prefilter", "prefilter_iterations",
+            "bilateral_sigma", "bilateral_range", "kuwahara_radius", "kuwahara_sharpness",
+            "kuwahara_alpha", "morphology", "structuring_element", "morphology_radius",
+            "min_component_area", "skeletonize",
+        ]
+    )]
+    pub graph: Option<PathBuf>,
+
+    /// Which filter to apply
+    #[arg(short, long, value_enum, default_value_t)]
+    pub mode: Mode,
+
+    /// Which channels are filtered and how the edges are colored
+    #[arg(long, value_enum, default_value_t)]
+    pub color_mode: ColorMode,
+
+    /// Luminance used by --color-mode luminance
+    #[arg(long, value_enum, default_value_t)]
+    pub luminance: LuminanceModel,
+
+    /// What the alpha channel of the output holds
+    #[arg(long, value_enum, default_value_t)]
+    pub alpha: AlphaMode,
+
+    /// Color of the lines, e.g. #000000 or #1a237e. Defaults to black when
+    /// only --paper is given
+    #[arg(long, value_parser = parse_color)]
+    pub ink: Option<[f32; 4]>,
+
+    /// Color behind the lines, e.g. #ffffff or #ffffff00 for transparent.
+    /// Defaults to white when only --ink is given
+    #[arg(long, value_parser = parse_color)]
+    pub paper: Option<[f32; 4]>,
+
+    /// Gradient map from paper to ink, as comma separated colors with
+    /// optional positions, e.g. "#fff8e1, #ff7043 0.4, #3e2723"
+    #[arg(long, value_parser = parse_gradient, conflicts_with_all = ["ink", "paper"])]
+    pub gradient: Option<dogfun::Gradient>,
+
+    /// Draw the lines over the input image, or over its bands with
+    /// --quantize, with this blend mode. Unless --ink, --paper or --gradient
+    /// say otherwise the lines are black on white for multiply and darken,
+    /// and white on black for the others
+    #[arg(long, value_enum)]
+    pub composite: Option<CompositeMode>,
+
+    /// How much of the blended lines shows over the input image, from 0 to
+    /// 1. Defaults to 1
+    #[arg(long, value_parser = parse_unit)]
+    pub opacity: Option<f32>,
+
+    /// Flatten the input image into this many bands of lightness and draw
+    /// the lines over them. --composite picks how, multiply if not given
+    #[arg(long, value_parser = clap::value_parser!(u32).range(2..=256))]
+    pub quantize: Option<u32>,
+
+    /// Steepness of the steps between the --quantize bands. 0 keeps the
+    /// input as it is, large values give hard edged bands
+    #[arg(long, default_value_t = 8.0, value_parser = parse_non_negative, requires = "quantize")]
+    pub quantize_sharpness: f32,
+
+    /// Smooth the input with an edge-preserving filter before finding edges,
+    /// so that noise doesn't show up as speckles
+    #[arg(long, value_enum)]
+    pub prefilter: Option<Prefilter>,
+
+    /// How many times --prefilter runs, each pass smoothing the last one
+    #[arg(
+        long,
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u32).range(1..=16),
+        requires = "prefilter"
+    )]
+    pub prefilter_iterations: u32,
+
+    /// Standard deviation of the distance weights of the bilateral filter.
+    /// Defaults to 3
+    #[arg(long, value_parser = parse_bilateral_sigma)]
+    pub bilateral_sigma: Option<f32>,
+
+    /// Color difference, from 0 to 1 per channel, past which the bilateral
+    /// filter stops mixing pixels. Defaults to 0.1
+    #[arg(long, value_parser = parse_standard_deviation)]
+    pub bilateral_range: Option<f32>,
+
+    /// Radius of the Kuwahara filter in flat areas. Along clear edges it
+    /// stretches to up to twice as long. Defaults to 6
+    #[arg(long, value_parser = parse_kuwahara_radius)]
+    pub kuwahara_radius: Option<f32>,
+
+    /// How strongly the Kuwahara filter favours the least varying parts of
+    /// its kernel. Larger values give crisper strokes. Defaults to 8
+    #[arg(long, value_parser = parse_non_negative)]
+    pub kuwahara_sharpness: Option<f32>,
+
+    /// How readily the Kuwahara filter stretches along edges, smaller values
+    /// stretching it more. Defaults to 1
+    #[arg(long, value_parser = parse_standard_deviation)]
+    pub kuwahara_alpha: Option<f32>,
+
+    /// Clean up the thresholded lines with a morphological operation
+    #[arg(long, value_enum)]
+    pub morphology: Option<Morphology>,
+
+    /// Shape of the neighbourhood --morphology looks at
+    #[arg(long, value_enum, default_value_t, requires = "morphology")]
+    pub structuring_element: StructuringElement,
+
+    /// Radius of the --morphology neighbourhood in pixels
+    #[arg(
+        long,
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u32).range(1..=32),
+        requires = "morphology"
+    )]
+    pub morphology_radius: u32,
+
+    /// Remove lines made of fewer than this many connected pixels
+    #[arg(long, default_value_t = 0)]
+    pub min_component_area: u32,
+
+    /// Thin the lines to a single pixel
+    #[arg(long)]
+    pub skeletonize: bool,
+
+    /// Filter the straight color of translucent input pixels, so that soft
+    /// alpha doesn't skew the edges
+    #[arg(long)]
+    pub unpremultiply: bool,
+
+    /// XDoG sharpening weight p, as in `(1 + p) * G(σ) - p * G(kσ)`.
+    /// Defaults to 20
+    #[arg(long, value_parser = parse_non_negative)]
+    pub sharpness: Option<f32>,
+
+    /// XDoG level below which the soft threshold starts darkening. Defaults
+    /// to 0.5
+    #[arg(long, value_parser = parse_finite, allow_negative_numbers = true)]
+    pub epsilon: Option<f32>,
+
+    /// XDoG steepness of the soft threshold. Defaults to 10
+    #[arg(long, value_parser = parse_non_negative)]
+    pub phi: Option<f32>,
+
+    /// FDoG and Kuwahara smoothing of the structure tensor. Larger values
+    /// give a smoother edge tangent flow
+    #[arg(long, default_value_t = 2.0, value_parser = parse_non_negative)]
+    pub tensor_sigma: f32,
+
+    /// FDoG standard deviation of the blur along the flow, which joins
+    /// broken lines
+    #[arg(long, default_value_t = 3.0, value_parser = parse_non_negative)]
+    pub flow_sigma: f32,
+
+    /// Zero-crossing mode: smallest change of the DoG response across a sign
+    /// change that still counts as an edge
+    #[arg(long, default_value_t = 0.002, value_parser = parse_non_negative)]
+    pub min_slope: f32,
+
+    /// Also save the edge tangent flow next to each output, as the smoothed
+    /// structure tensor, its tangent direction and its coherence
+    #[arg(long)]
+    pub export_flow: bool,
+
+    /// How --export-flow draws the tangent direction
+    #[arg(long, value_enum, default_value_t, requires = "export_flow")]
+    pub tangent_encoding: TangentEncoding,
+
+    /// Save the result of every node in the graph next to each output, named
+    /// `<output>_<index>_<node>`, e.g. `dog_02_subtract.png`
+    #[arg(long)]
+    pub dump_nodes: bool,
+
+    /// Amplification of the signed DoG response saved by --dump-nodes, which
+    /// is drawn around mid gray
+    #[arg(
+        long,
+        default_value_t = 10.0,
+        value_parser = parse_finite,
+        requires = "dump_nodes",
+        allow_negative_numbers = true
+    )]
+    pub dump_gain: f32,
+
+    /// Which implementation of the effect graph to use
+    #[arg(short, long, value_enum, default_value_t)]
+    pub backend: Backend,
+
+    /// Precision of intermediate images. Float buffers keep the sign of the
+    /// DoG response, so small thresholds behave as expected. Defaults to
+    /// unorm8, float16 for the modes and prefilters that need it, or the
+    /// precision given by --graph
+    #[arg(long, value_enum)]
+    pub precision: Option<Precision>,
+}
+
+impl Args {
+    /// Parses the process arguments, exiting with a usage error if they are
+    /// invalid.
+    pub fn parse_and_validate() -> Self {
+        let args = Self::parse();
+        if let Err(error) = args.validate() {
+            error.exit();
+        }
+        args
+    }
+
+    /// Standard deviation of the wide Gaussian for the selected mode.
+    fn wide_sigma(&self) -> f32 {
+        match self.mode {
+            Mode::Dog | Mode::Fdog | Mode::ZeroCrossing => self.sigma_2.unwrap_or(5.0),
+            Mode::Xdog => self.sigma_1 * self.k.unwrap_or(1.6),
+        }
+    }
+
+    fn threshold(&self) -> f32 {
+        self.threshold.unwrap_or(0.01)
+    }
+
+    /// Expands the inputs and pairs each of them with its output path.
+    pub fn jobs(&self) -> Result<Vec<Job>, clap::Error> {
+        let inputs = expand_inputs(&self.input)
+            .map_err(|error| Self::command().error(ErrorKind::InvalidValue, error))?;
+        let jobs = create_jobs(inputs, &self.output)
+            .map_err(|error| Self::command().error(ErrorKind::InvalidValue, error))?;
+        // The template may take its extension from the input, e.g. {name}
+        for job in &jobs {
+            self.check_output(&job.output)
+                .map_err(|(kind, error)| Self::command().error(kind, error))?;
+        }
+        Ok(jobs)
+    }
+
+    pub fn backend(&self) -> dogfun::Backend {
+        match self.backend {
+            #[cfg(windows)]
+            Backend::D2D => dogfun::Backend::D2D,
+            Backend::Cpu => dogfun::Backend::Cpu,
+        }
+    }
+
+    pub fn params(&self) -> dogfun::DogParams {
+        let width = self.threshold_width.unwrap_or(0.01);
+        dogfun::DogParams {
+            mode: match self.mode {
+                Mode::Dog => dogfun::Mode::Dog,
+                Mode::Xdog => dogfun::Mode::Xdog,
+                Mode::Fdog => dogfun::Mode::Fdog,
+                Mode::ZeroCrossing => dogfun::Mode::ZeroCrossing,
+            },
+            color_mode: match self.color_mode {
+                ColorMode::Grayscale => dogfun::ColorMode::Grayscale,
+                ColorMode::Luminance => dogfun::ColorMode::Luminance(match self.luminance {
+                    LuminanceModel::Rec601 => dogfun::LuminanceModel::Rec601,
+                    LuminanceModel::Rec709 => dogfun::LuminanceModel::Rec709,
+                    LuminanceModel::Lab => dogfun::LuminanceModel::Lab,
+                }),
+                ColorMode::Color => dogfun::ColorMode::Color,
+            },
+            alpha_mode: match self.alpha {
+                AlphaMode::Opaque => dogfun::AlphaMode::Opaque,
+                AlphaMode::Preserve => dogfun::AlphaMode::Preserve,
+                AlphaMode::Transparent => dogfun::AlphaMode::Transparent,
+            },
+            unpremultiply: self.unpremultiply,
+            colorize: self.colorize(),
+            // --quantize draws the lines over the bands with multiply
+            composite: self
+                .composite
+                .or(self.quantize.map(|_| CompositeMode::Multiply))
+                .map(|mode| dogfun::Composite {
+                    mode: match mode {
+                        CompositeMode::Multiply => dogfun::BlendMode::Multiply,
+                        CompositeMode::Screen => dogfun::BlendMode::Screen,
+                        CompositeMode::Darken => dogfun::BlendMode::Darken,
+                        CompositeMode::Lighten => dogfun::BlendMode::Lighten,
+                        CompositeMode::Difference => dogfun::BlendMode::Difference,
+                        CompositeMode::Subtract => dogfun::BlendMode::Subtract,
+                    },
+                    opacity: self.opacity.unwrap_or(1.0),
+                }),
+            prefilter: self.prefilter.map(|prefilter| match prefilter {
+                Prefilter::Bilateral => dogfun::Prefilter::Bilateral {
+                    spatial_sigma: self.bilateral_sigma.unwrap_or(3.0),
+                    range_sigma: self.bilateral_range.unwrap_or(0.1),
+                },
+                Prefilter::Kuwahara => dogfun::Prefilter::AnisotropicKuwahara {
+                    radius: self.kuwahara_radius.unwrap_or(6.0),
+                    sharpness: self.kuwahara_sharpness.unwrap_or(8.0),
+                    alpha: self.kuwahara_alpha.unwrap_or(1.0),
+                },
+            }),
+            prefilter_iterations: self.prefilter_iterations,
+            morphology: self.morphology.map(|mode| dogfun::Morphology {
+                mode: match mode {
+                    Morphology::Erode => dogfun::MorphologyMode::Erode,
+                    Morphology::Dilate => dogfun::MorphologyMode::Dilate,
+                    Morphology::Open => dogfun::MorphologyMode::Open,
+                    Morphology::Close => dogfun::MorphologyMode::Close,
+                },
+                shape: match self.structuring_element {
+                    StructuringElement::Square => dogfun::StructuringElement::Square,
+                    StructuringElement::Disk => dogfun::StructuringElement::Disk,
+                    StructuringElement::Diamond => dogfun::StructuringElement::Diamond,
+                },
+                radius: self.morphology_radius,
+            }),
+            min_component_area: self.min_component_area,
+            skeletonize: self.skeletonize,
+            quantize: self.quantize.map(|levels| dogfun::Quantize {
+                levels,
+                sharpness: self.quantize_sharpness,
+            }),
+            sigma_1: self.sigma_1,
+            sigma_2: self.wide_sigma(),
+            threshold: self.threshold(),
+            threshold_mode: match self.threshold_mode.unwrap_or_default() {
+                ThresholdMode::Hard => dogfun::ThresholdMode::Hard,
+                ThresholdMode::Smoothstep => dogfun::ThresholdMode::Smoothstep { width },
+                ThresholdMode::Tanh => dogfun::ThresholdMode::Tanh { width },
+                ThresholdMode::Hysteresis => dogfun::ThresholdMode::Hard,
+            },
+            low_threshold: (self.threshold_mode == Some(ThresholdMode::Hysteresis))
+                .then(|| self.low_threshold.unwrap_or(0.5 * self.threshold())),
+            auto_threshold: self.auto_threshold.map(|method| match method {
+                AutoThreshold::Otsu => dogfun::ThresholdSelection::Otsu,
+                AutoThreshold::Percentile => dogfun::ThresholdSelection::Percentile {
+                    fraction: self.keep_top.unwrap_or(10.0) / 100.0,
+                },
+                AutoThreshold::Density => dogfun::ThresholdSelection::Density {
+                    fraction: self.edge_density.unwrap_or(5.0) / 100.0,
+                },
+            }),
+            sharpness: self.sharpness.unwrap_or(20.0),
+            epsilon: self.epsilon.unwrap_or(0.5),
+            phi: self.phi.unwrap_or(10.0),
+            tensor_sigma: self.tensor_sigma,
+            flow_sigma: self.flow_sigma,
+            min_slope: self.min_slope,
+            precision: self.precision().unwrap_or_default(),
+        }
+    }
+
+    /// The gradient --ink, --paper or --gradient ask for, if any.
+    fn colorize(&self) -> Option<dogfun::Gradient> {
+        if let Some(gradient) = &self.gradient {
+            return Some(gradient.clone());
+        }
+        if self.ink.is_none() && self.paper.is_none() {
+            return None;
+        }
+        Some(dogfun::Gradient::ink_on_paper(
+            self.ink.unwrap_or([0.0, 0.0, 0.0, 1.0]),
+            self.paper.unwrap_or([1.0, 1.0, 1.0, 1.0]),
+        ))
+    }
+
+    /// The precision asked for on the command line, if any.
+    pub fn precision(&self) -> Option<dogfun::Precision> {
+        self.precision.map(|precision| match precision {
+            Precision::Unorm8 => dogfun::Precision::Unorm8,
+            Precision::Float16 => dogfun::Precision::Float16,
+            Precision::Float32 => dogfun::Precision::Float32,
+        })
+    }
+
+    /// The graph --export-flow runs next to the main one, at the precision
+    /// of the main graph.
+    pub fn flow_field(&self, precision: dogfun::Precision) -> dogfun::Graph {
+        let encoding = match self.tangent_encoding {
+            TangentEncoding::Hue => dogfun::TangentEncoding::Hue,
+            TangentEncoding::Vector => dogfun::TangentEncoding::Vector,
+        };
+        dogfun::Graph::flow_field(self.tensor_sigma, encoding, precision)
+    }
+
+    pub fn save_options(&self) -> dogfun::io::SaveOptions {
+        dogfun::io::SaveOptions {
+            overwrite: !self.no_clobber,
+            format: self.format(),
+            jpeg_quality: self.jpeg_quality,
+            png_compression: match self.png_compression {
+                PngCompression::Fast => dogfun::io::PngCompression::Fast,
+                PngCompression::Balanced => dogfun::io::PngCompression::Balanced,
+                PngCompression::High => dogfun::io::PngCompression::High,
+            },
+            color: match self.output_color {
+                OutputColor::Rgba => dogfun::io::OutputColor::Rgba8,
+                OutputColor::Gray => dogfun::io::OutputColor::Gray8,
+                OutputColor::Bilevel => dogfun::io::OutputColor::Gray1,
+            },
+        }
+    }
+
+    fn format(&self) -> Option<dogfun::io::OutputFormat> {
+        self.format.map(|format| match format {
+            OutputFormat::Png => dogfun::io::OutputFormat::Png,
+            OutputFormat::Jpeg => dogfun::io::OutputFormat::Jpeg,
+            OutputFormat::Tiff => dogfun::io::OutputFormat::Tiff,
+            OutputFormat::Bmp => dogfun::io::OutputFormat::Bmp,
+            OutputFormat::Webp => dogfun::io::OutputFormat::WebP,
+        })
+    }
+
+    /// Checks that the output format is known before any image is
+    /// processed, and that it can hold what --output-color and --alpha ask
+    /// for
+    fn check_output(&self, path: &Path) -> Result<(), (ErrorKind, String)> {
+        let Some(format) = self
+            .format()
+            .or_else(|| dogfun::io::OutputFormat::from_path(path))
+        else {
+            return Err((
+                ErrorKind::InvalidValue,
+                format!(
+                    "can't tell the output format of '{}', use a PNG, JPEG, TIFF, BMP or WebP extension or --format",
+                    path.display()
+                ),
+            ));
+        };
+        if self.output_color == OutputColor::Bilevel && format != dogfun::io::OutputFormat::Png {
+            return Err((
+                ErrorKind::ArgumentConflict,
+                format!(
+                    "--output-color bilevel is only supported for PNG output, not '{}'",
+                    path.display()
+                ),
+            ));
+        }
+        // The lines of --alpha transparent are only in the alpha channel
+        if self.alpha == AlphaMode::Transparent {
+            if self.output_color != OutputColor::Rgba {
+                return Err((
+                    ErrorKind::ArgumentConflict,
+                    "--alpha transparent needs --output-color rgba, the other layouts drop the alpha channel".to_owned(),
+                ));
+            }
+            if format == dogfun::io::OutputFormat::Jpeg {
+                return Err((
+                    ErrorKind::ArgumentConflict,
+                    format!(
+                        "--alpha transparent needs an output format with alpha, not JPEG for '{}'",
+                        path.display()
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), clap::Error> {
+        let mut command = Self::command();
+        if self.mode == Mode::Xdog {
+            if self.sigma_2.is_some() || self.threshold.is_some() {
+                return Err(command.error(
+                    ErrorKind::ArgumentConflict,
+                    "--sigma-2 and --threshold don't apply to --mode xdog, which uses -k and --epsilon instead",
+                ));
+            }
+            if let Some(k) = self.k.filter(|k| *k <= 1.0) {
+                return Err(command.error(
+                    ErrorKind::InvalidValue,
+                    format!("-k ({}) must be larger than 1", k),
+                ));
+            }
+        } else {
+            if self.k.is_some()
+                || self.sharpness.is_some()
+                || self.epsilon.is_some()
+                || self.phi.is_some()
+            {
+                return Err(command.error(
+                    ErrorKind::ArgumentConflict,
+                    "-k, --sharpness, --epsilon and --phi only apply to --mode xdog",
+                ));
+            }
+            if self.wide_sigma() <= self.sigma_1 {
+                return Err(command.error(
+                    ErrorKind::ArgumentConflict,
+                    format!(
+                        "--sigma-2 ({}) must be larger than --sigma-1 ({})",
+                        self.wide_sigma(),
+                        self.sigma_1
+                    ),
+                ));
+            }
+        }
+        if self.mode == Mode::ZeroCrossing
+            && (self.threshold.is_some() || self.threshold_width.is_some())
+        {
+            return Err(command.error(
+                ErrorKind::ArgumentConflict,
+                "--mode zero-crossing marks sign changes instead of thresholding, so --threshold and --threshold-width don't apply",
+            ));
+        }
+        if matches!(self.mode, Mode::Xdog | Mode::ZeroCrossing)
+            && (self.threshold_mode.is_some() || self.low_threshold.is_some())
+        {
+            return Err(command.error(
+                ErrorKind::ArgumentConflict,
+                "--threshold-mode and --low-threshold only apply to --mode dog and fdog",
+            ));
+        }
+        if self.threshold_width.is_some()
+            && !matches!(
+                self.threshold_mode,
+                Some(ThresholdMode::Smoothstep | ThresholdMode::Tanh)
+            )
+        {
+            return Err(command.error(
+                ErrorKind::ArgumentConflict,
+                "--threshold-width only applies to --threshold-mode smoothstep and tanh",
+            ));
+        }
+        if let Some(low_threshold) = self.low_threshold {
+            if self.threshold_mode != Some(ThresholdMode::Hysteresis) {
+                return Err(command.error(
+                    ErrorKind::ArgumentConflict,
+                    "--low-threshold only applies to --threshold-mode hysteresis",
+                ));
+            }
+            if low_threshold > self.threshold() {
+                return Err(command.error(
+                    ErrorKind::ArgumentConflict,
+                    format!(
+                        "--low-threshold ({}) must not be larger than --threshold ({})",
+                        low_threshold,
+                        self.threshold()
+                    ),
+                ));
+            }
+        }
+        if self.auto_threshold.is_some() && matches!(self.mode, Mode::Xdog | Mode::ZeroCrossing) {
+            return Err(command.error(
+                ErrorKind::ArgumentConflict,
+                "--auto-threshold only applies to --mode dog and fdog",
+            ));
+        }
+        if self.keep_top.is_some() && self.auto_threshold != Some(AutoThreshold::Percentile) {
+            return Err(command.error(
+                ErrorKind::ArgumentConflict,
+                "--keep-top only applies to --auto-threshold percentile",
+            ));
+        }
+        if self.edge_density.is_some() && self.auto_threshold != Some(AutoThreshold::Density) {
+            return Err(command.error(
+                ErrorKind::ArgumentConflict,
+                "--edge-density only applies to --auto-threshold density",
+            ));
+        }
+        if (self.bilateral_sigma.is_some() || self.bilateral_range.is_some())
+            && self.prefilter != Some(Prefilter::Bilateral)
+        {
+            return Err(command.error(
+                ErrorKind::ArgumentConflict,
+                "--bilateral-sigma and --bilateral-range only apply to --prefilter bilateral",
+            ));
+        }
+        if (self.kuwahara_radius.is_some()
+            || self.kuwahara_sharpness.is_some()
+            || self.kuwahara_alpha.is_some())
+            && self.prefilter != Some(Prefilter::Kuwahara)
+        {
+            return Err(command.error(
+                ErrorKind::ArgumentConflict,
+                "--kuwahara-radius, --kuwahara-sharpness and --kuwahara-alpha only apply to --prefilter kuwahara",
+            ));
+        }
+        if self.mode == Mode::ZeroCrossing && self.precision == Some(Precision::Unorm8) {
+            return Err(command.error(
+                ErrorKind::ArgumentConflict,
+                "--mode zero-crossing needs the negative DoG response, which --precision unorm8 clamps away",
+            ));
+        }
+        if (self.mode == Mode::Fdog || self.prefilter == Some(Prefilter::Kuwahara))
+            && self.precision == Some(Precision::Unorm8)
+        {
+            return Err(command.error(
+                ErrorKind::ArgumentConflict,
+                "--mode fdog and --prefilter kuwahara need the weak gradients of the structure tensor, which --precision unorm8 rounds away",
+            ));
+        }
+        if self.opacity.is_some() && self.composite.is_none() && self.quantize.is_none() {
+            return Err(command.error(
+                ErrorKind::ArgumentConflict,
+                "--opacity only applies when the lines are drawn over the image with --composite or --quantize",
+            ));
+        }
+        if (self.composite.is_some() || self.quantize.is_some())
+            && self.alpha == AlphaMode::Transparent
+        {
+            return Err(command.error(
+                ErrorKind::ArgumentConflict,
+                "--composite and --quantize draw over the input image, which --alpha transparent leaves out",
+            ));
+        }
+        // A template that takes its extension from the input, e.g. {name},
+        // is checked per image by jobs() instead
+        let output = Path::new(&self.output);
+        if output.extension().is_some() || !self.output.contains('{') {
+            self.check_output(output)
+                .map_err(|(kind, error)| command.error(kind, error))?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_standard_deviation(value: &str) -> Result<f32, String> {
+    let value: f32 = value
+        .parse()
+        .map_err(|_| format!("'{}' is not a number", value))?;
+    if !value.is_finite() || value <= 0.0 {
+        return Err("must be a positive number".to_owned());
+    }
+    Ok(value)
+}
+
+fn parse_bilateral_sigma(value: &str) -> Result<f32, String> {
+    let value = parse_standard_deviation(value)?;
+    // Matches the range declared in the bilateral effect's XML
+    if value > 50.0 {
+        return Err("must be at most 50".to_owned());
+    }
+    Ok(value)
+}
+
+fn parse_kuwahara_radius(value: &str) -> Result<f32, String> {
+    let value = parse_standard_deviation(value)?;
+    // Matches the range declared in the Kuwahara effect's XML
+    if value > 50.0 {
+        return Err("must be at most 50".to_owned());
+    }
+    Ok(value)
+}
+
+fn parse_threshold(value: &str) -> Result<f32, String> {
+    let value: f32 = value
+        .parse()
+        .map_err(|_| format!("'{}' is not a number", value))?;
+    // Matches the range declared in the threshold effect's XML
+    if !(0.0..=1000.0).contains(&value) {
+        return Err("must be between 0 and 1000".to_owned());
+    }
+    Ok(value)
+}
+
+fn parse_color(value: &str) -> Result<[f32; 4], String> {
+    dogfun::cpu::parse_color(value)
+}
+
+fn parse_gradient(value: &str) -> Result<dogfun::Gradient, String> {
+    dogfun::Gradient::parse(value)
+}
+
+fn parse_unit(value: &str) -> Result<f32, String> {
+    let value: f32 = value
+        .parse()
+        .map_err(|_| format!("'{}' is not a number", value))?;
+    if !(0.0..=1.0).contains(&value) {
+        return Err("must be between 0 and 1".to_owned());
+    }
+    Ok(value)
+}
+
+fn parse_percent(value: &str) -> Result<f32, String> {
+    let value: f32 = value
+        .parse()
+        .map_err(|_| format!("'{}' is not a number", value))?;
+    if !(value > 0.0 && value <= 100.0) {
+        return Err("must be larger than 0 and at most 100".to_owned());
+    }
+    Ok(value)
+}
+
+fn parse_finite(value: &str) -> Result<f32, String> {
+    let value: f32 = value
+        .parse()
+        .map_err(|_| format!("'{}' is not a number", value))?;
+    if !value.is_finite() {
+        return Err("must be a finite number".to_owned());
+    }
+    Ok(value)
+}
+
+fn parse_non_negative(value: &str) -> Result<f32, String> {
+    let value: f32 = value
+        .parse()
+        .map_err(|_| format!("'{}' is not a number", value))?;
+    if !value.is_finite() || value < 0.0 {
+        return Err("must be a non-negative number".to_owned());
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, clap::Error> {
+        let args = Args::try_parse_from(["dogfun", "-i", "in.png"].iter().chain(args))?;
+        args.validate()?;
+        Ok(args)
+    }
+
+    /// Asserts that `args` fail with `kind` and an error that mentions
+    /// `mention`.
+    fn rejects(args: &[&str], kind: ErrorKind, mention: &str) {
+        match parse(args) {
+            Ok(_) => panic!("{:?} was accepted", args),
+            Err(error) => {
+                assert_eq!(error.kind(), kind, "{:?}: {}", args, error);
+                assert!(error.to_string().contains(mention), "{:?}: {}", args, error);
+            }
+        }
+    }
+
+    #[test]
+    fn defaults_and_matching_options_are_accepted() {
+        for args in [
+            &[][..],
+            &["-m", "xdog", "-k", "2", "--epsilon", "-0.2", "--phi", "5"],
+            &["--threshold-mode", "hysteresis", "--low-threshold", "0.005"],
+            &["--threshold-mode", "tanh", "--threshold-width", "0.05"],
+            &["-m", "zero-crossing", "--min-slope", "0.01"],
+            &["--auto-threshold", "percentile", "--keep-top", "20"],
+            &["--prefilter", "bilateral", "--bilateral-sigma", "4"],
+            &["--prefilter", "kuwahara", "--kuwahara-radius", "8"],
+            &[
+                "--quantize",
+                "4",
+                "--composite",
+                "screen",
+                "--opacity",
+                "0.5",
+            ],
+            &["--alpha", "transparent", "-o", "out.webp"],
+            &[
+                "--output-color",
+                "bilevel",
+                "-o",
+                "out.jpg",
+                "--format",
+                "png",
+            ],
+            &["--dump-nodes", "--dump-gain", "-5"],
+        ] {
+            if let Err(error) = parse(args) {
+                panic!("{:?} was rejected: {}", args, error);
+            }
+        }
+    }
+
+    #[test]
+    fn non_finite_and_out_of_range_values_are_rejected() {
+        let kind = ErrorKind::ValueValidation;
+        rejects(&["-m", "xdog", "-k", "NaN"], kind, "finite");
+        rejects(&["-m", "xdog", "--epsilon", "inf"], kind, "finite");
+        rejects(&["--dump-nodes", "--dump-gain", "NaN"], kind, "finite");
+        rejects(&["--sigma-1", "0"], kind, "positive");
+        rejects(&["--sigma-1", "inf"], kind, "positive");
+        rejects(&["-t", "1001"], kind, "1000");
+        rejects(
+            &["--prefilter", "bilateral", "--bilateral-sigma", "51"],
+            kind,
+            "50",
+        );
+        rejects(
+            &["--auto-threshold", "density", "--edge-density", "0"],
+            kind,
+            "100",
+        );
+    }
+
+    #[test]
+    fn options_of_other_modes_are_rejected() {
+        let kind = ErrorKind::ArgumentConflict;
+        rejects(&["-m", "xdog", "--sigma-2", "9"], kind, "--sigma-2");
+        rejects(&["-m", "xdog", "-t", "0.7"], kind, "--threshold");
+        rejects(&["-k", "2"], kind, "-k");
+        rejects(&["-m", "fdog", "--phi", "3"], kind, "--phi");
+        rejects(&["-m", "zero-crossing", "-t", "0.5"], kind, "--threshold");
+        rejects(
+            &["-m", "zero-crossing", "--threshold-mode", "smoothstep"],
+            kind,
+            "--threshold-mode",
+        );
+        rejects(
+            &["-m", "xdog", "--threshold-mode", "hysteresis"],
+            kind,
+            "--threshold-mode",
+        );
+        rejects(
+            &["-m", "zero-crossing", "--low-threshold", "0.001"],
+            kind,
+            "--low-threshold",
+        );
+        rejects(
+            &["-m", "xdog", "--auto-threshold", "otsu"],
+            kind,
+            "--auto-threshold",
+        );
+    }
+
+    #[test]
+    fn options_without_the_option_they_refine_are_rejected() {
+        let kind = ErrorKind::ArgumentConflict;
+        rejects(&["--threshold-width", "0.1"], kind, "--threshold-width");
+        rejects(&["--low-threshold", "0.001"], kind, "--low-threshold");
+        rejects(
+            &["--auto-threshold", "otsu", "--keep-top", "5"],
+            kind,
+            "--keep-top",
+        );
+        rejects(
+            &["--auto-threshold", "percentile", "--edge-density", "5"],
+            kind,
+            "--edge-density",
+        );
+        rejects(&["--opacity", "0.5"], kind, "--opacity");
+        rejects(&["--bilateral-sigma", "2"], kind, "--bilateral-sigma");
+        rejects(
+            &["--prefilter", "bilateral", "--kuwahara-alpha", "2"],
+            kind,
+            "--kuwahara",
+        );
+        rejects(
+            &["--prefilter", "kuwahara", "--bilateral-range", "0.2"],
+            kind,
+            "--bilateral",
+        );
+    }
+
+    #[test]
+    fn conflicting_values_are_rejected() {
+        let kind = ErrorKind::ArgumentConflict;
+        rejects(&["--sigma-2", "2"], kind, "--sigma-2 (2)");
+        rejects(&["--sigma-1", "6"], kind, "--sigma-1 (6)");
+        rejects(
+            &["-m", "xdog", "-k", "1"],
+            ErrorKind::InvalidValue,
+            "-k (1)",
+        );
+        rejects(
+            &[
+                "--threshold-mode",
+                "hysteresis",
+                "-t",
+                "0.01",
+                "--low-threshold",
+                "0.02",
+            ],
+            kind,
+            "--low-threshold (0.02)",
+        );
+        rejects(
+            &["--auto-threshold", "otsu", "-t", "0.1"],
+            kind,
+            "--threshold",
+        );
+        rejects(
+            &["-m", "zero-crossing", "--precision", "unorm8"],
+            kind,
+            "unorm8",
+        );
+        rejects(
+            &["--prefilter", "kuwahara", "--precision", "unorm8"],
+            kind,
+            "unorm8",
+        );
+        rejects(
+            &["--composite", "multiply", "--alpha", "transparent"],
+            kind,
+            "--alpha",
+        );
+    }
+
+    #[test]
+    fn outputs_that_cannot_hold_the_result_are_rejected() {
+        rejects(&["-o", "out.gif"], ErrorKind::InvalidValue, "out.gif");
+        rejects(&["-o", "out"], ErrorKind::InvalidValue, "--format");
+        let kind = ErrorKind::ArgumentConflict;
+        rejects(
+            &["-o", "out.jpg", "--output-color", "bilevel"],
+            kind,
+            "bilevel",
+        );
+        rejects(
+            &["-o", "out.png", "-f", "tiff", "--output-color", "bilevel"],
+            kind,
+            "PNG",
+        );
+        rejects(
+            &["--alpha", "transparent", "--output-color", "gray"],
+            kind,
+            "rgba",
+        );
+        rejects(&["--alpha", "transparent", "-o", "out.jpg"], kind, "JPEG");
+        // Extensions taken from the input are checked per image by jobs()
+        assert!(parse(&["-o", "out/{name}", "--output-color", "bilevel"]).is_ok());
+    }
+}
@@ -0,0 +1,48 @@
+use super::{luma, Image};
+
+/// Which lines of an edge image [`edges_to_alpha`] treats as the edges.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EdgePolarity {
+    /// Light lines on a dark background, like the DoG threshold produces
+    #[default]
+    Light,
+    /// Dark lines on a light background, like XDoG produces
+    Dark,
+}
+
+/// Mirrors `UnpremultiplyPixelShader.hlsl`: divides the color by alpha and
+/// makes the pixel opaque, so that later nodes see the straight color of
+/// translucent pixels. Fully transparent pixels turn black.
+pub fn unpremultiply(input: &Image) -> Image {
+    input.map(|pixel| {
+        let alpha = pixel[3];
+        if alpha <= 0.0 {
+            return [0.0, 0.0, 0.0, 1.0];
+        }
+        [pixel[0] / alpha, pixel[1] / alpha, pixel[2] / alpha, 1.0]
+    })
+}
+
+/// Equivalent of `CLSID_D2D1AlphaMask`: scales the image by the alpha of
+/// the mask.
+pub fn alpha_mask(input: &Image, mask: &Image) -> Image {
+    input.zip_map(mask, |pixel, mask| pixel.map(|value| value * mask[3]))
+}
+
+/// Mirrors `EdgesToAlphaPixelShader.hlsl`: turns the edges of an opaque edge
+/// image into black lines on a transparent background. The line coverage is
+/// the Rec. 601 luma of the un-premultiplied input, inverted for dark lines.
+pub fn edges_to_alpha(input: &Image, polarity: EdgePolarity) -> Image {
+    input.map(|pixel| {
+        let alpha = pixel[3];
+        if alpha <= 0.0 {
+            return [0.0; 4];
+        }
+        let luma = luma(&pixel) / alpha;
+        let coverage = match polarity {
+            EdgePolarity::Light => luma,
+            EdgePolarity::Dark => 1.0 - luma,
+        };
+        [0.0, 0.0, 0.0, coverage.clamp(0.0, 1.0) * alpha]
+    })
+}
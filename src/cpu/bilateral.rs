@@ -0,0 +1,101 @@
+//! Edge-preserving smoothing ahead of the DoG, which keeps image noise from
+//! turning into speckles.
+
+use super::Image;
+
+/// Mirrors `BilateralPixelShader.hlsl`: averages the pixels around each one,
+/// weighted by both their distance and how different their premultiplied
+/// color is. Pixels across a strong edge hardly count, so edges stay sharp
+/// while flat areas are smoothed.
+pub fn bilateral(input: &Image, spatial_sigma: f32, range_sigma: f32) -> Image {
+    // Covers the spatial weights down to about 14%
+    let radius = (spatial_sigma * 2.0).ceil().max(0.0) as i32;
+    if radius == 0 || range_sigma <= 0.0 {
+        return input.clone();
+    }
+    let spatial = -0.5 / (spatial_sigma * spatial_sigma);
+    let range = -0.5 / (range_sigma * range_sigma);
+    let width = input.width() as i32;
+    let mut output = Image::new(input.width(), input.height());
+    for (index, pixel) in output.pixels_mut().iter_mut().enumerate() {
+        let x = index as i32 % width;
+        let y = index as i32 / width;
+        let center = input.pixel(x, y);
+        let mut sum = [0.0f32; 4];
+        let mut weight_sum = 0.0;
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                let sample = input.pixel(x + dx, y + dy);
+                let distance: f32 = (0..4).map(|c| (sample[c] - center[c]).powi(2)).sum();
+                let weight = ((dx * dx + dy * dy) as f32 * spatial + distance * range).exp();
+                for c in 0..4 {
+                    sum[c] += sample[c] * weight;
+                }
+                weight_sum += weight;
+            }
+        }
+        *pixel = sum.map(|value| value / weight_sum);
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: u32 = 48;
+
+    /// A vertical step from 0.2 to 0.8 in the middle, with up to ±0.05 of
+    /// fixed pseudo-random noise.
+    fn noisy_step() -> Image {
+        let pixels = (0..SIZE * SIZE)
+            .map(|index| {
+                let (x, y) = (index % SIZE, index / SIZE);
+                let noise = ((x * 7919 + y * 104729) % 61) as f32 / 60.0 - 0.5;
+                let value = if x < SIZE / 2 { 0.2 } else { 0.8 } + 0.1 * noise;
+                [value, value, value, 1.0]
+            })
+            .collect();
+        Image::from_pixels(SIZE, SIZE, pixels)
+    }
+
+    /// Mean and standard deviation of the red channel over a block of
+    /// columns, away from the top and bottom border.
+    fn statistics(image: &Image, columns: std::ops::Range<i32>) -> (f32, f32) {
+        let values: Vec<f32> = (12..36)
+            .flat_map(|y| columns.clone().map(move |x| (x, y)))
+            .map(|(x, y)| image.pixel(x, y)[0])
+            .collect();
+        let mean = values.iter().sum::<f32>() / values.len() as f32;
+        let variance = values
+            .iter()
+            .map(|value| (value - mean).powi(2))
+            .sum::<f32>()
+            / values.len() as f32;
+        (mean, variance.sqrt())
+    }
+
+    fn filter(input: &Image) -> Image {
+        bilateral(input, 3.0, 0.1)
+    }
+
+    #[test]
+    fn step_edges_survive() {
+        let output = filter(&noisy_step());
+        let (dark, _) = statistics(&output, 23..24);
+        let (light, _) = statistics(&output, 24..25);
+        assert!(dark < 0.25 && light > 0.75, "{} {}", dark, light);
+    }
+
+    #[test]
+    fn flat_noise_is_reduced() {
+        let input = noisy_step();
+        let output = filter(&input);
+        for columns in [10..18, 30..38] {
+            let (mean, noise) = statistics(&input, columns.clone());
+            let (smoothed_mean, smoothed_noise) = statistics(&output, columns);
+            assert!((smoothed_mean - mean).abs() < 0.01);
+            assert!(smoothed_noise < 0.5 * noise, "{} {}", noise, smoothed_noise);
+        }
+    }
+}
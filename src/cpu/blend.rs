@@ -0,0 +1,119 @@
+use super::Image;
+
+/// The subset of `D2D1_BLEND_MODE` supported by both backends.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlendMode {
+    Subtract,
+    Multiply,
+    Screen,
+    Darken,
+    Lighten,
+    Difference,
+}
+
+/// Equivalent of `CLSID_D2D1Blend`, where `destination` is input 0 and
+/// `source` is input 1.
+pub fn blend(destination: &Image, source: &Image, mode: BlendMode) -> Image {
+    match mode {
+        BlendMode::Subtract => blend_with(destination, source, |cs, cd| (cd - cs).max(0.0)),
+        BlendMode::Multiply => blend_with(destination, source, |cs, cd| cs * cd),
+        BlendMode::Screen => blend_with(destination, source, |cs, cd| cs + cd - cs * cd),
+        BlendMode::Darken => blend_with(destination, source, f32::min),
+        BlendMode::Lighten => blend_with(destination, source, f32::max),
+        BlendMode::Difference => blend_with(destination, source, |cs, cd| (cd - cs).abs()),
+    }
+}
+
+/// Applies a separable blend function `f(source, destination)` on straight
+/// colors and composites the result the same way the D2D blend effect does.
+fn blend_with<F: Fn(f32, f32) -> f32>(destination: &Image, source: &Image, f: F) -> Image {
+    destination.zip_map(source, |d, s| {
+        let da = d[3];
+        let sa = s[3];
+        let mut result = [0.0; 4];
+        for c in 0..3 {
+            let cd = if da > 0.0 { d[c] / da } else { 0.0 };
+            let cs = if sa > 0.0 { s[c] / sa } else { 0.0 };
+            result[c] = (1.0 - da) * s[c] + (1.0 - sa) * d[c] + sa * da * f(cs, cd);
+        }
+        result[3] = sa + da - sa * da;
+        result
+    })
+}
+
+/// Equivalent of `CLSID_D2D1CrossFade`: `weight` of `source` over
+/// `1 - weight` of `destination`.
+pub fn cross_fade(destination: &Image, source: &Image, weight: f32) -> Image {
+    destination.zip_map(source, |d, s| {
+        std::array::from_fn(|c| d[c] + (s[c] - d[c]) * weight)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn opaque(value: f32) -> Image {
+        Image::from_pixels(1, 1, vec![[value, value, value, 1.0]])
+    }
+
+    fn blend_opaque(destination: f32, source: f32, mode: BlendMode) -> f32 {
+        let output = blend(&opaque(destination), &opaque(source), mode);
+        assert_eq!(output.pixels()[0][3], 1.0);
+        output.pixels()[0][0]
+    }
+
+    #[test]
+    fn subtract_takes_source_from_destination() {
+        assert_eq!(blend_opaque(0.75, 0.25, BlendMode::Subtract), 0.5);
+    }
+
+    #[test]
+    fn subtract_clamps_negative_differences() {
+        assert_eq!(blend_opaque(0.25, 0.75, BlendMode::Subtract), 0.0);
+    }
+
+    #[test]
+    fn subtract_over_transparent_keeps_the_other_input() {
+        let transparent = Image::from_pixels(1, 1, vec![[0.0; 4]]);
+        let output = blend(&transparent, &opaque(0.5), BlendMode::Subtract);
+        assert_eq!(output.pixels()[0], [0.5, 0.5, 0.5, 1.0]);
+        let output = blend(&opaque(0.5), &transparent, BlendMode::Subtract);
+        assert_eq!(output.pixels()[0], [0.5, 0.5, 0.5, 1.0]);
+    }
+
+    #[test]
+    fn composite_modes_match_their_formulas() {
+        let (d, s) = (0.8, 0.25);
+        assert_eq!(blend_opaque(d, s, BlendMode::Multiply), d * s);
+        assert_eq!(blend_opaque(d, s, BlendMode::Screen), d + s - d * s);
+        assert_eq!(blend_opaque(d, s, BlendMode::Darken), s);
+        assert_eq!(blend_opaque(d, s, BlendMode::Lighten), d);
+        assert_eq!(blend_opaque(d, s, BlendMode::Difference), d - s);
+        assert_eq!(blend_opaque(s, d, BlendMode::Difference), d - s);
+    }
+
+    #[test]
+    fn neutral_lines_leave_the_image_unchanged() {
+        // White lines for the darkening modes, black ones for the others
+        for value in [0.0, 0.3, 1.0] {
+            assert_eq!(blend_opaque(value, 1.0, BlendMode::Multiply), value);
+            assert_eq!(blend_opaque(value, 1.0, BlendMode::Darken), value);
+            assert_eq!(blend_opaque(value, 0.0, BlendMode::Screen), value);
+            assert_eq!(blend_opaque(value, 0.0, BlendMode::Lighten), value);
+            assert_eq!(blend_opaque(value, 0.0, BlendMode::Difference), value);
+            assert_eq!(blend_opaque(value, 0.0, BlendMode::Subtract), value);
+        }
+    }
+
+    #[test]
+    fn cross_fade_interpolates_every_channel() {
+        let destination = Image::from_pixels(1, 1, vec![[0.0, 0.2, 1.0, 1.0]]);
+        let source = Image::from_pixels(1, 1, vec![[1.0, 0.6, 0.0, 0.0]]);
+        let output = cross_fade(&destination, &source, 0.25);
+        let expected = [0.25, 0.3, 0.75, 0.75];
+        for (value, expected) in output.pixels()[0].iter().zip(expected) {
+            assert!((value - expected).abs() < 1e-6);
+        }
+    }
+}
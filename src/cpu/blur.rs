@@ -0,0 +1,101 @@
+use super::Image;
+
+/// Builds a normalized 1D Gaussian kernel covering +/- 3 standard deviations,
+/// which is the same support D2D uses for its Gaussian blur.
+pub fn gaussian_kernel(standard_deviation: f32) -> Vec<f32> {
+    if standard_deviation <= 0.0 {
+        return vec![1.0];
+    }
+    let radius = (standard_deviation * 3.0).ceil() as i32;
+    let denominator = 2.0 * standard_deviation * standard_deviation;
+    let mut kernel: Vec<_> = (-radius..=radius)
+        .map(|i| (-((i * i) as f32) / denominator).exp())
+        .collect();
+    let sum: f32 = kernel.iter().sum();
+    for weight in &mut kernel {
+        *weight /= sum;
+    }
+    kernel
+}
+
+/// Separable Gaussian blur with `D2D1_BORDER_MODE_HARD` semantics: samples
+/// outside the image are transparent black and the output keeps the input's
+/// bounds.
+pub fn gaussian_blur(input: &Image, standard_deviation: f32) -> Image {
+    let kernel = gaussian_kernel(standard_deviation);
+    let horizontal = convolve(input, &kernel, true);
+    convolve(&horizontal, &kernel, false)
+}
+
+fn convolve(input: &Image, kernel: &[f32], horizontal: bool) -> Image {
+    let width = input.width() as i32;
+    let height = input.height() as i32;
+    let radius = (kernel.len() / 2) as i32;
+    let pixels = input.pixels();
+    let mut output = Image::new(input.width(), input.height());
+    let output_pixels = output.pixels_mut();
+    for y in 0..height {
+        for x in 0..width {
+            let mut sum = [0.0f32; 4];
+            for (i, weight) in kernel.iter().enumerate() {
+                let offset = i as i32 - radius;
+                let (sx, sy) = if horizontal {
+                    (x + offset, y)
+                } else {
+                    (x, y + offset)
+                };
+                if sx < 0 || sy < 0 || sx >= width || sy >= height {
+                    continue;
+                }
+                let sample = pixels[(sy * width + sx) as usize];
+                for c in 0..4 {
+                    sum[c] += sample[c] * weight;
+                }
+            }
+            output_pixels[(y * width + x) as usize] = sum;
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kernel_covers_three_standard_deviations() {
+        assert_eq!(gaussian_kernel(1.0).len(), 7);
+        assert_eq!(gaussian_kernel(1.5).len(), 11);
+        assert_eq!(gaussian_kernel(3.0).len(), 19);
+    }
+
+    #[test]
+    fn kernel_is_normalized_and_symmetric() {
+        let kernel = gaussian_kernel(2.5);
+        let sum: f32 = kernel.iter().sum();
+        assert!((sum - 1.0).abs() < 1e-6);
+        for (a, b) in kernel.iter().zip(kernel.iter().rev()) {
+            assert_eq!(a, b);
+        }
+        let center = kernel.len() / 2;
+        assert!(kernel.windows(2).take(center).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn zero_standard_deviation_is_identity() {
+        assert_eq!(gaussian_kernel(0.0), vec![1.0]);
+        let input = Image::from_pixels(2, 1, vec![[0.2, 0.4, 0.6, 1.0], [1.0; 4]]);
+        assert_eq!(gaussian_blur(&input, 0.0), input);
+    }
+
+    #[test]
+    fn blur_fades_into_transparent_borders() {
+        let input = Image::from_pixels(3, 1, vec![[1.0; 4]; 3]);
+        let output = gaussian_blur(&input, 1.0);
+        let pixels = output.pixels();
+        assert!(pixels[0][3] < pixels[1][3]);
+        for (a, b) in pixels[0].iter().zip(&pixels[2]) {
+            assert!((a - b).abs() < 1e-6);
+        }
+    }
+}
@@ -0,0 +1,268 @@
+use super::{luma, EdgePolarity, Image};
+
+/// Most stops a [`Gradient`] can have, the size of the array in
+/// `ColorizePixelShader.hlsl`.
+pub const MAX_GRADIENT_STOPS: usize = 8;
+
+/// A straight-alpha sRGB color at a position along a [`Gradient`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColorStop {
+    pub position: f32,
+    pub color: [f32; 4],
+}
+
+/// Colors that [`colorize`] maps the edge coverage through, from paper at
+/// position 0 to ink at position 1.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gradient {
+    stops: Vec<ColorStop>,
+}
+
+impl Gradient {
+    /// Checks that there are between 1 and [`MAX_GRADIENT_STOPS`] stops and
+    /// that their positions are finite and don't decrease.
+    pub fn new(stops: Vec<ColorStop>) -> Result<Self, String> {
+        if stops.is_empty() || stops.len() > MAX_GRADIENT_STOPS {
+            return Err(format!(
+                "a gradient needs between 1 and {} stops",
+                MAX_GRADIENT_STOPS
+            ));
+        }
+        if stops.iter().any(|stop| !stop.position.is_finite()) {
+            return Err("gradient positions must be finite numbers".to_owned());
+        }
+        if stops
+            .windows(2)
+            .any(|pair| pair[1].position < pair[0].position)
+        {
+            return Err("gradient positions must not decrease".to_owned());
+        }
+        Ok(Self { stops })
+    }
+
+    /// Paper where there are no edges and ink on the edges.
+    pub fn ink_on_paper(ink: [f32; 4], paper: [f32; 4]) -> Self {
+        Self {
+            stops: vec![
+                ColorStop {
+                    position: 0.0,
+                    color: paper,
+                },
+                ColorStop {
+                    position: 1.0,
+                    color: ink,
+                },
+            ],
+        }
+    }
+
+    /// Parses a comma separated list of colors, each optionally followed by
+    /// its position, e.g. `#ffffff, #ff8800 0.3, #000000`. Like CSS gradients,
+    /// the first and last stops default to 0 and 1 and stops without a
+    /// position are spread evenly between their neighbours.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut colors = Vec::new();
+        let mut positions = Vec::new();
+        for stop in text.split(',') {
+            let words: Vec<&str> = stop.split_whitespace().collect();
+            let position = match words.as_slice() {
+                [_] => None,
+                [_, position] => Some(
+                    position
+                        .parse::<f32>()
+                        .map_err(|_| format!("'{}' is not a number", position))?,
+                ),
+                _ => return Err(format!("'{}' is not a gradient stop", stop.trim())),
+            };
+            colors.push(parse_color(words[0])?);
+            positions.push(position);
+        }
+
+        let last = positions.len() - 1;
+        positions[0].get_or_insert(0.0);
+        if last > 0 {
+            positions[last].get_or_insert(1.0);
+        }
+        let mut start = 0;
+        for index in 1..=last {
+            if let Some(end) = positions[index] {
+                let from = positions[start].unwrap();
+                let count = (index - start) as f32;
+                for (step, position) in positions[start + 1..index].iter_mut().enumerate() {
+                    *position = Some(from + (end - from) * (step + 1) as f32 / count);
+                }
+                start = index;
+            }
+        }
+
+        let stops = colors
+            .into_iter()
+            .zip(positions)
+            .map(|(color, position)| ColorStop {
+                position: position.unwrap(),
+                color,
+            })
+            .collect();
+        Self::new(stops)
+    }
+
+    pub fn stops(&self) -> &[ColorStop] {
+        &self.stops
+    }
+
+    /// Straight-alpha color at `t`, clamped to the first and last stops.
+    pub fn sample(&self, t: f32) -> [f32; 4] {
+        let first = self.stops[0];
+        if t <= first.position {
+            return first.color;
+        }
+        for pair in self.stops.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+            if t <= to.position {
+                let span = to.position - from.position;
+                let f = if span > 0.0 {
+                    (t - from.position) / span
+                } else {
+                    1.0
+                };
+                return std::array::from_fn(|c| from.color[c] + (to.color[c] - from.color[c]) * f);
+            }
+        }
+        self.stops[self.stops.len() - 1].color
+    }
+}
+
+/// Parses `#rgb`, `#rrggbb` or `#rrggbbaa` into straight RGBA.
+pub fn parse_color(text: &str) -> Result<[f32; 4], String> {
+    let invalid = || format!("'{}' is not a color like #rrggbb or #rrggbbaa", text);
+    let hex = text.strip_prefix('#').ok_or_else(invalid)?;
+    if !hex.is_ascii() {
+        return Err(invalid());
+    }
+    let digits: Vec<u8> = match hex.len() {
+        3 => hex
+            .chars()
+            .map(|digit| u8::from_str_radix(&format!("{0}{0}", digit), 16))
+            .collect::<Result<_, _>>()
+            .map_err(|_| invalid())?,
+        6 | 8 => (0..hex.len())
+            .step_by(2)
+            .map(|index| u8::from_str_radix(&hex[index..index + 2], 16))
+            .collect::<Result<_, _>>()
+            .map_err(|_| invalid())?,
+        _ => return Err(invalid()),
+    };
+    let channel = |index: usize| digits.get(index).map_or(1.0, |value| *value as f32 / 255.0);
+    Ok([channel(0), channel(1), channel(2), channel(3)])
+}
+
+/// Mirrors `ColorizePixelShader.hlsl`: maps how much of an edge each pixel
+/// is, the Rec. 601 luma of the un-premultiplied input or its inverse for
+/// dark lines, through the gradient.
+pub fn colorize(input: &Image, polarity: EdgePolarity, gradient: &Gradient) -> Image {
+    input.map(|pixel| {
+        let alpha = pixel[3];
+        if alpha <= 0.0 {
+            return [0.0; 4];
+        }
+        let luma = luma(&pixel) / alpha;
+        let coverage = match polarity {
+            EdgePolarity::Light => luma,
+            EdgePolarity::Dark => 1.0 - luma,
+        };
+        let color = gradient.sample(coverage.clamp(0.0, 1.0));
+        let alpha = color[3] * alpha;
+        [color[0] * alpha, color[1] * alpha, color[2] * alpha, alpha]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn positions(gradient: &Gradient) -> Vec<f32> {
+        gradient.stops().iter().map(|stop| stop.position).collect()
+    }
+
+    #[test]
+    fn colors_parse_with_and_without_alpha() {
+        assert_eq!(parse_color("#fff"), Ok([1.0; 4]));
+        assert_eq!(parse_color("#ff0000"), Ok([1.0, 0.0, 0.0, 1.0]));
+        assert_eq!(parse_color("#00FF0033"), Ok([0.0, 1.0, 0.0, 0.2]));
+        for text in ["ff0000", "#ff00", "#gg0000", "#ff00000", "#ff00ä"] {
+            assert!(parse_color(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn stop_positions_default_like_css() {
+        let gradient = Gradient::parse("#fff, #f80 0.3, #888, #000").unwrap();
+        let spread = positions(&gradient);
+        assert_eq!(spread[..2], [0.0, 0.3]);
+        assert!((spread[2] - 0.65).abs() < 1e-6);
+        assert_eq!(spread[3], 1.0);
+        assert_eq!(
+            positions(&Gradient::parse("#fff,#000").unwrap()),
+            [0.0, 1.0]
+        );
+        assert_eq!(positions(&Gradient::parse("#fff 0.4").unwrap()), [0.4]);
+        assert_eq!(
+            Gradient::parse("#ffffff80 0.2, #000 0.8").unwrap().stops()[0],
+            ColorStop {
+                position: 0.2,
+                color: [1.0, 1.0, 1.0, 128.0 / 255.0]
+            }
+        );
+    }
+
+    #[test]
+    fn malformed_or_out_of_order_stops_are_rejected() {
+        for text in [
+            "",
+            "#fff,,#000",
+            "#fff 0.5 0.7",
+            "#fff half",
+            "#fff 0.6, #000 0.4",
+            "#fff, #888 1.5, #000",
+            "#fff NaN, #000",
+            "#fff, #000 inf",
+            "white, #000",
+            "#0, #1, #2, #3, #4, #5, #6, #7, #8",
+        ] {
+            assert!(Gradient::parse(text).is_err(), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn samples_interpolate_between_stops() {
+        let gradient = Gradient::parse("#000 0.25, #fff 0.75, #f00 0.75").unwrap();
+        assert_eq!(gradient.sample(0.0), [0.0, 0.0, 0.0, 1.0]);
+        assert_eq!(gradient.sample(0.5), [0.5, 0.5, 0.5, 1.0]);
+        // Stops at the same position make a hard step
+        assert_eq!(gradient.sample(0.75), [1.0, 1.0, 1.0, 1.0]);
+        assert_eq!(gradient.sample(0.76), [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(gradient.sample(1.0), [1.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn coverage_picks_the_color_and_alpha_is_premultiplied() {
+        let gradient = Gradient::parse("#ffffff00, #ff0000").unwrap();
+        let input = Image::from_pixels(
+            3,
+            1,
+            vec![[1.0, 1.0, 1.0, 1.0], [0.25, 0.25, 0.25, 0.5], [0.0; 4]],
+        );
+        let light = colorize(&input, EdgePolarity::Light, &gradient);
+        assert_eq!(light.pixels()[0], [1.0, 0.0, 0.0, 1.0]);
+        let half = light.pixels()[1];
+        let expected = [0.25, 0.125, 0.125, 0.25];
+        assert!(
+            half.iter().zip(expected).all(|(a, b)| (a - b).abs() < 1e-6),
+            "{:?}",
+            half
+        );
+        assert_eq!(light.pixels()[2], [0.0; 4]);
+        let dark = colorize(&input, EdgePolarity::Dark, &gradient);
+        assert_eq!(dark.pixels()[0], [0.0; 4]);
+    }
+}
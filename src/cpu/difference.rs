@@ -0,0 +1,12 @@
+use super::Image;
+
+/// Mirrors `SignedDifferencePixelShader.hlsl`: `bias + gain * (a - b)`.
+/// Unlike the subtract blend this doesn't clamp, so with float precision the
+/// negative half of a DoG response survives, and with a bias of 0.5 it can be
+/// drawn around mid gray.
+pub fn signed_difference(a: &Image, b: &Image, gain: f32, bias: f32) -> Image {
+    a.zip_map(b, |a, b| {
+        let difference = |c: usize| bias + gain * (a[c] - b[c]);
+        [difference(0), difference(1), difference(2), 1.0]
+    })
+}
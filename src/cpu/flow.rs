@@ -0,0 +1,290 @@
+//! Flow-based Difference of Gaussians (Kang et al. 2007).
+//!
+//! The structure tensor `[E F; F G]` of the image luminance is stored as
+//! `[E, 0.5 + 0.5 * F, G, 1]` so it fits in an unorm buffer. Blurring it with
+//! the regular Gaussian blur gives the smoothed edge tangent flow, whose minor
+//! eigenvector runs along edges and whose major eigenvector crosses them.
+
+use super::Image;
+
+/// Sobel gradients of the luminance, packed as a structure tensor. Gradients
+/// are scaled by 1/4 so that every component stays within [0, 1].
+pub fn structure_tensor(input: &Image) -> Image {
+    let luma = |x: i32, y: i32| super::luma(&input.pixel(x, y));
+    let width = input.width() as i32;
+    let mut output = Image::new(input.width(), input.height());
+    for (index, pixel) in output.pixels_mut().iter_mut().enumerate() {
+        let x = index as i32 % width;
+        let y = index as i32 / width;
+        let gx = (luma(x + 1, y - 1) + 2.0 * luma(x + 1, y) + luma(x + 1, y + 1)
+            - luma(x - 1, y - 1)
+            - 2.0 * luma(x - 1, y)
+            - luma(x - 1, y + 1))
+            / 4.0;
+        let gy = (luma(x - 1, y + 1) + 2.0 * luma(x, y + 1) + luma(x + 1, y + 1)
+            - luma(x - 1, y - 1)
+            - 2.0 * luma(x, y - 1)
+            - luma(x + 1, y - 1))
+            / 4.0;
+        *pixel = [gx * gx, 0.5 + 0.5 * gx * gy, gy * gy, 1.0];
+    }
+    output
+}
+
+/// Unpacks a premultiplied tensor pixel into `[E, F, G]`.
+pub fn unpack_tensor(pixel: [f32; 4]) -> [f32; 3] {
+    let alpha = pixel[3];
+    if alpha <= 0.0 {
+        return [0.0; 3];
+    }
+    [
+        pixel[0] / alpha,
+        2.0 * pixel[1] / alpha - 1.0,
+        pixel[2] / alpha,
+    ]
+}
+
+/// How [`flow_tangent`] turns the tangent orientation into a color.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TangentEncoding {
+    /// Fully saturated hue, going once around the color wheel over 180°
+    #[default]
+    Hue,
+    /// `0.5 + 0.5 * t` in red and green, with the tangent flipped so that it
+    /// never points up
+    Vector,
+}
+
+/// Major and minor eigenvalues of a structure tensor.
+pub fn eigenvalues(tensor: [f32; 3]) -> (f32, f32) {
+    let [e, f, g] = tensor;
+    let root = ((e - g) * (e - g) + 4.0 * f * f).sqrt();
+    (0.5 * (e + g + root), 0.5 * (e + g - root))
+}
+
+/// Unit tangent and gradient directions of a structure tensor. Where the
+/// tensor is isotropic the gradient defaults to the x axis.
+pub fn flow_directions(tensor: [f32; 3]) -> ([f32; 2], [f32; 2]) {
+    let [e, f, g] = tensor;
+    let (major, _) = eigenvalues(tensor);
+    let gradient = if e >= g {
+        [major - g, f]
+    } else {
+        [f, major - e]
+    };
+    let length = (gradient[0] * gradient[0] + gradient[1] * gradient[1]).sqrt();
+    let gradient = if length > 1e-8 {
+        [gradient[0] / length, gradient[1] / length]
+    } else {
+        [1.0, 0.0]
+    };
+    ([-gradient[1], gradient[0]], gradient)
+}
+
+/// Visualizes the tangent direction of a blurred structure tensor.
+pub fn flow_tangent(tensor: &Image, encoding: TangentEncoding) -> Image {
+    tensor.map(|pixel| {
+        let (tangent, _) = flow_directions(unpack_tensor(pixel));
+        match encoding {
+            TangentEncoding::Hue => {
+                // Tangents have no orientation, so 180° apart is the same hue
+                let hue = (tangent[1].atan2(tangent[0]) / std::f32::consts::PI).rem_euclid(1.0);
+                let channel = |offset: f32| {
+                    ((hue * 6.0 + offset).rem_euclid(6.0) - 3.0)
+                        .abs()
+                        .clamp(1.0, 2.0)
+                        - 1.0
+                };
+                [channel(0.0), channel(4.0), channel(2.0), 1.0]
+            }
+            TangentEncoding::Vector => {
+                let tangent = if tangent[1] < 0.0 || (tangent[1] == 0.0 && tangent[0] < 0.0) {
+                    [-tangent[0], -tangent[1]]
+                } else {
+                    tangent
+                };
+                [0.5 + 0.5 * tangent[0], 0.5 + 0.5 * tangent[1], 0.0, 1.0]
+            }
+        }
+    })
+}
+
+/// How strongly the structure tensor is oriented, `(λ1 - λ2) / (λ1 + λ2)`,
+/// from 0 in flat or isotropic areas to 1 along clean edges.
+pub fn coherence(tensor: &Image) -> Image {
+    tensor.map(|pixel| {
+        let (major, minor) = eigenvalues(unpack_tensor(pixel));
+        let sum = major + minor;
+        let coherence = if sum > 1e-8 {
+            (major - minor) / sum
+        } else {
+            0.0
+        };
+        [coherence, coherence, coherence, 1.0]
+    })
+}
+
+/// Difference of Gaussians sampled along the gradient of the flow field, so
+/// that each pixel only compares itself with its neighbours across the edge.
+/// The response keeps its sign, which only 8-bit buffers clamp away.
+pub fn flow_dog(input: &Image, tensor: &Image, sigma_1: f32, sigma_2: f32) -> Image {
+    let radius = (sigma_2.max(sigma_1) * 3.0).ceil() as i32;
+    let width = input.width() as i32;
+    let mut output = Image::new(input.width(), input.height());
+    for (index, pixel) in output.pixels_mut().iter_mut().enumerate() {
+        let x = index as i32 % width;
+        let y = index as i32 / width;
+        let (_, gradient) = flow_directions(unpack_tensor(tensor.pixel(x, y)));
+        let mut narrow = [0.0f32; 3];
+        let mut wide = [0.0f32; 3];
+        let mut narrow_weight = 0.0;
+        let mut wide_weight = 0.0;
+        for t in -radius..=radius {
+            let t = t as f32;
+            let sample = input.sample(x as f32 + gradient[0] * t, y as f32 + gradient[1] * t);
+            let weight_1 = gaussian(t, sigma_1);
+            let weight_2 = gaussian(t, sigma_2);
+            for c in 0..3 {
+                narrow[c] += sample[c] * weight_1;
+                wide[c] += sample[c] * weight_2;
+            }
+            narrow_weight += weight_1;
+            wide_weight += weight_2;
+        }
+        for c in 0..3 {
+            pixel[c] = narrow[c] / narrow_weight - wide[c] / wide_weight;
+        }
+        pixel[3] = 1.0;
+    }
+    output
+}
+
+/// Gaussian blur along the streamline through each pixel, following the
+/// tangent of the flow field one pixel at a time in both directions.
+pub fn flow_blur(input: &Image, tensor: &Image, standard_deviation: f32) -> Image {
+    let steps = (standard_deviation * 3.0).ceil() as i32;
+    let width = input.width() as i32;
+    let mut output = Image::new(input.width(), input.height());
+    for (index, pixel) in output.pixels_mut().iter_mut().enumerate() {
+        let x = index as i32 % width;
+        let y = index as i32 / width;
+        let mut sum = input.pixel(x, y);
+        let mut weight_sum = 1.0;
+        let (tangent, _) = flow_directions(unpack_tensor(tensor.pixel(x, y)));
+        for sign in [1.0, -1.0] {
+            let mut position = [x as f32, y as f32];
+            let mut direction = [tangent[0] * sign, tangent[1] * sign];
+            for step in 1..=steps {
+                let (mut next, _) =
+                    flow_directions(unpack_tensor(tensor.sample(position[0], position[1])));
+                // Tangents have no orientation, keep following the same way
+                if next[0] * direction[0] + next[1] * direction[1] < 0.0 {
+                    next = [-next[0], -next[1]];
+                }
+                direction = next;
+                position = [position[0] + direction[0], position[1] + direction[1]];
+                let weight = gaussian(step as f32, standard_deviation);
+                let sample = input.sample(position[0], position[1]);
+                for c in 0..4 {
+                    sum[c] += sample[c] * weight;
+                }
+                weight_sum += weight;
+            }
+        }
+        *pixel = sum.map(|value| value / weight_sum);
+    }
+    output
+}
+
+fn gaussian(t: f32, standard_deviation: f32) -> f32 {
+    if standard_deviation <= 0.0 {
+        return if t == 0.0 { 1.0 } else { 0.0 };
+    }
+    (-(t * t) / (2.0 * standard_deviation * standard_deviation)).exp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(width: u32, height: u32, value: impl Fn(u32, u32) -> f32) -> Image {
+        let pixels = (0..width * height)
+            .map(|index| {
+                let value = value(index % width, index / width);
+                [value, value, value, 1.0]
+            })
+            .collect();
+        Image::from_pixels(width, height, pixels)
+    }
+
+    fn tangent_at(input: &Image, x: i32, y: i32) -> [f32; 2] {
+        let tensor = structure_tensor(input);
+        flow_directions(unpack_tensor(tensor.pixel(x, y))).0
+    }
+
+    fn assert_parallel(a: [f32; 2], b: [f32; 2]) {
+        let cross = a[0] * b[1] - a[1] * b[0];
+        assert!(cross.abs() < 1e-5, "{:?} is not parallel to {:?}", a, b);
+    }
+
+    #[test]
+    fn tangents_run_along_straight_edges() {
+        let vertical = image(9, 9, |x, _| if x > 4 { 1.0 } else { 0.0 });
+        assert_parallel(tangent_at(&vertical, 4, 4), [0.0, 1.0]);
+        let horizontal = image(9, 9, |_, y| if y > 4 { 1.0 } else { 0.0 });
+        assert_parallel(tangent_at(&horizontal, 4, 4), [1.0, 0.0]);
+        let diagonal = image(9, 9, |x, y| if x > y { 1.0 } else { 0.0 });
+        assert_parallel(tangent_at(&diagonal, 4, 4), [1.0, 1.0]);
+
+        let tensor = structure_tensor(&vertical);
+        let (major, minor) = eigenvalues(unpack_tensor(tensor.pixel(4, 4)));
+        assert!(major > 0.5 && minor.abs() < 1e-6);
+        assert_eq!(coherence(&tensor).pixel(4, 4)[0], 1.0);
+        let vector = flow_tangent(&tensor, TangentEncoding::Vector).pixel(4, 4);
+        assert_eq!(vector, [0.5, 1.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn flat_areas_have_no_orientation() {
+        let tensor = structure_tensor(&image(5, 5, |_, _| 0.5));
+        assert_eq!(coherence(&tensor).pixel(2, 2)[0], 0.0);
+        let (tangent, gradient) = flow_directions(unpack_tensor(tensor.pixel(2, 2)));
+        assert_eq!((tangent, gradient), ([0.0, 1.0], [1.0, 0.0]));
+    }
+
+    #[test]
+    fn flow_blur_smooths_along_the_flow_only() {
+        // Vertical flow everywhere
+        let tensor = Image::from_pixels(9, 31, vec![[1.0, 0.5, 0.0, 1.0]; 9 * 31]);
+        let stripe = |position: u32| (position % 2) as f32;
+
+        let across = image(9, 31, |x, _| stripe(x));
+        let output = flow_blur(&across, &tensor, 2.0);
+        for x in 0..9 {
+            let pixel = output.pixel(x, 15);
+            assert!((pixel[0] - stripe(x as u32)).abs() < 1e-5, "{:?}", pixel);
+        }
+
+        let along = image(9, 31, |_, y| stripe(y));
+        let output = flow_blur(&along, &tensor, 2.0);
+        for x in 0..9 {
+            let pixel = output.pixel(x, 15);
+            assert!((pixel[0] - 0.5).abs() < 0.05, "{:?}", pixel);
+            assert!((pixel[3] - 1.0).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn flow_dog_compares_pixels_across_the_flow() {
+        let tensor = Image::from_pixels(15, 15, vec![[1.0, 0.5, 0.0, 1.0]; 15 * 15]);
+        let edge = image(15, 15, |x, _| if x > 7 { 1.0 } else { 0.0 });
+        let output = flow_dog(&edge, &tensor, 1.0, 1.6);
+        // Bright next to the edge on the bright side, dark on the dark side
+        assert!(output.pixel(8, 7)[0] > 0.05);
+        assert!(output.pixel(7, 7)[0] < -0.05);
+        // The same edge running along the flow gives no response
+        let edge = image(15, 15, |_, y| if y > 7 { 1.0 } else { 0.0 });
+        let output = flow_dog(&edge, &tensor, 1.0, 1.6);
+        assert!(output.pixel(7, 7)[0].abs() < 1e-5);
+    }
+}
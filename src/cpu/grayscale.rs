@@ -0,0 +1,9 @@
+use super::{luma, Image};
+
+/// Equivalent of `CLSID_D2D1Grayscale`.
+pub fn grayscale(input: &Image) -> Image {
+    input.map(|pixel| {
+        let luma = luma(&pixel);
+        [luma, luma, luma, pixel[3]]
+    })
+}
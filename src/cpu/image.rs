@@ -0,0 +1,96 @@
+/// A premultiplied RGBA image with 32-bit float channels.
+///
+/// This is the CPU backend's stand-in for an `ID2D1Image`. Pixels are stored
+/// row-major as `[r, g, b, a]`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Image {
+    width: u32,
+    height: u32,
+    pixels: Vec<[f32; 4]>,
+}
+
+impl Image {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![[0.0; 4]; (width * height) as usize],
+        }
+    }
+
+    pub fn from_pixels(width: u32, height: u32, pixels: Vec<[f32; 4]>) -> Self {
+        assert_eq!(pixels.len(), (width * height) as usize);
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[[f32; 4]] {
+        &self.pixels
+    }
+
+    pub fn pixels_mut(&mut self) -> &mut [[f32; 4]] {
+        &mut self.pixels
+    }
+
+    /// The pixel at `x`, `y`, or transparent black outside the image.
+    pub fn pixel(&self, x: i32, y: i32) -> [f32; 4] {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return [0.0; 4];
+        }
+        self.pixels[(y as u32 * self.width + x as u32) as usize]
+    }
+
+    /// Bilinearly samples the image at a position where integer coordinates
+    /// are pixel centers, treating everything outside as transparent black.
+    pub fn sample(&self, x: f32, y: f32) -> [f32; 4] {
+        let x0 = x.floor();
+        let y0 = y.floor();
+        let fx = x - x0;
+        let fy = y - y0;
+        let (x0, y0) = (x0 as i32, y0 as i32);
+        let top_left = self.pixel(x0, y0);
+        let top_right = self.pixel(x0 + 1, y0);
+        let bottom_left = self.pixel(x0, y0 + 1);
+        let bottom_right = self.pixel(x0 + 1, y0 + 1);
+        let mut result = [0.0; 4];
+        for c in 0..4 {
+            let top = top_left[c] + (top_right[c] - top_left[c]) * fx;
+            let bottom = bottom_left[c] + (bottom_right[c] - bottom_left[c]) * fx;
+            result[c] = top + (bottom - top) * fy;
+        }
+        result
+    }
+
+    pub fn map<F: Fn([f32; 4]) -> [f32; 4]>(&self, f: F) -> Self {
+        Self {
+            width: self.width,
+            height: self.height,
+            pixels: self.pixels.iter().map(|pixel| f(*pixel)).collect(),
+        }
+    }
+
+    pub fn zip_map<F: Fn([f32; 4], [f32; 4]) -> [f32; 4]>(&self, other: &Image, f: F) -> Self {
+        assert_eq!((self.width, self.height), (other.width, other.height));
+        Self {
+            width: self.width,
+            height: self.height,
+            pixels: self
+                .pixels
+                .iter()
+                .zip(&other.pixels)
+                .map(|(a, b)| f(*a, *b))
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,192 @@
+//! Anisotropic Kuwahara filter (Kyprianidis et al. 2009), with the
+//! polynomial sector weights of Kyprianidis et al. 2010.
+//!
+//! Each pixel is replaced by a blend of the mean colors of eight overlapping
+//! sectors of an ellipse around it, favouring the sectors whose colors vary
+//! the least. The ellipse follows the edge tangent flow of a blurred
+//! structure tensor and stretches along clearly oriented edges, which turns
+//! the image into painterly strokes while keeping its edges sharp.
+
+use super::{
+    flow::{eigenvalues, flow_directions, unpack_tensor},
+    Image,
+};
+
+/// Number of sectors the ellipse is split into.
+const SECTORS: usize = 8;
+
+/// Mirrors `AnisotropicKuwaharaPixelShader.hlsl`. `radius` is the radius of
+/// the filter in flat areas, `sharpness` how strongly the least varying
+/// sectors win and `alpha` how little anisotropy it takes to stretch the
+/// ellipse, with smaller values stretching it more.
+pub fn anisotropic_kuwahara(
+    input: &Image,
+    tensor: &Image,
+    radius: f32,
+    sharpness: f32,
+    alpha: f32,
+) -> Image {
+    if radius <= 0.0 {
+        return input.clone();
+    }
+    // Overlap of neighbouring sectors
+    let zeta = 2.0 / radius;
+    let zero_crossing = 3.0 * std::f32::consts::PI / 8.0;
+    let eta = (zeta + zero_crossing.cos()) / zero_crossing.sin().powi(2);
+    let width = input.width() as i32;
+    let mut output = Image::new(input.width(), input.height());
+    for (index, pixel) in output.pixels_mut().iter_mut().enumerate() {
+        let x = index as i32 % width;
+        let y = index as i32 / width;
+        let tensor = unpack_tensor(tensor.pixel(x, y));
+        let (major, minor) = eigenvalues(tensor);
+        let anisotropy = if major + minor > 1e-8 {
+            (major - minor) / (major + minor)
+        } else {
+            0.0
+        };
+        let (tangent, _) = flow_directions(tensor);
+
+        // Semi-axes along and across the tangent
+        let a = radius * ((alpha + anisotropy) / alpha).clamp(0.1, 2.0);
+        let b = radius * (alpha / (alpha + anisotropy)).clamp(0.1, 2.0);
+        let [cos, sin] = tangent;
+        let max_x = (a * a * cos * cos + b * b * sin * sin).sqrt() as i32;
+        let max_y = (a * a * sin * sin + b * b * cos * cos).sqrt() as i32;
+
+        let mut means = [[0.0f32; 4]; SECTORS];
+        let mut squares = [[0.0f32; 3]; SECTORS];
+        let mut weights = [0.0f32; SECTORS];
+        for j in -max_y..=max_y {
+            for i in -max_x..=max_x {
+                // The offset in the frame of the ellipse, which maps it onto
+                // a disk with a radius of 0.5
+                let (i_f, j_f) = (i as f32, j as f32);
+                let v = [
+                    (cos * i_f + sin * j_f) * 0.5 / a,
+                    (-sin * i_f + cos * j_f) * 0.5 / b,
+                ];
+                let length_squared = v[0] * v[0] + v[1] * v[1];
+                if length_squared > 0.25 {
+                    continue;
+                }
+                let sector_weights = polynomial_weights(v, zeta, eta);
+                let sum: f32 = sector_weights.iter().sum();
+                let gaussian = (-3.125 * length_squared).exp() / sum;
+                let sample = input.pixel(x + i, y + j);
+                for k in 0..SECTORS {
+                    let weight = sector_weights[k] * gaussian;
+                    for c in 0..4 {
+                        means[k][c] += sample[c] * weight;
+                    }
+                    for c in 0..3 {
+                        squares[k][c] += sample[c] * sample[c] * weight;
+                    }
+                    weights[k] += weight;
+                }
+            }
+        }
+
+        let mut sum = [0.0f32; 4];
+        let mut weight_sum = 0.0;
+        for k in 0..SECTORS {
+            let mean = means[k].map(|value| value / weights[k]);
+            let variance: f32 = (0..3)
+                .map(|c| (squares[k][c] / weights[k] - mean[c] * mean[c]).abs())
+                .sum();
+            let weight = 1.0 / (1.0 + (255.0 * variance).powf(0.5 * sharpness));
+            for c in 0..4 {
+                sum[c] += mean[c] * weight;
+            }
+            weight_sum += weight;
+        }
+        *pixel = sum.map(|value| value / weight_sum);
+    }
+    output
+}
+
+/// How much an offset inside the unit ellipse belongs to each sector. The
+/// sectors are bounded by parabolas rather than straight lines, so that they
+/// overlap smoothly.
+fn polynomial_weights(v: [f32; 2], zeta: f32, eta: f32) -> [f32; SECTORS] {
+    let mut weights = [0.0; SECTORS];
+    let rotated = [
+        std::f32::consts::FRAC_1_SQRT_2 * (v[0] - v[1]),
+        std::f32::consts::FRAC_1_SQRT_2 * (v[0] + v[1]),
+    ];
+    // Even sectors come from the offset itself, odd ones from the offset
+    // turned by 45°
+    for (first, v) in [(0, v), (1, rotated)] {
+        let vxx = zeta - eta * v[0] * v[0];
+        let vyy = zeta - eta * v[1] * v[1];
+        let squared = |value: f32| value.max(0.0).powi(2);
+        weights[first] = squared(v[1] + vxx);
+        weights[first + 2] = squared(-v[0] + vyy);
+        weights[first + 4] = squared(-v[1] + vxx);
+        weights[first + 6] = squared(v[0] + vyy);
+    }
+    weights
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpu::{blur::gaussian_blur, flow::structure_tensor};
+
+    const SIZE: u32 = 48;
+
+    /// A vertical step from 0.2 to 0.8 in the middle, with up to ±0.05 of
+    /// fixed pseudo-random noise.
+    fn noisy_step() -> Image {
+        let pixels = (0..SIZE * SIZE)
+            .map(|index| {
+                let (x, y) = (index % SIZE, index / SIZE);
+                let noise = ((x * 7919 + y * 104729) % 61) as f32 / 60.0 - 0.5;
+                let value = if x < SIZE / 2 { 0.2 } else { 0.8 } + 0.1 * noise;
+                [value, value, value, 1.0]
+            })
+            .collect();
+        Image::from_pixels(SIZE, SIZE, pixels)
+    }
+
+    /// Mean and standard deviation of the red channel over a block of
+    /// columns, away from the top and bottom border.
+    fn statistics(image: &Image, columns: std::ops::Range<i32>) -> (f32, f32) {
+        let values: Vec<f32> = (12..36)
+            .flat_map(|y| columns.clone().map(move |x| (x, y)))
+            .map(|(x, y)| image.pixel(x, y)[0])
+            .collect();
+        let mean = values.iter().sum::<f32>() / values.len() as f32;
+        let variance = values
+            .iter()
+            .map(|value| (value - mean).powi(2))
+            .sum::<f32>()
+            / values.len() as f32;
+        (mean, variance.sqrt())
+    }
+
+    fn filter(input: &Image) -> Image {
+        let tensor = gaussian_blur(&structure_tensor(input), 2.0);
+        anisotropic_kuwahara(input, &tensor, 6.0, 8.0, 1.0)
+    }
+
+    #[test]
+    fn step_edges_survive() {
+        let output = filter(&noisy_step());
+        let (dark, _) = statistics(&output, 23..24);
+        let (light, _) = statistics(&output, 24..25);
+        assert!(dark < 0.25 && light > 0.75, "{} {}", dark, light);
+    }
+
+    #[test]
+    fn flat_noise_is_reduced() {
+        let input = noisy_step();
+        let output = filter(&input);
+        for columns in [10..18, 30..38] {
+            let (mean, noise) = statistics(&input, columns.clone());
+            let (smoothed_mean, smoothed_noise) = statistics(&output, columns);
+            assert!((smoothed_mean - mean).abs() < 0.01);
+            assert!(smoothed_noise < 0.5 * noise, "{} {}", noise, smoothed_noise);
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use super::{luma, Image};
+
+/// How [`luminance`] weighs the color channels.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LuminanceModel {
+    /// Rec. 601 luma of the gamma-encoded channels, the same weights as
+    /// `CLSID_D2D1Grayscale`
+    #[default]
+    Rec601,
+    /// Rec. 709 luma of the gamma-encoded channels
+    Rec709,
+    /// CIELAB lightness L* of the sRGB color, scaled from [0, 100] to [0, 1]
+    Lab,
+}
+
+/// Mirrors `LuminancePixelShader.hlsl`: replaces the color channels with the
+/// luminance of the un-premultiplied color, keeping alpha.
+pub fn luminance(input: &Image, model: LuminanceModel) -> Image {
+    input.map(|pixel| {
+        let alpha = pixel[3];
+        if alpha <= 0.0 {
+            return [0.0; 4];
+        }
+        let [r, g, b] = [pixel[0] / alpha, pixel[1] / alpha, pixel[2] / alpha];
+        let value = match model {
+            LuminanceModel::Rec601 => luma(&[r, g, b]),
+            LuminanceModel::Rec709 => 0.2126 * r + 0.7152 * g + 0.0722 * b,
+            LuminanceModel::Lab => {
+                let y = 0.2126 * srgb_to_linear(r)
+                    + 0.7152 * srgb_to_linear(g)
+                    + 0.0722 * srgb_to_linear(b);
+                lightness(y)
+            }
+        };
+        let value = value * alpha;
+        [value, value, value, alpha]
+    })
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// CIELAB L* of a relative luminance, divided by 100.
+fn lightness(y: f32) -> f32 {
+    const DELTA: f32 = 6.0 / 29.0;
+    let f = if y > DELTA * DELTA * DELTA {
+        y.cbrt()
+    } else {
+        y / (3.0 * DELTA * DELTA) + 4.0 / 29.0
+    };
+    (116.0 * f - 16.0) / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn luminance_of(color: [f32; 4], model: LuminanceModel) -> [f32; 4] {
+        luminance(&Image::from_pixels(1, 1, vec![color]), model).pixels()[0]
+    }
+
+    #[test]
+    fn primaries_get_the_model_weights() {
+        let primaries = [
+            [1.0, 0.0, 0.0, 1.0],
+            [0.0, 1.0, 0.0, 1.0],
+            [0.0, 0.0, 1.0, 1.0],
+        ];
+        for (model, weights) in [
+            (LuminanceModel::Rec601, [0.299, 0.587, 0.114]),
+            (LuminanceModel::Rec709, [0.2126, 0.7152, 0.0722]),
+            // CIELAB L* of the sRGB primaries, 53.24, 87.73 and 32.30
+            (LuminanceModel::Lab, [0.5324, 0.8773, 0.3230]),
+        ] {
+            for (color, weight) in primaries.into_iter().zip(weights) {
+                let value = luminance_of(color, model)[0];
+                assert!(
+                    (value - weight).abs() < 1e-4,
+                    "{:?} {:?}: {}",
+                    model,
+                    color,
+                    value
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn lab_lightness_is_perceptual() {
+        assert_eq!(
+            luminance_of([0.0, 0.0, 0.0, 1.0], LuminanceModel::Lab)[0],
+            0.0
+        );
+        assert!((luminance_of([1.0; 4], LuminanceModel::Lab)[0] - 1.0).abs() < 1e-5);
+        // sRGB mid gray is L* 53.39, and dark colors use the linear segment
+        let gray = luminance_of([0.5, 0.5, 0.5, 1.0], LuminanceModel::Lab)[0];
+        assert!((gray - 0.5339).abs() < 1e-4, "{}", gray);
+        let dark = luminance_of([0.02, 0.02, 0.02, 1.0], LuminanceModel::Lab)[0];
+        assert!((dark - 0.01400).abs() < 1e-4, "{}", dark);
+    }
+
+    #[test]
+    fn alpha_is_kept_and_colors_are_unpremultiplied() {
+        let output = luminance_of([0.5, 0.0, 0.0, 0.5], LuminanceModel::Lab);
+        assert!((output[0] - 0.5 * 0.5324).abs() < 1e-4);
+        assert_eq!(output[3], 0.5);
+        assert_eq!(luminance_of([0.0; 4], LuminanceModel::Rec709), [0.0; 4]);
+    }
+}
@@ -0,0 +1,344 @@
+//! Portable CPU implementation of the Difference of Gaussians effect graph.
+//!
+//! Each node mirrors the D2D effect of the same name. By default D2D stores
+//! intermediate images at the precision of the render target
+//! (`B8G8R8A8_UNORM`), so `Context` rounds every node's output to 8 bits to
+//! produce the same clamping and banding. With [`Precision::Float16`] or
+//! [`Precision::Float32`] it keeps the same precision as D2D's float buffers
+//! instead, which also keeps negative values.
+//!
+//! At the default precision each blurred intermediate lies within 0.5/255 of
+//! the exact result, so the subtracted DoG response is within 1/255 per
+//! channel of it, or 2/255 within a blur radius of the transparent image
+//! border (see `tests::dog_response_is_within_one_level_of_exact_math`). D2D
+//! rounds its 8-bit intermediates to the nearest level as well, which puts
+//! the two backends at most 2/255 apart away from the border, unless D2D
+//! downsamples internally for large standard deviations, which this
+//! implementation does not do. Because the threshold is a hard step, a pixel
+//! of the final image can only differ when its DoG response is within that
+//! tolerance of the threshold. The flow-based nodes sample between pixels,
+//! where GPU texture filtering works with lower precision weights, so their
+//! output can drift a little further from D2D's.
+
+mod alpha;
+mod bilateral;
+mod blend;
+mod blur;
+mod colorize;
+mod difference;
+mod flow;
+mod grayscale;
+mod image;
+mod kuwahara;
+mod luminance;
+mod morphology;
+mod quantize;
+mod threshold;
+mod xdog;
+mod zero_crossing;
+
+use half::f16;
+
+pub use self::image::Image;
+pub use alpha::EdgePolarity;
+pub use blend::BlendMode;
+pub use colorize::{parse_color, ColorStop, Gradient, MAX_GRADIENT_STOPS};
+pub use flow::TangentEncoding;
+pub use luminance::LuminanceModel;
+pub use morphology::{MorphologyOperation, StructuringElement};
+pub use threshold::{select_threshold, ThresholdMode, ThresholdSelection};
+
+/// Luminance weights shared with `CLSID_D2D1Grayscale`.
+const LUMA: [f32; 3] = [0.299, 0.587, 0.114];
+
+/// The luminance of the first three channels of `pixel`, weighted by
+/// [`LUMA`].
+fn luma(pixel: &[f32]) -> f32 {
+    LUMA[0] * pixel[0] + LUMA[1] * pixel[1] + LUMA[2] * pixel[2]
+}
+
+/// Precision intermediate images are stored at, the equivalent of
+/// `D2D1_BUFFER_PRECISION`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Precision {
+    /// 8 bits per channel, clamped to [0, 1]. This is D2D's default.
+    #[default]
+    Unorm8,
+    /// 16-bit float per channel
+    Float16,
+    /// 32-bit float per channel
+    Float32,
+}
+
+#[derive(Default)]
+pub struct Context {
+    precision: Precision,
+}
+
+impl Context {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_precision(precision: Precision) -> Self {
+        Self { precision }
+    }
+
+    pub fn gaussian_blur(&self, input: &Image, standard_deviation: f32) -> Image {
+        self.store(blur::gaussian_blur(input, standard_deviation))
+    }
+
+    pub fn bilateral(&self, input: &Image, spatial_sigma: f32, range_sigma: f32) -> Image {
+        self.store(bilateral::bilateral(input, spatial_sigma, range_sigma))
+    }
+
+    pub fn subtract(&self, input_1: &Image, input_2: &Image) -> Image {
+        self.blend(input_1, input_2, BlendMode::Subtract)
+    }
+
+    pub fn blend(&self, destination: &Image, source: &Image, mode: BlendMode) -> Image {
+        self.store(blend::blend(destination, source, mode))
+    }
+
+    pub fn cross_fade(&self, destination: &Image, source: &Image, weight: f32) -> Image {
+        self.store(blend::cross_fade(destination, source, weight))
+    }
+
+    pub fn signed_difference(&self, a: &Image, b: &Image, gain: f32, bias: f32) -> Image {
+        self.store(difference::signed_difference(a, b, gain, bias))
+    }
+
+    pub fn threshold(&self, input: &Image, threshold: f32, mode: ThresholdMode) -> Image {
+        self.store(threshold::threshold(input, threshold, mode))
+    }
+
+    /// Two level threshold that keeps weak responses connected to strong
+    /// ones. This looks at the whole image, so the D2D backend runs it on
+    /// the CPU too.
+    pub fn hysteresis(&self, input: &Image, low: f32, high: f32) -> Image {
+        self.store(threshold::hysteresis(input, low, high))
+    }
+
+    /// Threshold picked from the histogram of the input, see
+    /// [`select_threshold`]. Like `hysteresis` it always runs on the CPU.
+    /// Also returns the threshold it picked.
+    pub fn auto_threshold(
+        &self,
+        input: &Image,
+        selection: ThresholdSelection,
+        mode: ThresholdMode,
+        low_ratio: Option<f32>,
+    ) -> (Image, f32) {
+        let (output, threshold) = threshold::auto_threshold(input, selection, mode, low_ratio);
+        (self.store(output), threshold)
+    }
+
+    pub fn xdog(
+        &self,
+        narrow: &Image,
+        wide: &Image,
+        sharpness: f32,
+        epsilon: f32,
+        phi: f32,
+    ) -> Image {
+        self.store(xdog::xdog(narrow, wide, sharpness, epsilon, phi))
+    }
+
+    /// Thin edges where a signed response crosses zero. Needs float precision
+    /// upstream, 8-bit buffers have already clamped the negative half away.
+    pub fn zero_crossing(&self, input: &Image, min_slope: f32) -> Image {
+        self.store(zero_crossing::zero_crossing(input, min_slope))
+    }
+
+    /// Structure tensor of the input's luminance, packed into an image.
+    pub fn structure_tensor(&self, input: &Image) -> Image {
+        self.store(flow::structure_tensor(input))
+    }
+
+    pub fn flow_dog(&self, input: &Image, tensor: &Image, sigma_1: f32, sigma_2: f32) -> Image {
+        self.store(flow::flow_dog(input, tensor, sigma_1, sigma_2))
+    }
+
+    pub fn flow_blur(&self, input: &Image, tensor: &Image, standard_deviation: f32) -> Image {
+        self.store(flow::flow_blur(input, tensor, standard_deviation))
+    }
+
+    pub fn flow_tangent(&self, tensor: &Image, encoding: TangentEncoding) -> Image {
+        self.store(flow::flow_tangent(tensor, encoding))
+    }
+
+    pub fn coherence(&self, tensor: &Image) -> Image {
+        self.store(flow::coherence(tensor))
+    }
+
+    pub fn anisotropic_kuwahara(
+        &self,
+        input: &Image,
+        tensor: &Image,
+        radius: f32,
+        sharpness: f32,
+        alpha: f32,
+    ) -> Image {
+        self.store(kuwahara::anisotropic_kuwahara(
+            input, tensor, radius, sharpness, alpha,
+        ))
+    }
+
+    pub fn grayscale(&self, input: &Image) -> Image {
+        self.store(grayscale::grayscale(input))
+    }
+
+    pub fn luminance(&self, input: &Image, model: LuminanceModel) -> Image {
+        self.store(luminance::luminance(input, model))
+    }
+
+    pub fn quantize(&self, input: &Image, levels: u32, sharpness: f32) -> Image {
+        self.store(quantize::quantize(input, levels, sharpness))
+    }
+
+    pub fn morphology(
+        &self,
+        input: &Image,
+        operation: MorphologyOperation,
+        shape: StructuringElement,
+        radius: u32,
+    ) -> Image {
+        self.store(morphology::morphology(input, operation, shape, radius))
+    }
+
+    /// Drops lines of fewer than `min_area` connected pixels. This looks at
+    /// the whole image, so the D2D backend runs it on the CPU too.
+    pub fn remove_small_components(
+        &self,
+        input: &Image,
+        min_area: u32,
+        polarity: EdgePolarity,
+    ) -> Image {
+        self.store(morphology::remove_small_components(
+            input, min_area, polarity,
+        ))
+    }
+
+    /// Thins lines to one pixel. Like `remove_small_components` it always
+    /// runs on the CPU.
+    pub fn skeletonize(&self, input: &Image, polarity: EdgePolarity) -> Image {
+        self.store(morphology::skeletonize(input, polarity))
+    }
+
+    pub fn unpremultiply(&self, input: &Image) -> Image {
+        self.store(alpha::unpremultiply(input))
+    }
+
+    pub fn alpha_mask(&self, input: &Image, mask: &Image) -> Image {
+        self.store(alpha::alpha_mask(input, mask))
+    }
+
+    pub fn edges_to_alpha(&self, input: &Image, polarity: EdgePolarity) -> Image {
+        self.store(alpha::edges_to_alpha(input, polarity))
+    }
+
+    pub fn colorize(&self, input: &Image, polarity: EdgePolarity, gradient: &Gradient) -> Image {
+        self.store(colorize::colorize(input, polarity, gradient))
+    }
+
+    fn store(&self, image: Image) -> Image {
+        match self.precision {
+            Precision::Unorm8 => {
+                image.map(|pixel| pixel.map(|value| crate::io::to_unorm8(value) as f32 / 255.0))
+            }
+            Precision::Float16 => {
+                image.map(|pixel| pixel.map(|value| f16::from_f32(value).to_f32()))
+            }
+            Precision::Float32 => image,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unorm8_rounds_to_the_nearest_step_and_clamps() {
+        let context = Context::new();
+        let input = Image::from_pixels(1, 1, vec![[0.5 / 255.0, 0.49 / 255.0, -0.25, 1.5]]);
+        let output = context.store(input);
+        assert_eq!(output.pixels()[0], [1.0 / 255.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn float_precisions_keep_negative_values() {
+        let input = Image::from_pixels(1, 1, vec![[-0.25, 0.003, 1.5, 1.0]]);
+        let output = Context::with_precision(Precision::Float16).store(input.clone());
+        assert_eq!(output.pixels()[0][0], -0.25);
+        assert!((output.pixels()[0][1] - 0.003).abs() < 1e-5);
+        let output = Context::with_precision(Precision::Float32).store(input.clone());
+        assert_eq!(output, input);
+    }
+
+    #[test]
+    fn dog_response_is_within_one_level_of_exact_math() {
+        // 8-bit values like a decoded image, with edges in every direction
+        let pixels = (0..48 * 48)
+            .map(|i| {
+                let (x, y) = (i % 48, i / 48);
+                let r = ((x * 37 + y * 11) % 256) as f32 / 255.0;
+                let g = if (x / 6 + y / 9) % 2 == 0 {
+                    1.0
+                } else {
+                    20.0 / 255.0
+                };
+                let b = ((x * y) % 256) as f32 / 255.0;
+                [r, g, b, 1.0]
+            })
+            .collect();
+        let input = Image::from_pixels(48, 48, pixels);
+        let unorm8 = Context::new();
+        let exact = Context::with_precision(Precision::Float32);
+        for (sigma_1, sigma_2) in [(1.0, 1.6), (1.3, 2.1), (3.0, 5.0)] {
+            let response = unorm8.subtract(
+                &unorm8.gaussian_blur(&input, sigma_1),
+                &unorm8.gaussian_blur(&input, sigma_2),
+            );
+            let expected = exact.subtract(
+                &exact.gaussian_blur(&input, sigma_1),
+                &exact.gaussian_blur(&input, sigma_2),
+            );
+            // Within a kernel radius of the border the blurs fade into
+            // transparency, and compositing the rounded alpha adds up to
+            // another level
+            let radius = (sigma_2 * 3.0).ceil() as u32;
+            let opaque = radius..48 - radius;
+            for (i, (pixel, expected)) in
+                response.pixels().iter().zip(expected.pixels()).enumerate()
+            {
+                let (x, y) = (i as u32 % 48, i as u32 / 48);
+                let tolerance = if opaque.contains(&x) && opaque.contains(&y) {
+                    1.0 / 255.0
+                } else {
+                    2.0 / 255.0
+                };
+                for (value, expected) in pixel.iter().zip(expected) {
+                    assert!((value - expected.clamp(0.0, 1.0)).abs() <= tolerance + 1e-5);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn small_differences_vanish_at_unorm8() {
+        // A 0.1/255 step survives the subtract at float precision only
+        let destination = Image::from_pixels(1, 1, vec![[0.5 + 0.1 / 255.0, 0.5, 0.5, 1.0]]);
+        let source = Image::from_pixels(1, 1, vec![[0.5, 0.5, 0.5, 1.0]]);
+        let unorm8 = Context::new();
+        let difference = unorm8.blend(
+            &unorm8.store(destination.clone()),
+            &unorm8.store(source.clone()),
+            BlendMode::Subtract,
+        );
+        assert_eq!(difference.pixels()[0][0], 0.0);
+        let float32 = Context::with_precision(Precision::Float32);
+        let difference = float32.blend(&destination, &source, BlendMode::Subtract);
+        assert!(difference.pixels()[0][0] > 0.0);
+    }
+}
@@ -0,0 +1,406 @@
+//! Morphological cleanup of edge images.
+//!
+//! [`morphology`] is a local filter like the others and mirrors
+//! `MorphologyPixelShader.hlsl`. Removing small components and thinning
+//! lines to a skeleton need to look at a whole line at once, which no pixel
+//! shader can, so the D2D backend runs them on the CPU as well.
+
+use super::{luma, EdgePolarity, Image};
+
+/// Whether [`morphology`] keeps the smallest or the largest value under the
+/// structuring element.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MorphologyOperation {
+    /// Minimum, which shrinks light regions and grows dark ones
+    Erode,
+    /// Maximum, which grows light regions and shrinks dark ones
+    Dilate,
+}
+
+/// The neighbourhood [`morphology`] looks at around each pixel.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StructuringElement {
+    /// Every pixel within `radius` along both axes
+    #[default]
+    Square,
+    /// Pixels whose center is within `radius + 0.5` of the center
+    Disk,
+    /// Pixels within `radius` steps along the axes
+    Diamond,
+}
+
+impl StructuringElement {
+    /// Whether the offset `dx`, `dy` lies within the element.
+    pub fn contains(self, dx: i32, dy: i32, radius: u32) -> bool {
+        let radius = radius as i32;
+        match self {
+            StructuringElement::Square => dx.abs() <= radius && dy.abs() <= radius,
+            StructuringElement::Disk => {
+                let limit = radius as f32 + 0.5;
+                ((dx * dx + dy * dy) as f32) <= limit * limit
+            }
+            StructuringElement::Diamond => dx.abs() + dy.abs() <= radius,
+        }
+    }
+}
+
+/// Mirrors `MorphologyPixelShader.hlsl`: the per-channel minimum or maximum
+/// of the pixels under the structuring element. Transparent pixels, such as
+/// those outside the image, are skipped so that erosion doesn't eat into the
+/// image from its borders.
+pub fn morphology(
+    input: &Image,
+    operation: MorphologyOperation,
+    shape: StructuringElement,
+    radius: u32,
+) -> Image {
+    let offsets: Vec<(i32, i32)> = {
+        let radius = radius as i32;
+        (-radius..=radius)
+            .flat_map(|dy| (-radius..=radius).map(move |dx| (dx, dy)))
+            .filter(|(dx, dy)| shape.contains(*dx, *dy, radius as u32))
+            .collect()
+    };
+    let width = input.width() as i32;
+    let mut output = Image::new(input.width(), input.height());
+    for (index, pixel) in output.pixels_mut().iter_mut().enumerate() {
+        let x = index as i32 % width;
+        let y = index as i32 / width;
+        let mut result: Option<[f32; 4]> = None;
+        for (dx, dy) in &offsets {
+            let sample = input.pixel(x + dx, y + dy);
+            if sample[3] <= 0.0 {
+                continue;
+            }
+            result = Some(match result {
+                None => sample,
+                Some(result) => {
+                    let mut combined = result;
+                    for c in 0..4 {
+                        combined[c] = match operation {
+                            MorphologyOperation::Erode => result[c].min(sample[c]),
+                            MorphologyOperation::Dilate => result[c].max(sample[c]),
+                        };
+                    }
+                    combined
+                }
+            });
+        }
+        *pixel = result.unwrap_or([0.0; 4]);
+    }
+    output
+}
+
+/// Replaces every connected line with fewer than `min_area` pixels by the
+/// background. Lines are the pixels whose luma is past half of their alpha
+/// in the direction of `polarity`, connected through all eight neighbours.
+pub fn remove_small_components(input: &Image, min_area: u32, polarity: EdgePolarity) -> Image {
+    let width = input.width() as usize;
+    let height = input.height() as usize;
+    let lines = line_mask(input, polarity);
+    let mut output = input.clone();
+    let mut visited = vec![false; lines.len()];
+    let mut component = Vec::new();
+    let mut stack = Vec::new();
+    for start in 0..lines.len() {
+        if !lines[start] || visited[start] {
+            continue;
+        }
+        // Flood fill the component, remembering its pixels
+        component.clear();
+        visited[start] = true;
+        stack.push(start);
+        while let Some(index) = stack.pop() {
+            component.push(index);
+            let x = (index % width) as i32;
+            let y = (index / width) as i32;
+            for (dx, dy) in NEIGHBOURS {
+                let (nx, ny) = (x + dx, y + dy);
+                if nx < 0 || ny < 0 || nx >= width as i32 || ny >= height as i32 {
+                    continue;
+                }
+                let neighbour = ny as usize * width + nx as usize;
+                if lines[neighbour] && !visited[neighbour] {
+                    visited[neighbour] = true;
+                    stack.push(neighbour);
+                }
+            }
+        }
+        if component.len() < min_area as usize {
+            let pixels = output.pixels_mut();
+            for index in &component {
+                pixels[*index] = background(pixels[*index][3], polarity);
+            }
+        }
+    }
+    output
+}
+
+/// Thins the lines of an edge image to one pixel wide skeletons with the
+/// Zhang–Suen algorithm. Pixels that are thinned away turn into background,
+/// the rest keep their value.
+pub fn skeletonize(input: &Image, polarity: EdgePolarity) -> Image {
+    let width = input.width() as i32;
+    let height = input.height() as i32;
+    let mut lines = line_mask(input, polarity);
+    let mut removed = Vec::new();
+    loop {
+        let mut changed = false;
+        for pass in 0..2 {
+            removed.clear();
+            for y in 0..height {
+                for x in 0..width {
+                    let index = (y * width + x) as usize;
+                    if !lines[index] {
+                        continue;
+                    }
+                    // P2 to P9, clockwise from the pixel above
+                    let at = |dx: i32, dy: i32| {
+                        let (nx, ny) = (x + dx, y + dy);
+                        nx >= 0
+                            && ny >= 0
+                            && nx < width
+                            && ny < height
+                            && lines[(ny * width + nx) as usize]
+                    };
+                    let p = [
+                        at(0, -1),
+                        at(1, -1),
+                        at(1, 0),
+                        at(1, 1),
+                        at(0, 1),
+                        at(-1, 1),
+                        at(-1, 0),
+                        at(-1, -1),
+                    ];
+                    let count = p.iter().filter(|set| **set).count();
+                    let transitions = (0..8).filter(|i| !p[*i] && p[(i + 1) % 8]).count();
+                    let [p2, _, p4, _, p6, _, p8, _] = p;
+                    // P2 * P4 * P6 = 0 and P4 * P6 * P8 = 0 on the first pass,
+                    // P2 * P4 * P8 = 0 and P2 * P6 * P8 = 0 on the second
+                    let corner = match pass {
+                        0 => !(p4 && p6) || (!p2 && !p8),
+                        _ => !(p2 && p8) || (!p4 && !p6),
+                    };
+                    if (2..=6).contains(&count) && transitions == 1 && corner {
+                        removed.push(index);
+                    }
+                }
+            }
+            for index in &removed {
+                lines[*index] = false;
+            }
+            changed |= !removed.is_empty();
+        }
+        if !changed {
+            break;
+        }
+    }
+    let mut output = input.clone();
+    let was_line = line_mask(input, polarity);
+    for (index, pixel) in output.pixels_mut().iter_mut().enumerate() {
+        if was_line[index] && !lines[index] {
+            *pixel = background(pixel[3], polarity);
+        }
+    }
+    output
+}
+
+const NEIGHBOURS: [(i32, i32); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+/// Which pixels of an edge image belong to a line.
+fn line_mask(input: &Image, polarity: EdgePolarity) -> Vec<bool> {
+    input
+        .pixels()
+        .iter()
+        .map(|pixel| {
+            let alpha = pixel[3];
+            let luma = luma(pixel);
+            alpha > 0.0
+                && match polarity {
+                    EdgePolarity::Light => luma > 0.5 * alpha,
+                    EdgePolarity::Dark => luma < 0.5 * alpha,
+                }
+        })
+        .collect()
+}
+
+/// A premultiplied background pixel with the given alpha.
+fn background(alpha: f32, polarity: EdgePolarity) -> [f32; 4] {
+    match polarity {
+        EdgePolarity::Light => [0.0, 0.0, 0.0, alpha],
+        EdgePolarity::Dark => [alpha, alpha, alpha, alpha],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An opaque image from rows of `#` for light pixels and `.` for dark.
+    fn image(rows: &[&str]) -> Image {
+        let pixels = rows
+            .iter()
+            .flat_map(|row| {
+                row.chars().map(|c| {
+                    let value = if c == '#' { 1.0 } else { 0.0 };
+                    [value, value, value, 1.0]
+                })
+            })
+            .collect();
+        Image::from_pixels(rows[0].len() as u32, rows.len() as u32, pixels)
+    }
+
+    fn rows(image: &Image) -> Vec<String> {
+        image
+            .pixels()
+            .chunks(image.width() as usize)
+            .map(|row| {
+                row.iter()
+                    .map(|pixel| if pixel[0] > 0.5 { '#' } else { '.' })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn invert(image: &Image) -> Image {
+        image.map(|pixel| [1.0 - pixel[0], 1.0 - pixel[1], 1.0 - pixel[2], pixel[3]])
+    }
+
+    fn count(image: &Image) -> usize {
+        image.pixels().iter().filter(|pixel| pixel[0] > 0.5).count()
+    }
+
+    #[test]
+    fn dilate_grows_a_point_into_the_element() {
+        let mut point = vec!["......."; 7];
+        point[3] = "...#...";
+        let point = image(&point);
+        let dilate = |shape| morphology(&point, MorphologyOperation::Dilate, shape, 2);
+        assert_eq!(count(&dilate(StructuringElement::Square)), 25);
+        assert_eq!(count(&dilate(StructuringElement::Disk)), 21);
+        assert_eq!(count(&dilate(StructuringElement::Diamond)), 13);
+        assert_eq!(
+            rows(&dilate(StructuringElement::Diamond)),
+            [".......", "...#...", "..###..", ".#####.", "..###..", "...#...", "......."]
+        );
+    }
+
+    #[test]
+    fn erode_shrinks_regions_but_not_from_the_image_border() {
+        let block = image(&[
+            ".......", ".#####.", ".#####.", ".#####.", ".#####.", ".#####.", ".......",
+        ]);
+        let eroded = morphology(
+            &block,
+            MorphologyOperation::Erode,
+            StructuringElement::Diamond,
+            1,
+        );
+        assert_eq!(
+            rows(&eroded),
+            [".......", ".......", "..###..", "..###..", "..###..", ".......", "......."]
+        );
+        let eroded = morphology(
+            &block,
+            MorphologyOperation::Erode,
+            StructuringElement::Disk,
+            2,
+        );
+        assert_eq!(count(&eroded), 1);
+        let full = image(&["#####"; 5]);
+        let eroded = morphology(
+            &full,
+            MorphologyOperation::Erode,
+            StructuringElement::Disk,
+            2,
+        );
+        assert_eq!(count(&eroded), 25);
+    }
+
+    #[test]
+    fn components_of_min_area_are_kept() {
+        let input = image(&[
+            "##.....#", //
+            "#......#", "........", "..#.....", "...#...#",
+        ]);
+        let output = remove_small_components(&input, 3, EdgePolarity::Light);
+        assert_eq!(
+            rows(&output),
+            ["##......", "#.......", "........", "........", "........"]
+        );
+        let output = remove_small_components(&input, 2, EdgePolarity::Light);
+        assert_eq!(
+            rows(&output),
+            ["##.....#", "#......#", "........", "..#.....", "...#...."]
+        );
+    }
+
+    #[test]
+    fn thick_bars_thin_to_connected_single_pixel_lines() {
+        let input = image(&[
+            "................",
+            "..############..",
+            "..############..",
+            "..############..",
+            "................",
+        ]);
+        let output = skeletonize(&input, EdgePolarity::Light);
+        let output_rows = rows(&output);
+        // One pixel thick in every column it reaches
+        for x in 0..16 {
+            let thickness = output_rows
+                .iter()
+                .filter(|row| row.as_bytes()[x] == b'#')
+                .count();
+            assert!(thickness <= 1, "{:?}", output_rows);
+        }
+        // ...and a single unbroken line along most of the bar
+        let line = &output_rows[2];
+        let start = line.find('#').unwrap();
+        let end = line.rfind('#').unwrap();
+        assert!(end - start >= 8, "{:?}", output_rows);
+        assert!(
+            line[start..=end].chars().all(|c| c == '#'),
+            "{:?}",
+            output_rows
+        );
+        assert_eq!(
+            remove_small_components(&output, count(&output) as u32, EdgePolarity::Light),
+            output
+        );
+    }
+
+    #[test]
+    fn dark_polarity_treats_dark_pixels_as_lines() {
+        let input = invert(&image(&[
+            "................",
+            "..############..",
+            "..############..",
+            "..############..",
+            "................",
+            ".#..............",
+        ]));
+        let cleaned = remove_small_components(&input, 2, EdgePolarity::Dark);
+        // The speck turns into white paper
+        assert_eq!(cleaned.pixels()[5 * 16 + 1], [1.0; 4]);
+        assert_eq!(count(&invert(&cleaned)), 36);
+        let light = skeletonize(&invert(&cleaned), EdgePolarity::Light);
+        let dark = skeletonize(&cleaned, EdgePolarity::Dark);
+        assert_eq!(invert(&dark), light);
+        // Read as light lines, the speck is part of the paper and stays
+        assert_eq!(
+            remove_small_components(&input, 2, EdgePolarity::Light),
+            input
+        );
+    }
+}
@@ -0,0 +1,94 @@
+//! Soft luminance quantization (Winnemöller et al. 2006), which flattens an
+//! image into bands of even lightness for a cartoon look.
+
+use super::{luma, Image};
+
+/// Mirrors `QuantizePixelShader.hlsl`: snaps the Rec. 601 luma of every
+/// pixel to one of `levels` evenly spaced values from black to white and
+/// scales the color to match, keeping its hue and saturation.
+///
+/// Between two levels the luma follows a `tanh` ramp centered on the bin
+/// boundary and scaled so that it meets the levels exactly. `sharpness` is
+/// the steepness of that ramp: 0 leaves the image as it is and large values
+/// give hard bands.
+pub fn quantize(input: &Image, levels: u32, sharpness: f32) -> Image {
+    let step = 1.0 / (levels.max(2) - 1) as f32;
+    let scale = (0.5 * sharpness).tanh();
+    input.map(|pixel| {
+        let alpha = pixel[3];
+        if alpha <= 0.0 || scale <= 0.0 {
+            return pixel;
+        }
+        let rgb = [pixel[0] / alpha, pixel[1] / alpha, pixel[2] / alpha];
+        let luma = luma(&rgb);
+        let boundary = ((luma / step - 0.5).round() + 0.5) * step;
+        let offset = ((luma - boundary) / step * sharpness).tanh() / scale;
+        let quantized = boundary + 0.5 * step * offset.clamp(-1.0, 1.0);
+        let rgb = if luma > 1e-4 {
+            rgb.map(|value| (value * quantized / luma).min(1.0))
+        } else {
+            [quantized; 3]
+        };
+        [rgb[0] * alpha, rgb[1] * alpha, rgb[2] * alpha, alpha]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ramp() -> Image {
+        let pixels = (0..256)
+            .map(|value| {
+                let value = value as f32 / 255.0;
+                [value, value, value, 1.0]
+            })
+            .collect();
+        Image::from_pixels(256, 1, pixels)
+    }
+
+    fn bands(image: &Image) -> Vec<u32> {
+        let mut bands: Vec<u32> = image
+            .pixels()
+            .iter()
+            .map(|pixel| (pixel[0] * 1000.0).round() as u32)
+            .collect();
+        bands.dedup();
+        bands
+    }
+
+    #[test]
+    fn hard_bands_snap_to_the_levels() {
+        assert_eq!(bands(&quantize(&ramp(), 4, 5000.0)), [0, 333, 667, 1000]);
+        assert_eq!(bands(&quantize(&ramp(), 2, 5000.0)), [0, 1000]);
+        // Fewer than two levels behave like two
+        assert_eq!(bands(&quantize(&ramp(), 1, 5000.0)), [0, 1000]);
+    }
+
+    #[test]
+    fn soft_bands_ramp_between_the_levels() {
+        let output = quantize(&ramp(), 4, 5.0);
+        assert!(bands(&output).len() > 4);
+        // Still monotonic and still meeting black and white
+        let values: Vec<f32> = output.pixels().iter().map(|pixel| pixel[0]).collect();
+        assert!(values.windows(2).all(|pair| pair[0] <= pair[1] + 1e-6));
+        assert!(values[0].abs() < 1e-6 && (values[255] - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn zero_sharpness_leaves_the_image_unchanged() {
+        let input = ramp();
+        assert_eq!(quantize(&input, 4, 0.0), input);
+    }
+
+    #[test]
+    fn colors_keep_their_hue_and_alpha() {
+        // Straight color [0.6, 0.4, 0.2] at half alpha, luma 0.437
+        let input = Image::from_pixels(1, 1, vec![[0.3, 0.2, 0.1, 0.5]]);
+        let pixel = quantize(&input, 3, 5000.0).pixels()[0];
+        assert_eq!(pixel[3], 0.5);
+        let rgb = [pixel[0] / 0.5, pixel[1] / 0.5, pixel[2] / 0.5];
+        assert!((luma(&rgb) - 0.5).abs() < 1e-4, "{:?}", rgb);
+        assert!((rgb[0] / rgb[2] - 3.0).abs() < 1e-4 && (rgb[1] / rgb[2] - 2.0).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,397 @@
+use super::Image;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ThresholdMode {
+    /// Binary step at the threshold
+    Hard,
+    /// Smoothstep ramp of the given width centered on the threshold
+    Smoothstep { width: f32 },
+    /// `0.5 + 0.5 * tanh((value - threshold) / width)`
+    Tanh { width: f32 },
+}
+
+/// Mirrors `ThresholdPixelShader.hlsl`: each color channel is thresholded
+/// with `mode` and alpha is forced to opaque.
+pub fn threshold(input: &Image, threshold: f32, mode: ThresholdMode) -> Image {
+    let step = |value: f32| match mode {
+        ThresholdMode::Smoothstep { width } if width > 0.0 => {
+            let t = ((value - threshold) / width + 0.5).clamp(0.0, 1.0);
+            t * t * (3.0 - 2.0 * t)
+        }
+        ThresholdMode::Tanh { width } if width > 0.0 => {
+            0.5 + 0.5 * ((value - threshold) / width).tanh()
+        }
+        _ => {
+            if value >= threshold {
+                1.0
+            } else {
+                0.0
+            }
+        }
+    };
+    input.map(|pixel| [step(pixel[0]), step(pixel[1]), step(pixel[2]), 1.0])
+}
+
+/// Canny's hysteresis threshold, applied to each color channel on its own.
+/// Values of at least `high` are edges, and so are values of at least `low`
+/// that connect to one of them through their eight neighbours. This follows
+/// lines across the whole image, so unlike [`threshold`] there is no pixel
+/// shader for it and the D2D backend runs it on the CPU too.
+pub fn hysteresis(input: &Image, low: f32, high: f32) -> Image {
+    let width = input.width() as usize;
+    let height = input.height() as usize;
+    let mut output = Image::new(input.width(), input.height());
+    for pixel in output.pixels_mut() {
+        pixel[3] = 1.0;
+    }
+    let mut stack = Vec::new();
+    for c in 0..3 {
+        let values: Vec<f32> = input.pixels().iter().map(|pixel| pixel[c]).collect();
+        let mut edges = vec![false; values.len()];
+        for (index, value) in values.iter().enumerate() {
+            if *value >= high {
+                edges[index] = true;
+                stack.push(index);
+            }
+        }
+        // Grow the strong edges into the weak ones they touch
+        while let Some(index) = stack.pop() {
+            let x = (index % width) as i32;
+            let y = (index / width) as i32;
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    let (nx, ny) = (x + dx, y + dy);
+                    if nx < 0 || ny < 0 || nx >= width as i32 || ny >= height as i32 {
+                        continue;
+                    }
+                    let neighbour = ny as usize * width + nx as usize;
+                    if !edges[neighbour] && values[neighbour] >= low {
+                        edges[neighbour] = true;
+                        stack.push(neighbour);
+                    }
+                }
+            }
+        }
+        for (pixel, edge) in output.pixels_mut().iter_mut().zip(edges) {
+            pixel[c] = if edge { 1.0 } else { 0.0 };
+        }
+    }
+    output
+}
+
+/// How [`select_threshold`] picks a threshold from a response.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ThresholdSelection {
+    /// Otsu's method, the threshold that best splits the histogram of the
+    /// response into two classes
+    Otsu,
+    /// Keeps the strongest `fraction` of the positive responses
+    Percentile { fraction: f32 },
+    /// Turns `fraction` of the image into edges
+    Density { fraction: f32 },
+}
+
+/// Number of histogram bins Otsu's method works with.
+const OTSU_BINS: usize = 1024;
+
+/// Picks a threshold for [`threshold`] from the histogram of a response,
+/// pooling the values of all color channels. Negative responses count as
+/// zero, and the threshold is always positive since a zero response is
+/// never an edge.
+pub fn select_threshold(input: &Image, selection: ThresholdSelection) -> f32 {
+    let mut values: Vec<f32> = input
+        .pixels()
+        .iter()
+        .flat_map(|pixel| pixel[..3].iter().map(|value| value.max(0.0)))
+        .collect();
+    let threshold = match selection {
+        ThresholdSelection::Otsu => otsu(&values),
+        ThresholdSelection::Percentile { fraction } => {
+            values.retain(|value| *value > 0.0);
+            strongest(&mut values, fraction)
+        }
+        ThresholdSelection::Density { fraction } => strongest(&mut values, fraction),
+    };
+    threshold.max(f32::MIN_POSITIVE)
+}
+
+/// Thresholds a response with a value picked by [`select_threshold`], or
+/// with [`hysteresis`] between `low_ratio` times that value and the value
+/// itself. Like [`hysteresis`] this needs the whole image. Returns the
+/// picked value along with the image.
+pub fn auto_threshold(
+    input: &Image,
+    selection: ThresholdSelection,
+    mode: ThresholdMode,
+    low_ratio: Option<f32>,
+) -> (Image, f32) {
+    let high = select_threshold(input, selection);
+    let output = match low_ratio {
+        Some(ratio) => hysteresis(input, ratio * high, high),
+        None => threshold(input, high, mode),
+    };
+    (output, high)
+}
+
+/// The smallest of the `fraction` largest values, keeping at least one.
+fn strongest(values: &mut [f32], fraction: f32) -> f32 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let count = ((fraction * values.len() as f32).ceil() as usize).clamp(1, values.len());
+    let (_, value, _) = values.select_nth_unstable_by(count - 1, |a, b| b.total_cmp(a));
+    *value
+}
+
+/// Lower edge of the first bin above the split with the largest variance
+/// between the two classes.
+fn otsu(values: &[f32]) -> f32 {
+    let max = values.iter().copied().fold(0.0f32, f32::max);
+    if max <= 0.0 {
+        return 0.0;
+    }
+    let bin_width = max / OTSU_BINS as f32;
+    let mut histogram = [0u64; OTSU_BINS];
+    for value in values {
+        let bin = ((value / bin_width) as usize).min(OTSU_BINS - 1);
+        histogram[bin] += 1;
+    }
+    let total = values.len() as f64;
+    let sum: f64 = histogram
+        .iter()
+        .enumerate()
+        .map(|(bin, count)| bin as f64 * *count as f64)
+        .sum();
+    let mut below_count = 0.0;
+    let mut below_sum = 0.0;
+    let mut best = (0.0, 0);
+    for (bin, count) in histogram.iter().enumerate().take(OTSU_BINS - 1) {
+        below_count += *count as f64;
+        below_sum += bin as f64 * *count as f64;
+        let above_count = total - below_count;
+        if below_count == 0.0 || above_count == 0.0 {
+            continue;
+        }
+        let difference = below_sum / below_count - (sum - below_sum) / above_count;
+        let variance = below_count * above_count * difference * difference;
+        if variance > best.0 {
+            best = (variance, bin);
+        }
+    }
+    (best.1 + 1) as f32 * bin_width
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(values: &[f32]) -> Image {
+        Image::from_pixels(
+            values.len() as u32,
+            1,
+            values
+                .iter()
+                .map(|value| [*value, *value, *value, 1.0])
+                .collect(),
+        )
+    }
+
+    fn lit(image: &Image) -> Vec<bool> {
+        image.pixels().iter().map(|pixel| pixel[0] > 0.5).collect()
+    }
+
+    #[test]
+    fn hard_threshold_includes_the_threshold_value() {
+        let output = threshold(&row(&[0.0, 0.009, 0.01, 0.5]), 0.01, ThresholdMode::Hard);
+        assert_eq!(lit(&output), [false, false, true, true]);
+    }
+
+    #[test]
+    fn threshold_forces_opaque_alpha() {
+        let input = Image::from_pixels(1, 1, vec![[0.2, 0.0, 0.2, 0.5]]);
+        let output = threshold(&input, 0.1, ThresholdMode::Hard);
+        assert_eq!(output.pixels()[0], [1.0, 0.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn soft_thresholds_are_centered_on_the_threshold() {
+        for mode in [
+            ThresholdMode::Smoothstep { width: 0.1 },
+            ThresholdMode::Tanh { width: 0.1 },
+        ] {
+            let output = threshold(&row(&[0.0, 0.5, 1.0]), 0.5, mode);
+            let pixels = output.pixels();
+            assert!(pixels[0][0] < 0.01);
+            assert!((pixels[1][0] - 0.5).abs() < 1e-6);
+            assert!(pixels[2][0] > 0.99);
+        }
+    }
+
+    #[test]
+    fn smoothstep_spans_exactly_its_width() {
+        let mode = ThresholdMode::Smoothstep { width: 0.2 };
+        let output = threshold(&row(&[0.39, 0.4, 0.45, 0.55, 0.6, 0.61]), 0.5, mode);
+        let values: Vec<f32> = output.pixels().iter().map(|pixel| pixel[0]).collect();
+        assert_eq!(values[0], 0.0);
+        assert!(values[1] < 1e-5);
+        assert!((values[2] - 0.15625).abs() < 1e-5);
+        assert!((values[3] - 0.84375).abs() < 1e-5);
+        assert!(values[4] > 1.0 - 1e-5);
+        assert_eq!(values[5], 1.0);
+    }
+
+    #[test]
+    fn tanh_rises_monotonically_and_never_saturates() {
+        let mode = ThresholdMode::Tanh { width: 0.05 };
+        let output = threshold(&row(&[0.4, 0.45, 0.5, 0.55, 0.6]), 0.5, mode);
+        let values: Vec<f32> = output.pixels().iter().map(|pixel| pixel[0]).collect();
+        assert!(values.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(values[0] > 0.0 && values[4] < 1.0);
+        let expected = 0.5 + 0.5 * 1f32.tanh();
+        assert!((values[3] - expected).abs() < 1e-6);
+    }
+
+    #[test]
+    fn zero_width_falls_back_to_a_hard_step() {
+        for mode in [
+            ThresholdMode::Smoothstep { width: 0.0 },
+            ThresholdMode::Tanh { width: 0.0 },
+        ] {
+            let output = threshold(&row(&[0.49, 0.5]), 0.5, mode);
+            assert_eq!(lit(&output), [false, true]);
+            assert_eq!(output.pixels()[0][0], 0.0);
+        }
+    }
+
+    fn grid(width: u32, rows: &[&[f32]]) -> Image {
+        let pixels = rows
+            .iter()
+            .flat_map(|row| row.iter().map(|value| [*value, *value, *value, 1.0]))
+            .collect();
+        Image::from_pixels(width, rows.len() as u32, pixels)
+    }
+
+    #[test]
+    fn hysteresis_keeps_weak_pixels_connected_diagonally() {
+        let input = grid(
+            4,
+            &[
+                &[0.9, 0.0, 0.0, 0.0],
+                &[0.0, 0.3, 0.0, 0.0],
+                &[0.0, 0.0, 0.3, 0.0],
+                &[0.0, 0.0, 0.0, 0.1],
+            ],
+        );
+        let output = hysteresis(&input, 0.2, 0.5);
+        assert_eq!(
+            lit(&output),
+            [
+                true, false, false, false, //
+                false, true, false, false, //
+                false, false, true, false, //
+                false, false, false, false,
+            ]
+        );
+        assert!(output.pixels().iter().all(|pixel| pixel[3] == 1.0));
+    }
+
+    #[test]
+    fn hysteresis_drops_isolated_weak_runs() {
+        let output = hysteresis(&row(&[0.9, 0.0, 0.3, 0.3, 0.3, 0.0]), 0.2, 0.5);
+        assert_eq!(lit(&output), [true, false, false, false, false, false]);
+    }
+
+    #[test]
+    fn hysteresis_without_a_lower_band_is_a_single_threshold() {
+        let input = row(&[0.1, 0.3, 0.5, 0.7, 0.3, 0.9]);
+        for low in [0.5, 0.8] {
+            let output = hysteresis(&input, low, 0.5);
+            assert_eq!(
+                lit(&output),
+                lit(&threshold(&input, 0.5, ThresholdMode::Hard))
+            );
+        }
+    }
+
+    #[test]
+    fn hysteresis_follows_lines_along_the_border_without_wrapping() {
+        let input = grid(
+            3,
+            &[
+                &[0.3, 0.3, 0.9], //
+                &[0.3, 0.0, 0.0],
+                &[0.0, 0.0, 0.3],
+            ],
+        );
+        let output = hysteresis(&input, 0.2, 0.5);
+        // The last pixel of the first row doesn't touch the first of the second
+        assert_eq!(
+            lit(&output),
+            [
+                true, true, true, //
+                true, false, false, //
+                false, false, false,
+            ]
+        );
+        let output = hysteresis(&grid(3, &[&[0.0, 0.0, 0.9], &[0.3, 0.0, 0.0]]), 0.2, 0.5);
+        assert_eq!(lit(&output), [false, false, true, false, false, false]);
+    }
+
+    #[test]
+    fn otsu_splits_a_bimodal_histogram() {
+        let mut values = vec![0.1; 60];
+        values.extend([0.8; 40]);
+        let picked = select_threshold(&row(&values), ThresholdSelection::Otsu);
+        assert!(picked > 0.1 && picked <= 0.8, "{}", picked);
+        let (output, threshold) = auto_threshold(
+            &row(&values),
+            ThresholdSelection::Otsu,
+            ThresholdMode::Hard,
+            None,
+        );
+        assert_eq!(threshold, picked);
+        assert_eq!(lit(&output).iter().filter(|lit| **lit).count(), 40);
+    }
+
+    #[test]
+    fn all_zero_responses_pick_the_smallest_positive_threshold() {
+        let input = row(&[0.0, -0.5, 0.0, 0.0]);
+        for selection in [
+            ThresholdSelection::Otsu,
+            ThresholdSelection::Percentile { fraction: 0.1 },
+            ThresholdSelection::Density { fraction: 0.1 },
+        ] {
+            assert_eq!(select_threshold(&input, selection), f32::MIN_POSITIVE);
+            let (output, _) = auto_threshold(&input, selection, ThresholdMode::Hard, None);
+            assert!(lit(&output).iter().all(|lit| !lit));
+        }
+    }
+
+    #[test]
+    fn percentile_of_one_keeps_every_positive_response() {
+        let input = row(&[0.0, 0.3, 0.05, 0.0, 0.6]);
+        let selection = ThresholdSelection::Percentile { fraction: 1.0 };
+        assert_eq!(select_threshold(&input, selection), 0.05);
+        let (output, _) = auto_threshold(&input, selection, ThresholdMode::Hard, None);
+        assert_eq!(lit(&output), [false, true, true, false, true]);
+    }
+
+    #[test]
+    fn percentile_keeps_the_strongest_fraction() {
+        let values: Vec<f32> = (1..=10).map(|value| value as f32 / 10.0).collect();
+        let selection = ThresholdSelection::Percentile { fraction: 0.3 };
+        assert_eq!(select_threshold(&row(&values), selection), 0.8);
+    }
+
+    #[test]
+    fn density_above_the_positive_share_only_keeps_positive_responses() {
+        let input = row(&[0.0, 0.4, 0.0, 0.0, 0.2, 0.0, 0.0, 0.0]);
+        let selection = ThresholdSelection::Density { fraction: 0.5 };
+        assert_eq!(select_threshold(&input, selection), f32::MIN_POSITIVE);
+        let (output, _) = auto_threshold(&input, selection, ThresholdMode::Hard, None);
+        assert_eq!(
+            lit(&output),
+            [false, true, false, false, true, false, false, false]
+        );
+    }
+}
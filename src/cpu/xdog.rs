@@ -0,0 +1,73 @@
+use super::Image;
+
+/// Mirrors `XDoGPixelShader.hlsl`: sharpens the narrow blur against the wide
+/// one, `(1 + p) * narrow - p * wide`, then applies the soft threshold
+/// `1 + tanh(phi * (u - epsilon))` below `epsilon`.
+pub fn xdog(narrow: &Image, wide: &Image, sharpness: f32, epsilon: f32, phi: f32) -> Image {
+    let soft_threshold = |value: f32| {
+        if value >= epsilon {
+            1.0
+        } else {
+            1.0 + (phi * (value - epsilon)).tanh()
+        }
+    };
+    narrow.zip_map(wide, |n, w| {
+        let u = |c: usize| (1.0 + sharpness) * n[c] - sharpness * w[c];
+        [
+            soft_threshold(u(0)),
+            soft_threshold(u(1)),
+            soft_threshold(u(2)),
+            1.0,
+        ]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gray(value: f32) -> Image {
+        Image::from_pixels(1, 1, vec![[value, value, value, 1.0]])
+    }
+
+    fn xdog_gray(narrow: f32, wide: f32, sharpness: f32, epsilon: f32, phi: f32) -> f32 {
+        let output = xdog(&gray(narrow), &gray(wide), sharpness, epsilon, phi);
+        assert_eq!(output.pixels()[0][3], 1.0);
+        output.pixels()[0][0]
+    }
+
+    #[test]
+    fn responses_from_epsilon_up_are_white() {
+        assert_eq!(xdog_gray(0.5, 0.5, 20.0, 0.5, 10.0), 1.0);
+        assert_eq!(xdog_gray(0.9, 0.2, 20.0, 0.5, 10.0), 1.0);
+    }
+
+    #[test]
+    fn sharpening_weighs_the_narrow_blur_against_the_wide_one() {
+        // u = 3 * 0.6 - 2 * 0.5 = 0.8, just below epsilon
+        let expected = 1.0 + (10.0f32 * (0.8 - 0.9)).tanh();
+        assert!((xdog_gray(0.6, 0.5, 2.0, 0.9, 10.0) - expected).abs() < 1e-5);
+        // Without sharpening the wide blur has no effect
+        let expected = 1.0 + (10.0f32 * (0.3 - 0.5)).tanh();
+        assert!((xdog_gray(0.3, 1.0, 0.0, 0.5, 10.0) - expected).abs() < 1e-6);
+    }
+
+    #[test]
+    fn phi_sets_how_quickly_responses_below_epsilon_darken() {
+        let soft = xdog_gray(0.4, 0.4, 20.0, 0.5, 2.0);
+        let steep = xdog_gray(0.4, 0.4, 20.0, 0.5, 50.0);
+        assert!(steep < soft && soft < 1.0);
+        assert!(steep < 1e-3);
+        // The soft threshold is continuous at epsilon
+        assert!(1.0 - xdog_gray(0.5 - 1e-4, 0.5 - 1e-4, 20.0, 0.5, 10.0) < 1e-2);
+    }
+
+    #[test]
+    fn channels_are_thresholded_on_their_own() {
+        let narrow = Image::from_pixels(1, 1, vec![[0.8, 0.2, 0.5, 0.5]]);
+        let output = xdog(&narrow, &narrow, 20.0, 0.5, 100.0);
+        let pixel = output.pixels()[0];
+        assert_eq!([pixel[0], pixel[2], pixel[3]], [1.0, 1.0, 1.0]);
+        assert!(pixel[1] < 1e-6);
+    }
+}
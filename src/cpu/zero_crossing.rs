@@ -0,0 +1,99 @@
+use super::{luma, Image};
+
+/// Mirrors `ZeroCrossingPixelShader.hlsl`: marks the pixels where the
+/// luminance of a signed response changes sign (Marr–Hildreth). Of the two
+/// pixels on either side of a crossing only the one closer to zero is marked,
+/// which keeps edges one pixel thin, and only if the response changes by at
+/// least `min_slope` between them. Edges are white on black, and neighbours
+/// outside the image are ignored.
+pub fn zero_crossing(input: &Image, min_slope: f32) -> Image {
+    let width = input.width() as i32;
+    let mut output = Image::new(input.width(), input.height());
+    for (index, pixel) in output.pixels_mut().iter_mut().enumerate() {
+        let x = index as i32 % width;
+        let y = index as i32 / width;
+        let value = luma(&input.pixel(x, y));
+        let edge = [(1, 0), (-1, 0), (0, 1), (0, -1)].iter().any(|(dx, dy)| {
+            let neighbour = input.pixel(x + dx, y + dy);
+            if neighbour[3] <= 0.0 {
+                return false;
+            }
+            let neighbour = luma(&neighbour);
+            (value > 0.0) != (neighbour > 0.0)
+                && (value.abs() < neighbour.abs()
+                    || (value.abs() == neighbour.abs() && value > 0.0))
+                && (value - neighbour).abs() >= min_slope
+        });
+        let edge = if edge { 1.0 } else { 0.0 };
+        *pixel = [edge, edge, edge, 1.0];
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(width: u32, values: &[f32]) -> Image {
+        Image::from_pixels(
+            width,
+            values.len() as u32 / width,
+            values
+                .iter()
+                .map(|value| [*value, *value, *value, 1.0])
+                .collect(),
+        )
+    }
+
+    fn marked(image: &Image) -> Vec<bool> {
+        image.pixels().iter().map(|pixel| pixel[0] == 1.0).collect()
+    }
+
+    #[test]
+    fn only_the_pixel_closer_to_zero_is_marked() {
+        let output = zero_crossing(&grid(5, &[-0.3, -0.1, 0.2, 0.4, 0.5]), 0.0);
+        assert_eq!(marked(&output), [false, true, false, false, false]);
+        let output = zero_crossing(&grid(4, &[0.5, 0.05, -0.2, -0.6]), 0.0);
+        assert_eq!(marked(&output), [false, true, false, false]);
+        // A tie goes to the positive side
+        let output = zero_crossing(&grid(2, &[-0.2, 0.2]), 0.0);
+        assert_eq!(marked(&output), [false, true]);
+    }
+
+    #[test]
+    fn edges_are_one_pixel_thin() {
+        // A vertical step from negative to positive across a 6x4 image
+        let values: Vec<f32> = (0..24)
+            .map(|i| [-0.4, -0.3, -0.2, 0.1, 0.3, 0.4][i % 6])
+            .collect();
+        let output = zero_crossing(&grid(6, &values), 0.0);
+        for row in marked(&output).chunks(6) {
+            assert_eq!(row, [false, false, false, true, false, false]);
+        }
+    }
+
+    #[test]
+    fn min_slope_drops_weak_crossings() {
+        let input = grid(4, &[-0.01, 0.01, 0.3, -0.3]);
+        assert_eq!(
+            marked(&zero_crossing(&input, 0.0)),
+            [false, true, true, false]
+        );
+        assert_eq!(
+            marked(&zero_crossing(&input, 0.05)),
+            [false, false, true, false]
+        );
+        assert_eq!(marked(&zero_crossing(&input, 0.7)), [false; 4]);
+    }
+
+    #[test]
+    fn responses_without_a_sign_change_have_no_edges() {
+        let output = zero_crossing(&grid(3, &[0.1, 0.5, 0.2, 0.05, 0.3, 0.9]), 0.0);
+        assert!(output
+            .pixels()
+            .iter()
+            .all(|pixel| *pixel == [0.0, 0.0, 0.0, 1.0]));
+        let output = zero_crossing(&grid(2, &[-0.1, -0.5, -0.2, -0.3]), 0.0);
+        assert_eq!(marked(&output), [false; 4]);
+    }
+}
@@ -1,8 +1,12 @@
-use windows::core::{Interface, Result};
-use windows::Graphics::DirectX::Direct3D11::IDirect3DSurface;
-use windows::Win32::Graphics::Direct3D11::{ID3D11Texture2D, D3D11_CREATE_DEVICE_DEBUG};
+use windows::core::Result;
+use windows::Win32::Graphics::Direct3D11::{
+    ID3D11Texture2D, D3D11_CPU_ACCESS_READ, D3D11_CREATE_DEVICE_DEBUG, D3D11_MAPPED_SUBRESOURCE,
+    D3D11_MAP_READ, D3D11_TEXTURE2D_DESC, D3D11_USAGE_STAGING,
+};
 
-use windows::Win32::Graphics::Dxgi::IDXGISurface;
+use windows::Win32::Graphics::Dxgi::Common::{
+    DXGI_FORMAT, DXGI_FORMAT_R16G16B16A16_FLOAT, DXGI_FORMAT_R32G32B32A32_FLOAT,
+};
 use windows::Win32::Graphics::{
     Direct3D::{D3D_DRIVER_TYPE, D3D_DRIVER_TYPE_HARDWARE, D3D_DRIVER_TYPE_WARP},
     Direct3D11::{
@@ -11,7 +15,6 @@ use windows::Win32::Graphics::{
     },
     Dxgi::DXGI_ERROR_UNSUPPORTED,
 };
-use windows::Win32::System::WinRT::Direct3D11::CreateDirect3D11SurfaceFromDXGISurface;
 
 fn create_d3d_device_with_type(
     driver_type: D3D_DRIVER_TYPE,
@@ -52,8 +55,59 @@ pub fn create_d3d_device() -> Result<ID3D11Device> {
     Ok(device.unwrap())
 }
 
-pub fn create_direct3d_surface(d3d_texture: &ID3D11Texture2D) -> Result<IDirect3DSurface> {
-    let dxgi_surface: IDXGISurface = d3d_texture.cast()?;
-    let inspectable = unsafe { CreateDirect3D11SurfaceFromDXGISurface(Some(&dxgi_surface))? };
-    inspectable.cast()
+/// Size of a pixel in the texture formats the pipeline renders to.
+pub fn bytes_per_pixel(format: DXGI_FORMAT) -> usize {
+    match format {
+        DXGI_FORMAT_R32G32B32A32_FLOAT => 16,
+        DXGI_FORMAT_R16G16B16A16_FLOAT => 8,
+        _ => 4,
+    }
+}
+
+/// Copies the contents of a texture back to the CPU as tightly packed rows.
+pub fn read_texture_bytes(d3d_device: &ID3D11Device, texture: &ID3D11Texture2D) -> Result<Vec<u8>> {
+    let staging_texture = {
+        let mut desc = D3D11_TEXTURE2D_DESC::default();
+        unsafe {
+            texture.GetDesc(&mut desc);
+        }
+        desc.Usage = D3D11_USAGE_STAGING;
+        desc.BindFlags = 0;
+        desc.CPUAccessFlags = D3D11_CPU_ACCESS_READ.0 as u32;
+        desc.MiscFlags = 0;
+
+        unsafe {
+            let mut staging_texture = None;
+            d3d_device.CreateTexture2D(&desc, None, Some(&mut staging_texture))?;
+            staging_texture.unwrap()
+        }
+    };
+
+    let mut desc = D3D11_TEXTURE2D_DESC::default();
+    unsafe {
+        staging_texture.GetDesc(&mut desc);
+    }
+    let row_len = desc.Width as usize * bytes_per_pixel(desc.Format);
+    let mut bytes = vec![0u8; row_len * desc.Height as usize];
+
+    unsafe {
+        let d3d_context = d3d_device.GetImmediateContext()?;
+        d3d_context.CopyResource(&staging_texture, texture);
+
+        let mut mapped = D3D11_MAPPED_SUBRESOURCE::default();
+        d3d_context.Map(&staging_texture, 0, D3D11_MAP_READ, 0, Some(&mut mapped))?;
+        let source = std::slice::from_raw_parts(
+            mapped.pData as *const u8,
+            mapped.RowPitch as usize * desc.Height as usize,
+        );
+        for (dest_row, source_row) in bytes
+            .chunks_exact_mut(row_len)
+            .zip(source.chunks(mapped.RowPitch as usize))
+        {
+            dest_row.copy_from_slice(&source_row[..row_len]);
+        }
+        d3d_context.Unmap(&staging_texture, 0);
+    }
+
+    Ok(bytes)
 }
@@ -0,0 +1,175 @@
+use windows::{
+    core::{implement, w, IUnknown, Result, GUID, HRESULT, PCWSTR},
+    Win32::{
+        Foundation::{E_INVALIDARG, S_OK},
+        Graphics::Direct2D::{
+            ID2D1DrawInfo, ID2D1DrawTransform, ID2D1EffectImpl, ID2D1Factory1,
+            D2D1_PROPERTY_BINDING,
+        },
+    },
+};
+
+pub const ANISOTROPIC_KUWAHARA_EFFECT_CLSID: GUID =
+    GUID::from_u128(0x46CEDF95_6AA8_4BA2_B602_8EC825463952);
+pub const ANISOTROPIC_KUWAHARA_EFFECT_SHADER: GUID =
+    GUID::from_u128(0xA1839F67_A826_4A31_8820_FD06BAD23ECB);
+
+pub const ANISOTROPIC_KUWAHARA_PROP_RADIUS: u32 = 0;
+pub const ANISOTROPIC_KUWAHARA_PROP_SHARPNESS: u32 = 1;
+pub const ANISOTROPIC_KUWAHARA_PROP_ALPHA: u32 = 2;
+
+/// Anisotropic Kuwahara filter. Input 0 is the image and input 1 the blurred
+/// output of the structure tensor effect, whose flow orients and stretches
+/// the filter's elliptic kernel.
+#[implement(ID2D1EffectImpl, ID2D1DrawTransform)]
+pub struct AnisotropicKuwaharaEffect {
+    constants: AnisotropicKuwaharaEffectConstants,
+    draw_info: Option<ID2D1DrawInfo>,
+}
+
+#[repr(C)]
+struct AnisotropicKuwaharaEffectConstants {
+    radius: f32,
+    sharpness: f32,
+    alpha: f32,
+}
+
+impl_pixel_shader_transform!(
+    AnisotropicKuwaharaEffect_Impl,
+    ANISOTROPIC_KUWAHARA_EFFECT_SHADER,
+    shaders::anisotropic_kuwahara_pixel_shader(),
+    2,
+    // The kernel is stretched to at most twice the radius
+    |effect| (effect.constants.radius * 2.0).ceil() as i32
+);
+
+impl AnisotropicKuwaharaEffect {
+    fn new() -> Self {
+        Self {
+            constants: AnisotropicKuwaharaEffectConstants {
+                radius: 6.0,
+                sharpness: 8.0,
+                alpha: 1.0,
+            },
+            draw_info: None,
+        }
+    }
+    pub fn register(factory: &ID2D1Factory1) -> Result<()> {
+        let bindings = [
+            D2D1_PROPERTY_BINDING {
+                propertyName: w!("Radius"),
+                setFunction: Some(radius_helpers::value_setter),
+                getFunction: Some(radius_helpers::value_getter),
+            },
+            D2D1_PROPERTY_BINDING {
+                propertyName: w!("Sharpness"),
+                setFunction: Some(sharpness_helpers::value_setter),
+                getFunction: Some(sharpness_helpers::value_getter),
+            },
+            D2D1_PROPERTY_BINDING {
+                propertyName: w!("Alpha"),
+                setFunction: Some(alpha_helpers::value_setter),
+                getFunction: Some(alpha_helpers::value_getter),
+            },
+        ];
+
+        unsafe {
+            factory.RegisterEffectFromString(
+                &ANISOTROPIC_KUWAHARA_EFFECT_CLSID,
+                ANISOTROPIC_KUWAHARA_EFFECT_XML,
+                Some(&bindings),
+                Some(Self::create_effect),
+            )?;
+        }
+        Ok(())
+    }
+    unsafe extern "system" fn create_effect(effectimpl: *mut Option<IUnknown>) -> HRESULT {
+        // This gets us the base vtable in AnisotropicKuwaharaEffect_Impl
+        let effect_unknown: IUnknown = Self::new().into();
+        if let Some(effectimpl) = effectimpl.as_mut() {
+            *effectimpl = Some(effect_unknown);
+            S_OK
+        } else {
+            E_INVALIDARG
+        }
+    }
+    fn set_radius(&mut self, radius: f32) -> Result<()> {
+        if !(0.0..=50.0).contains(&radius) {
+            return E_INVALIDARG.ok();
+        }
+        self.constants.radius = radius;
+        Ok(())
+    }
+    fn get_radius(&self) -> f32 {
+        self.constants.radius
+    }
+    fn set_sharpness(&mut self, sharpness: f32) -> Result<()> {
+        self.constants.sharpness = sharpness;
+        Ok(())
+    }
+    fn get_sharpness(&self) -> f32 {
+        self.constants.sharpness
+    }
+    fn set_alpha(&mut self, alpha: f32) -> Result<()> {
+        if alpha <= 0.0 {
+            return E_INVALIDARG.ok();
+        }
+        self.constants.alpha = alpha;
+        Ok(())
+    }
+    fn get_alpha(&self) -> f32 {
+        self.constants.alpha
+    }
+}
+
+const ANISOTROPIC_KUWAHARA_EFFECT_XML: PCWSTR = w!(r#"<?xml version='1.0'?>
+<Effect>
+    <Property name='DisplayName' type='string' value='AnisotropicKuwaharaEffect'/>
+    <Property name='Author' type='string' value='Contoso'/>
+    <Property name='Category' type='string' value='Stylize'/>
+    <Property name='Description' type='string' value='Painterly smoothing with an anisotropic Kuwahara filter.'/>
+    <Inputs>
+        <Input name='Source'/>
+        <Input name='Flow'/>
+    </Inputs>
+    <Property name='Radius' type='float'>
+        <Property name='DisplayName' type='string' value='Radius'/>
+        <Property name='Min' type='float' value='0.0' />
+        <Property name='Max' type='float' value='50.0' />
+        <Property name='Default' type='float' value='6.0' />
+    </Property>
+    <Property name='Sharpness' type='float'>
+        <Property name='DisplayName' type='string' value='Sharpness'/>
+        <Property name='Min' type='float' value='0.0' />
+        <Property name='Max' type='float' value='1000.0' />
+        <Property name='Default' type='float' value='8.0' />
+    </Property>
+    <Property name='Alpha' type='float'>
+        <Property name='DisplayName' type='string' value='Alpha'/>
+        <Property name='Min' type='float' value='0.001' />
+        <Property name='Max' type='float' value='1000.0' />
+        <Property name='Default' type='float' value='1.0' />
+    </Property>
+</Effect>"#);
+
+create_setter_helpers!(
+    AnisotropicKuwaharaEffect_Impl,
+    set_radius,
+    get_radius,
+    f32,
+    radius_helpers
+);
+create_setter_helpers!(
+    AnisotropicKuwaharaEffect_Impl,
+    set_sharpness,
+    get_sharpness,
+    f32,
+    sharpness_helpers
+);
+create_setter_helpers!(
+    AnisotropicKuwaharaEffect_Impl,
+    set_alpha,
+    get_alpha,
+    f32,
+    alpha_helpers
+);
@@ -0,0 +1,151 @@
+use windows::{
+    core::{implement, w, IUnknown, Result, GUID, HRESULT, PCWSTR},
+    Win32::{
+        Foundation::{E_INVALIDARG, S_OK},
+        Graphics::Direct2D::{
+            ID2D1DrawInfo, ID2D1DrawTransform, ID2D1EffectImpl, ID2D1Factory1,
+            D2D1_PROPERTY_BINDING,
+        },
+    },
+};
+
+pub const BILATERAL_EFFECT_CLSID: GUID = GUID::from_u128(0x6C6EF587_4A1B_4E83_91F1_F963CA9CB9E2);
+pub const BILATERAL_EFFECT_SHADER: GUID = GUID::from_u128(0x2CCA0D3F_C27A_48AD_BF77_8F18BC43F665);
+
+pub const BILATERAL_PROP_SPATIAL_SIGMA: u32 = 0;
+pub const BILATERAL_PROP_RANGE_SIGMA: u32 = 1;
+
+/// Edge-preserving bilateral filter. Neighbours are weighted by a Gaussian of
+/// their distance and one of their color difference.
+#[implement(ID2D1EffectImpl, ID2D1DrawTransform)]
+pub struct BilateralEffect {
+    constants: BilateralEffectConstants,
+    draw_info: Option<ID2D1DrawInfo>,
+}
+
+#[repr(C)]
+struct BilateralEffectConstants {
+    spatial_sigma: f32,
+    range_sigma: f32,
+    /// Samples taken on either side of the pixel, 2 times the spatial sigma
+    radius: i32,
+}
+
+impl_pixel_shader_transform!(
+    BilateralEffect_Impl,
+    BILATERAL_EFFECT_SHADER,
+    shaders::bilateral_pixel_shader(),
+    1,
+    |effect| effect.constants.radius
+);
+
+impl BilateralEffect {
+    fn new() -> Self {
+        let mut effect = Self {
+            constants: BilateralEffectConstants {
+                spatial_sigma: 3.0,
+                range_sigma: 0.1,
+                radius: 0,
+            },
+            draw_info: None,
+        };
+        effect.update_radius();
+        effect
+    }
+    pub fn register(factory: &ID2D1Factory1) -> Result<()> {
+        let bindings = [
+            D2D1_PROPERTY_BINDING {
+                propertyName: w!("SpatialSigma"),
+                setFunction: Some(spatial_sigma_helpers::value_setter),
+                getFunction: Some(spatial_sigma_helpers::value_getter),
+            },
+            D2D1_PROPERTY_BINDING {
+                propertyName: w!("RangeSigma"),
+                setFunction: Some(range_sigma_helpers::value_setter),
+                getFunction: Some(range_sigma_helpers::value_getter),
+            },
+        ];
+
+        unsafe {
+            factory.RegisterEffectFromString(
+                &BILATERAL_EFFECT_CLSID,
+                BILATERAL_EFFECT_XML,
+                Some(&bindings),
+                Some(Self::create_effect),
+            )?;
+        }
+        Ok(())
+    }
+    unsafe extern "system" fn create_effect(effectimpl: *mut Option<IUnknown>) -> HRESULT {
+        // This gets us the base vtable in BilateralEffect_Impl
+        let effect_unknown: IUnknown = Self::new().into();
+        if let Some(effectimpl) = effectimpl.as_mut() {
+            *effectimpl = Some(effect_unknown);
+            S_OK
+        } else {
+            E_INVALIDARG
+        }
+    }
+    fn update_radius(&mut self) {
+        self.constants.radius = (self.constants.spatial_sigma * 2.0).ceil() as i32;
+    }
+    fn set_spatial_sigma(&mut self, spatial_sigma: f32) -> Result<()> {
+        if !(0.0..=50.0).contains(&spatial_sigma) {
+            return E_INVALIDARG.ok();
+        }
+        self.constants.spatial_sigma = spatial_sigma;
+        self.update_radius();
+        Ok(())
+    }
+    fn get_spatial_sigma(&self) -> f32 {
+        self.constants.spatial_sigma
+    }
+    fn set_range_sigma(&mut self, range_sigma: f32) -> Result<()> {
+        if !(0.0..=1000.0).contains(&range_sigma) {
+            return E_INVALIDARG.ok();
+        }
+        self.constants.range_sigma = range_sigma;
+        Ok(())
+    }
+    fn get_range_sigma(&self) -> f32 {
+        self.constants.range_sigma
+    }
+}
+
+const BILATERAL_EFFECT_XML: PCWSTR = w!(r#"<?xml version='1.0'?>
+<Effect>
+    <Property name='DisplayName' type='string' value='BilateralEffect'/>
+    <Property name='Author' type='string' value='Contoso'/>
+    <Property name='Category' type='string' value='Stylize'/>
+    <Property name='Description' type='string' value='Edge-preserving bilateral smoothing.'/>
+    <Inputs>
+        <Input name='Source'/>
+    </Inputs>
+    <Property name='SpatialSigma' type='float'>
+        <Property name='DisplayName' type='string' value='SpatialSigma'/>
+        <Property name='Min' type='float' value='0.0' />
+        <Property name='Max' type='float' value='50.0' />
+        <Property name='Default' type='float' value='3.0' />
+    </Property>
+    <Property name='RangeSigma' type='float'>
+        <Property name='DisplayName' type='string' value='RangeSigma'/>
+        <Property name='Min' type='float' value='0.0' />
+        <Property name='Max' type='float' value='1000.0' />
+        <Property name='Default' type='float' value='0.1' />
+    </Property>
+</Effect>"#);
+
+create_setter_helpers!(
+    BilateralEffect_Impl,
+    set_spatial_sigma,
+    get_spatial_sigma,
+    f32,
+    spatial_sigma_helpers
+);
+create_setter_helpers!(
+    BilateralEffect_Impl,
+    set_range_sigma,
+    get_range_sigma,
+    f32,
+    range_sigma_helpers
+);
@@ -0,0 +1,70 @@
+use windows::{
+    core::{implement, w, IUnknown, Result, GUID, HRESULT, PCWSTR},
+    Win32::{
+        Foundation::{E_INVALIDARG, S_OK},
+        Graphics::Direct2D::{ID2D1DrawInfo, ID2D1DrawTransform, ID2D1EffectImpl, ID2D1Factory1},
+    },
+};
+
+pub const COHERENCE_EFFECT_CLSID: GUID = GUID::from_u128(0xDF576AED_247B_4BFF_BBBF_AA1FE507A9AC);
+pub const COHERENCE_EFFECT_SHADER: GUID = GUID::from_u128(0xBCFF2B18_B202_47EA_8047_5F6147C1B88F);
+
+/// Coherence `(λ1 - λ2) / (λ1 + λ2)` of a blurred structure tensor, drawn in
+/// grayscale.
+#[implement(ID2D1EffectImpl, ID2D1DrawTransform)]
+pub struct CoherenceEffect {
+    constants: CoherenceEffectConstants,
+    draw_info: Option<ID2D1DrawInfo>,
+}
+
+#[repr(C)]
+struct CoherenceEffectConstants {}
+
+impl_pixel_shader_transform!(
+    CoherenceEffect_Impl,
+    COHERENCE_EFFECT_SHADER,
+    shaders::coherence_pixel_shader(),
+    1,
+    opaque
+);
+
+impl CoherenceEffect {
+    fn new() -> Self {
+        Self {
+            constants: CoherenceEffectConstants {},
+            draw_info: None,
+        }
+    }
+    pub fn register(factory: &ID2D1Factory1) -> Result<()> {
+        unsafe {
+            factory.RegisterEffectFromString(
+                &COHERENCE_EFFECT_CLSID,
+                COHERENCE_EFFECT_XML,
+                None,
+                Some(Self::create_effect),
+            )?;
+        }
+        Ok(())
+    }
+    unsafe extern "system" fn create_effect(effectimpl: *mut Option<IUnknown>) -> HRESULT {
+        // This gets us the base vtable in CoherenceEffect_Impl
+        let effect_unknown: IUnknown = Self::new().into();
+        if let Some(effectimpl) = effectimpl.as_mut() {
+            *effectimpl = Some(effect_unknown);
+            S_OK
+        } else {
+            E_INVALIDARG
+        }
+    }
+}
+
+const COHERENCE_EFFECT_XML: PCWSTR = w!(r#"<?xml version='1.0'?>
+<Effect>
+    <Property name='DisplayName' type='string' value='CoherenceEffect'/>
+    <Property name='Author' type='string' value='Contoso'/>
+    <Property name='Category' type='string' value='Stylize'/>
+    <Property name='Description' type='string' value='Draws how clearly oriented the edge tangent flow is.'/>
+    <Inputs>
+        <Input name='Flow'/>
+    </Inputs>
+</Effect>"#);
@@ -0,0 +1,209 @@
+use windows::{
+    core::{implement, w, IUnknown, Result, GUID, HRESULT, PCWSTR},
+    Win32::{
+        Foundation::{E_INVALIDARG, S_OK},
+        Graphics::Direct2D::{
+            ID2D1DrawInfo, ID2D1DrawTransform, ID2D1EffectImpl, ID2D1Factory1,
+            D2D1_PROPERTY_BINDING,
+        },
+    },
+};
+
+pub const COLORIZE_EFFECT_CLSID: GUID = GUID::from_u128(0x5A97EF00_FB91_481D_93B6_645B4AD8E798);
+pub const COLORIZE_EFFECT_SHADER: GUID = GUID::from_u128(0x26496567_6742_4144_923C_E36FBB2315FA);
+
+pub const COLORIZE_PROP_POLARITY: u32 = 0;
+pub const COLORIZE_PROP_STOP_COUNT: u32 = 1;
+pub const COLORIZE_PROP_COLORS: u32 = 2;
+pub const COLORIZE_PROP_POSITIONS: u32 = 3;
+
+pub const COLORIZE_POLARITY_LIGHT: u32 = 0;
+pub const COLORIZE_POLARITY_DARK: u32 = 1;
+
+pub const COLORIZE_MAX_STOPS: usize = 8;
+
+/// Maps the edge coverage of an edge image, its luma or the inverse of it for
+/// dark lines, through a gradient of up to 8 straight-alpha colors. `Colors`
+/// and `Positions` are blobs holding all 8 stops, of which the first
+/// `StopCount` are used.
+#[implement(ID2D1EffectImpl, ID2D1DrawTransform)]
+pub struct ColorizeEffect {
+    constants: ColorizeEffectConstants,
+    draw_info: Option<ID2D1DrawInfo>,
+}
+
+#[repr(C)]
+struct ColorizeEffectConstants {
+    colors: [[f32; 4]; COLORIZE_MAX_STOPS],
+    /// Packed four to a register
+    positions: [f32; COLORIZE_MAX_STOPS],
+    stop_count: u32,
+    polarity: u32,
+    _padding: [u32; 2],
+}
+
+impl_pixel_shader_transform!(
+    ColorizeEffect_Impl,
+    COLORIZE_EFFECT_SHADER,
+    shaders::colorize_pixel_shader(),
+    1
+);
+
+impl ColorizeEffect {
+    fn new() -> Self {
+        // Black paper and white ink, which leaves a grayscale edge image as is
+        let mut colors = [[0.0; 4]; COLORIZE_MAX_STOPS];
+        colors[0] = [0.0, 0.0, 0.0, 1.0];
+        colors[1] = [1.0, 1.0, 1.0, 1.0];
+        let mut positions = [0.0; COLORIZE_MAX_STOPS];
+        positions[1] = 1.0;
+        Self {
+            constants: ColorizeEffectConstants {
+                colors,
+                positions,
+                stop_count: 2,
+                polarity: COLORIZE_POLARITY_LIGHT,
+                _padding: [0; 2],
+            },
+            draw_info: None,
+        }
+    }
+    pub fn register(factory: &ID2D1Factory1) -> Result<()> {
+        let bindings = [
+            D2D1_PROPERTY_BINDING {
+                propertyName: w!("Polarity"),
+                setFunction: Some(polarity_helpers::value_setter),
+                getFunction: Some(polarity_helpers::value_getter),
+            },
+            D2D1_PROPERTY_BINDING {
+                propertyName: w!("StopCount"),
+                setFunction: Some(stop_count_helpers::value_setter),
+                getFunction: Some(stop_count_helpers::value_getter),
+            },
+            D2D1_PROPERTY_BINDING {
+                propertyName: w!("Colors"),
+                setFunction: Some(colors_helpers::value_setter),
+                getFunction: Some(colors_helpers::value_getter),
+            },
+            D2D1_PROPERTY_BINDING {
+                propertyName: w!("Positions"),
+                setFunction: Some(positions_helpers::value_setter),
+                getFunction: Some(positions_helpers::value_getter),
+            },
+        ];
+
+        unsafe {
+            factory.RegisterEffectFromString(
+                &COLORIZE_EFFECT_CLSID,
+                COLORIZE_EFFECT_XML,
+                Some(&bindings),
+                Some(Self::create_effect),
+            )?;
+        }
+        Ok(())
+    }
+    unsafe extern "system" fn create_effect(effectimpl: *mut Option<IUnknown>) -> HRESULT {
+        // This gets us the base vtable in ColorizeEffect_Impl
+        let effect_unknown: IUnknown = Self::new().into();
+        if let Some(effectimpl) = effectimpl.as_mut() {
+            *effectimpl = Some(effect_unknown);
+            S_OK
+        } else {
+            E_INVALIDARG
+        }
+    }
+    fn set_polarity(&mut self, polarity: u32) -> Result<()> {
+        if polarity > COLORIZE_POLARITY_DARK {
+            return E_INVALIDARG.ok();
+        }
+        self.constants.polarity = polarity;
+        Ok(())
+    }
+    fn get_polarity(&self) -> u32 {
+        self.constants.polarity
+    }
+    fn set_stop_count(&mut self, stop_count: u32) -> Result<()> {
+        if stop_count == 0 || stop_count as usize > COLORIZE_MAX_STOPS {
+            return E_INVALIDARG.ok();
+        }
+        self.constants.stop_count = stop_count;
+        Ok(())
+    }
+    fn get_stop_count(&self) -> u32 {
+        self.constants.stop_count
+    }
+    fn set_colors(&mut self, colors: [[f32; 4]; COLORIZE_MAX_STOPS]) -> Result<()> {
+        self.constants.colors = colors;
+        Ok(())
+    }
+    fn get_colors(&self) -> [[f32; 4]; COLORIZE_MAX_STOPS] {
+        self.constants.colors
+    }
+    fn set_positions(&mut self, positions: [f32; COLORIZE_MAX_STOPS]) -> Result<()> {
+        self.constants.positions = positions;
+        Ok(())
+    }
+    fn get_positions(&self) -> [f32; COLORIZE_MAX_STOPS] {
+        self.constants.positions
+    }
+}
+
+const COLORIZE_EFFECT_XML: PCWSTR = w!(r#"<?xml version='1.0'?>
+<Effect>
+    <Property name='DisplayName' type='string' value='ColorizeEffect'/>
+    <Property name='Author' type='string' value='Contoso'/>
+    <Property name='Category' type='string' value='Stylize'/>
+    <Property name='Description' type='string' value='Maps the lines of an edge image through a gradient.'/>
+    <Inputs>
+        <Input name='Source'/>
+    </Inputs>
+    <Property name='Polarity' type='enum'>
+        <Property name='DisplayName' type='string' value='Polarity'/>
+        <Property name='Default' type='enum' value='0' />
+        <Fields>
+            <Field name='Light' displayname='Light lines' index='0' />
+            <Field name='Dark' displayname='Dark lines' index='1' />
+        </Fields>
+    </Property>
+    <Property name='StopCount' type='uint32'>
+        <Property name='DisplayName' type='string' value='StopCount'/>
+        <Property name='Min' type='uint32' value='1' />
+        <Property name='Max' type='uint32' value='8' />
+        <Property name='Default' type='uint32' value='2' />
+    </Property>
+    <Property name='Colors' type='blob'>
+        <Property name='DisplayName' type='string' value='Colors'/>
+    </Property>
+    <Property name='Positions' type='blob'>
+        <Property name='DisplayName' type='string' value='Positions'/>
+    </Property>
+</Effect>"#);
+
+create_setter_helpers!(
+    ColorizeEffect_Impl,
+    set_polarity,
+    get_polarity,
+    u32,
+    polarity_helpers
+);
+create_setter_helpers!(
+    ColorizeEffect_Impl,
+    set_stop_count,
+    get_stop_count,
+    u32,
+    stop_count_helpers
+);
+create_setter_helpers!(
+    ColorizeEffect_Impl,
+    set_colors,
+    get_colors,
+    [[f32; 4]; COLORIZE_MAX_STOPS],
+    colors_helpers
+);
+create_setter_helpers!(
+    ColorizeEffect_Impl,
+    set_positions,
+    get_positions,
+    [f32; COLORIZE_MAX_STOPS],
+    positions_helpers
+);
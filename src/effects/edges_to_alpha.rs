@@ -0,0 +1,115 @@
+use windows::{
+    core::{implement, w, IUnknown, Result, GUID, HRESULT, PCWSTR},
+    Win32::{
+        Foundation::{E_INVALIDARG, S_OK},
+        Graphics::Direct2D::{
+            ID2D1DrawInfo, ID2D1DrawTransform, ID2D1EffectImpl, ID2D1Factory1,
+            D2D1_PROPERTY_BINDING,
+        },
+    },
+};
+
+pub const EDGES_TO_ALPHA_EFFECT_CLSID: GUID =
+    GUID::from_u128(0x50C2D47F_85B7_45FF_AF3B_A2DCC1ABE67D);
+pub const EDGES_TO_ALPHA_EFFECT_SHADER: GUID =
+    GUID::from_u128(0x1E69D83C_938A_45C6_8D76_E8F4386DC33D);
+
+pub const EDGES_TO_ALPHA_PROP_POLARITY: u32 = 0;
+
+pub const EDGES_TO_ALPHA_POLARITY_LIGHT: u32 = 0;
+pub const EDGES_TO_ALPHA_POLARITY_DARK: u32 = 1;
+
+/// Turns the light or dark lines of an edge image into black lines on a
+/// transparent background, for compositing over other images.
+#[implement(ID2D1EffectImpl, ID2D1DrawTransform)]
+pub struct EdgesToAlphaEffect {
+    constants: EdgesToAlphaEffectConstants,
+    draw_info: Option<ID2D1DrawInfo>,
+}
+
+#[repr(C)]
+struct EdgesToAlphaEffectConstants {
+    polarity: u32,
+}
+
+impl_pixel_shader_transform!(
+    EdgesToAlphaEffect_Impl,
+    EDGES_TO_ALPHA_EFFECT_SHADER,
+    shaders::edges_to_alpha_pixel_shader(),
+    1
+);
+
+impl EdgesToAlphaEffect {
+    fn new() -> Self {
+        Self {
+            constants: EdgesToAlphaEffectConstants {
+                polarity: EDGES_TO_ALPHA_POLARITY_LIGHT,
+            },
+            draw_info: None,
+        }
+    }
+    pub fn register(factory: &ID2D1Factory1) -> Result<()> {
+        let bindings = [D2D1_PROPERTY_BINDING {
+            propertyName: w!("Polarity"),
+            setFunction: Some(polarity_helpers::value_setter),
+            getFunction: Some(polarity_helpers::value_getter),
+        }];
+
+        unsafe {
+            factory.RegisterEffectFromString(
+                &EDGES_TO_ALPHA_EFFECT_CLSID,
+                EDGES_TO_ALPHA_EFFECT_XML,
+                Some(&bindings),
+                Some(Self::create_effect),
+            )?;
+        }
+        Ok(())
+    }
+    unsafe extern "system" fn create_effect(effectimpl: *mut Option<IUnknown>) -> HRESULT {
+        // This gets us the base vtable in EdgesToAlphaEffect_Impl
+        let effect_unknown: IUnknown = Self::new().into();
+        if let Some(effectimpl) = effectimpl.as_mut() {
+            *effectimpl = Some(effect_unknown);
+            S_OK
+        } else {
+            E_INVALIDARG
+        }
+    }
+    fn set_polarity(&mut self, polarity: u32) -> Result<()> {
+        if polarity > EDGES_TO_ALPHA_POLARITY_DARK {
+            return E_INVALIDARG.ok();
+        }
+        self.constants.polarity = polarity;
+        Ok(())
+    }
+    fn get_polarity(&self) -> u32 {
+        self.constants.polarity
+    }
+}
+
+const EDGES_TO_ALPHA_EFFECT_XML: PCWSTR = w!(r#"<?xml version='1.0'?>
+<Effect>
+    <Property name='DisplayName' type='string' value='EdgesToAlphaEffect'/>
+    <Property name='Author' type='string' value='Contoso'/>
+    <Property name='Category' type='string' value='Stylize'/>
+    <Property name='Description' type='string' value='Draws the lines of an edge image on a transparent background.'/>
+    <Inputs>
+        <Input name='Source'/>
+    </Inputs>
+    <Property name='Polarity' type='enum'>
+        <Property name='DisplayName' type='string' value='Polarity'/>
+        <Property name='Default' type='enum' value='0' />
+        <Fields>
+            <Field name='Light' displayname='Light lines' index='0' />
+            <Field name='Dark' displayname='Dark lines' index='1' />
+        </Fields>
+    </Property>
+</Effect>"#);
+
+create_setter_helpers!(
+    EdgesToAlphaEffect_Impl,
+    set_polarity,
+    get_polarity,
+    u32,
+    polarity_helpers
+);
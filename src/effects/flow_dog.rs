@@ -0,0 +1,155 @@
+use windows::{
+    core::{implement, w, IUnknown, Result, GUID, HRESULT, PCWSTR},
+    Win32::{
+        Foundation::{E_INVALIDARG, S_OK},
+        Graphics::Direct2D::{
+            ID2D1DrawInfo, ID2D1DrawTransform, ID2D1EffectImpl, ID2D1Factory1,
+            D2D1_PROPERTY_BINDING,
+        },
+    },
+};
+
+pub const FLOW_DOG_EFFECT_CLSID: GUID = GUID::from_u128(0xCF92AA03_525A_4769_ADB0_1B2FC50C90F1);
+pub const FLOW_DOG_EFFECT_SHADER: GUID = GUID::from_u128(0x3CCB54BC_6A0F_400D_924B_581861A74246);
+
+pub const FLOW_DOG_PROP_SIGMA_1: u32 = 0;
+pub const FLOW_DOG_PROP_SIGMA_2: u32 = 1;
+
+/// Difference of Gaussians sampled across the edge tangent flow. Input 0 is
+/// the image and input 1 the blurred output of the structure tensor effect.
+#[implement(ID2D1EffectImpl, ID2D1DrawTransform)]
+pub struct FlowDoGEffect {
+    constants: FlowDoGEffectConstants,
+    draw_info: Option<ID2D1DrawInfo>,
+}
+
+#[repr(C)]
+struct FlowDoGEffectConstants {
+    sigma_1: f32,
+    sigma_2: f32,
+    /// Samples taken on either side of the pixel, 3 times the largest sigma
+    radius: i32,
+}
+
+impl_pixel_shader_transform!(
+    FlowDoGEffect_Impl,
+    FLOW_DOG_EFFECT_SHADER,
+    shaders::flow_dog_pixel_shader(),
+    2,
+    |effect| effect.constants.radius,
+    opaque
+);
+
+impl FlowDoGEffect {
+    fn new() -> Self {
+        let mut effect = Self {
+            constants: FlowDoGEffectConstants {
+                sigma_1: 3.0,
+                sigma_2: 5.0,
+                radius: 0,
+            },
+            draw_info: None,
+        };
+        effect.update_radius();
+        effect
+    }
+    pub fn register(factory: &ID2D1Factory1) -> Result<()> {
+        let bindings = [
+            D2D1_PROPERTY_BINDING {
+                propertyName: w!("Sigma1"),
+                setFunction: Some(sigma_1_helpers::value_setter),
+                getFunction: Some(sigma_1_helpers::value_getter),
+            },
+            D2D1_PROPERTY_BINDING {
+                propertyName: w!("Sigma2"),
+                setFunction: Some(sigma_2_helpers::value_setter),
+                getFunction: Some(sigma_2_helpers::value_getter),
+            },
+        ];
+
+        unsafe {
+            factory.RegisterEffectFromString(
+                &FLOW_DOG_EFFECT_CLSID,
+                FLOW_DOG_EFFECT_XML,
+                Some(&bindings),
+                Some(Self::create_effect),
+            )?;
+        }
+        Ok(())
+    }
+    unsafe extern "system" fn create_effect(effectimpl: *mut Option<IUnknown>) -> HRESULT {
+        // This gets us the base vtable in FlowDoGEffect_Impl
+        let effect_unknown: IUnknown = Self::new().into();
+        if let Some(effectimpl) = effectimpl.as_mut() {
+            *effectimpl = Some(effect_unknown);
+            S_OK
+        } else {
+            E_INVALIDARG
+        }
+    }
+    fn update_radius(&mut self) {
+        let sigma = self.constants.sigma_1.max(self.constants.sigma_2);
+        self.constants.radius = (sigma * 3.0).ceil() as i32;
+    }
+    fn set_sigma_1(&mut self, sigma_1: f32) -> Result<()> {
+        if !(0.0..=250.0).contains(&sigma_1) {
+            return E_INVALIDARG.ok();
+        }
+        self.constants.sigma_1 = sigma_1;
+        self.update_radius();
+        Ok(())
+    }
+    fn get_sigma_1(&self) -> f32 {
+        self.constants.sigma_1
+    }
+    fn set_sigma_2(&mut self, sigma_2: f32) -> Result<()> {
+        if !(0.0..=250.0).contains(&sigma_2) {
+            return E_INVALIDARG.ok();
+        }
+        self.constants.sigma_2 = sigma_2;
+        self.update_radius();
+        Ok(())
+    }
+    fn get_sigma_2(&self) -> f32 {
+        self.constants.sigma_2
+    }
+}
+
+const FLOW_DOG_EFFECT_XML: PCWSTR = w!(r#"<?xml version='1.0'?>
+<Effect>
+    <Property name='DisplayName' type='string' value='FlowDoGEffect'/>
+    <Property name='Author' type='string' value='Contoso'/>
+    <Property name='Category' type='string' value='Stylize'/>
+    <Property name='Description' type='string' value='Difference of Gaussians across the edge tangent flow.'/>
+    <Inputs>
+        <Input name='Source'/>
+        <Input name='Flow'/>
+    </Inputs>
+    <Property name='Sigma1' type='float'>
+        <Property name='DisplayName' type='string' value='Sigma1'/>
+        <Property name='Min' type='float' value='0.0' />
+        <Property name='Max' type='float' value='250.0' />
+        <Property name='Default' type='float' value='3.0' />
+    </Property>
+    <Property name='Sigma2' type='float'>
+        <Property name='DisplayName' type='string' value='Sigma2'/>
+        <Property name='Min' type='float' value='0.0' />
+        <Property name='Max' type='float' value='250.0' />
+        <Property name='Default' type='float' value='5.0' />
+    </Property>
+</Effect>"#);
+
+create_setter_helpers!(
+    FlowDoGEffect_Impl,
+    set_sigma_1,
+    get_sigma_1,
+    f32,
+    sigma_1_helpers
+);
+create_setter_helpers!(
+    FlowDoGEffect_Impl,
+    set_sigma_2,
+    get_sigma_2,
+    f32,
+    sigma_2_helpers
+);
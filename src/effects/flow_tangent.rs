@@ -0,0 +1,115 @@
+use windows::{
+    core::{implement, w, IUnknown, Result, GUID, HRESULT, PCWSTR},
+    Win32::{
+        Foundation::{E_INVALIDARG, S_OK},
+        Graphics::Direct2D::{
+            ID2D1DrawInfo, ID2D1DrawTransform, ID2D1EffectImpl, ID2D1Factory1,
+            D2D1_PROPERTY_BINDING,
+        },
+    },
+};
+
+pub const FLOW_TANGENT_EFFECT_CLSID: GUID = GUID::from_u128(0x4C87B98A_9EAF_4BDD_81FC_292A521218BA);
+pub const FLOW_TANGENT_EFFECT_SHADER: GUID =
+    GUID::from_u128(0xCBB2FAB8_3255_4A06_A602_18B4EBB81D38);
+
+pub const FLOW_TANGENT_PROP_ENCODING: u32 = 0;
+
+pub const FLOW_TANGENT_ENCODING_HUE: u32 = 0;
+pub const FLOW_TANGENT_ENCODING_VECTOR: u32 = 1;
+
+/// Draws the tangent direction of a blurred structure tensor, either as a
+/// hue or as a vector in the red and green channels.
+#[implement(ID2D1EffectImpl, ID2D1DrawTransform)]
+pub struct FlowTangentEffect {
+    constants: FlowTangentEffectConstants,
+    draw_info: Option<ID2D1DrawInfo>,
+}
+
+#[repr(C)]
+struct FlowTangentEffectConstants {
+    encoding: u32,
+}
+
+impl_pixel_shader_transform!(
+    FlowTangentEffect_Impl,
+    FLOW_TANGENT_EFFECT_SHADER,
+    shaders::flow_tangent_pixel_shader(),
+    1,
+    opaque
+);
+
+impl FlowTangentEffect {
+    fn new() -> Self {
+        Self {
+            constants: FlowTangentEffectConstants {
+                encoding: FLOW_TANGENT_ENCODING_HUE,
+            },
+            draw_info: None,
+        }
+    }
+    pub fn register(factory: &ID2D1Factory1) -> Result<()> {
+        let bindings = [D2D1_PROPERTY_BINDING {
+            propertyName: w!("Encoding"),
+            setFunction: Some(encoding_helpers::value_setter),
+            getFunction: Some(encoding_helpers::value_getter),
+        }];
+
+        unsafe {
+            factory.RegisterEffectFromString(
+                &FLOW_TANGENT_EFFECT_CLSID,
+                FLOW_TANGENT_EFFECT_XML,
+                Some(&bindings),
+                Some(Self::create_effect),
+            )?;
+        }
+        Ok(())
+    }
+    unsafe extern "system" fn create_effect(effectimpl: *mut Option<IUnknown>) -> HRESULT {
+        // This gets us the base vtable in FlowTangentEffect_Impl
+        let effect_unknown: IUnknown = Self::new().into();
+        if let Some(effectimpl) = effectimpl.as_mut() {
+            *effectimpl = Some(effect_unknown);
+            S_OK
+        } else {
+            E_INVALIDARG
+        }
+    }
+    fn set_encoding(&mut self, encoding: u32) -> Result<()> {
+        if encoding > FLOW_TANGENT_ENCODING_VECTOR {
+            return E_INVALIDARG.ok();
+        }
+        self.constants.encoding = encoding;
+        Ok(())
+    }
+    fn get_encoding(&self) -> u32 {
+        self.constants.encoding
+    }
+}
+
+const FLOW_TANGENT_EFFECT_XML: PCWSTR = w!(r#"<?xml version='1.0'?>
+<Effect>
+    <Property name='DisplayName' type='string' value='FlowTangentEffect'/>
+    <Property name='Author' type='string' value='Contoso'/>
+    <Property name='Category' type='string' value='Stylize'/>
+    <Property name='Description' type='string' value='Draws the tangent direction of the edge tangent flow.'/>
+    <Inputs>
+        <Input name='Flow'/>
+    </Inputs>
+    <Property name='Encoding' type='enum'>
+        <Property name='DisplayName' type='string' value='Encoding'/>
+        <Property name='Default' type='enum' value='0' />
+        <Fields>
+            <Field name='Hue' displayname='Hue' index='0' />
+            <Field name='Vector' displayname='Vector' index='1' />
+        </Fields>
+    </Property>
+</Effect>"#);
+
+create_setter_helpers!(
+    FlowTangentEffect_Impl,
+    set_encoding,
+    get_encoding,
+    u32,
+    encoding_helpers
+);
@@ -0,0 +1,115 @@
+use windows::{
+    core::{implement, w, IUnknown, Result, GUID, HRESULT, PCWSTR},
+    Win32::{
+        Foundation::{E_INVALIDARG, S_OK},
+        Graphics::Direct2D::{
+            ID2D1DrawInfo, ID2D1DrawTransform, ID2D1EffectImpl, ID2D1Factory1,
+            D2D1_PROPERTY_BINDING,
+        },
+    },
+};
+
+pub const LUMINANCE_EFFECT_CLSID: GUID = GUID::from_u128(0x37DC3D0C_0947_44EA_9CC3_2ABD9906CF77);
+pub const LUMINANCE_EFFECT_SHADER: GUID = GUID::from_u128(0x87E25A41_A9A1_4FA6_BB1D_24A2350B8BA1);
+
+pub const LUMINANCE_PROP_MODEL: u32 = 0;
+
+pub const LUMINANCE_MODEL_REC601: u32 = 0;
+pub const LUMINANCE_MODEL_REC709: u32 = 1;
+pub const LUMINANCE_MODEL_LAB: u32 = 2;
+
+/// Replaces the color channels with Rec. 601 or Rec. 709 luma or with CIELAB
+/// lightness, keeping alpha.
+#[implement(ID2D1EffectImpl, ID2D1DrawTransform)]
+pub struct LuminanceEffect {
+    constants: LuminanceEffectConstants,
+    draw_info: Option<ID2D1DrawInfo>,
+}
+
+#[repr(C)]
+struct LuminanceEffectConstants {
+    model: u32,
+}
+
+impl_pixel_shader_transform!(
+    LuminanceEffect_Impl,
+    LUMINANCE_EFFECT_SHADER,
+    shaders::luminance_pixel_shader(),
+    1
+);
+
+impl LuminanceEffect {
+    fn new() -> Self {
+        Self {
+            constants: LuminanceEffectConstants {
+                model: LUMINANCE_MODEL_REC601,
+            },
+            draw_info: None,
+        }
+    }
+    pub fn register(factory: &ID2D1Factory1) -> Result<()> {
+        let bindings = [D2D1_PROPERTY_BINDING {
+            propertyName: w!("Model"),
+            setFunction: Some(model_helpers::value_setter),
+            getFunction: Some(model_helpers::value_getter),
+        }];
+
+        unsafe {
+            factory.RegisterEffectFromString(
+                &LUMINANCE_EFFECT_CLSID,
+                LUMINANCE_EFFECT_XML,
+                Some(&bindings),
+                Some(Self::create_effect),
+            )?;
+        }
+        Ok(())
+    }
+    unsafe extern "system" fn create_effect(effectimpl: *mut Option<IUnknown>) -> HRESULT {
+        // This gets us the base vtable in LuminanceEffect_Impl
+        let effect_unknown: IUnknown = Self::new().into();
+        if let Some(effectimpl) = effectimpl.as_mut() {
+            *effectimpl = Some(effect_unknown);
+            S_OK
+        } else {
+            E_INVALIDARG
+        }
+    }
+    fn set_model(&mut self, model: u32) -> Result<()> {
+        if model > LUMINANCE_MODEL_LAB {
+            return E_INVALIDARG.ok();
+        }
+        self.constants.model = model;
+        Ok(())
+    }
+    fn get_model(&self) -> u32 {
+        self.constants.model
+    }
+}
+
+const LUMINANCE_EFFECT_XML: PCWSTR = w!(r#"<?xml version='1.0'?>
+<Effect>
+    <Property name='DisplayName' type='string' value='LuminanceEffect'/>
+    <Property name='Author' type='string' value='Contoso'/>
+    <Property name='Category' type='string' value='Stylize'/>
+    <Property name='Description' type='string' value='Converts an image to luma or CIELAB lightness.'/>
+    <Inputs>
+        <Input name='Source'/>
+    </Inputs>
+    <Property name='Model' type='enum'>
+        <Property name='DisplayName' type='string' value='Model'/>
+        <Property name='Default' type='enum' value='0' />
+        <Fields>
+            <Field name='Rec601' displayname='Rec. 601' index='0' />
+            <Field name='Rec709' displayname='Rec. 709' index='1' />
+            <Field name='Lab' displayname='CIELAB L*' index='2' />
+        </Fields>
+    </Property>
+</Effect>"#);
+
+create_setter_helpers!(
+    LuminanceEffect_Impl,
+    set_model,
+    get_model,
+    u32,
+    model_helpers
+);
@@ -1,5 +1,21 @@
+use anisotropic_kuwahara::AnisotropicKuwaharaEffect;
+use bilateral::BilateralEffect;
+use coherence::CoherenceEffect;
+use colorize::ColorizeEffect;
+use edges_to_alpha::EdgesToAlphaEffect;
+use flow_blur::FlowBlurEffect;
+use flow_dog::FlowDoGEffect;
+use flow_tangent::FlowTangentEffect;
+use luminance::LuminanceEffect;
+use morphology::MorphologyEffect;
+use quantize::QuantizeEffect;
+use signed_difference::SignedDifferenceEffect;
+use structure_tensor::StructureTensorEffect;
 use threshold::ThresholdEffect;
+use unpremultiply::UnpremultiplyEffect;
 use windows::{core::Result, Win32::Graphics::Direct2D::ID2D1Factory1};
+use xdog::XDoGEffect;
+use zero_crossing::ZeroCrossingEffect;
 
 macro_rules! create_setter_helpers {
     ($impl_wraper:ty, $setter_method:ident, $getter_method:ident, $value_ty:ty, $helper_mod:ident) => {
@@ -90,9 +106,277 @@ macro_rules! create_setter_helpers {
     };
 }
 
+/// Implements the D2D transform interfaces for an effect that renders a
+/// single pixel shader over inputs that all share the output's bounds. The
+/// effect struct needs a `constants` field holding the shader's constant
+/// buffer and a `draw_info: Option<ID2D1DrawInfo>` field.
+///
+/// Shaders that sample around the current pixel pass a closure computing how
+/// many pixels of each input they read past the output rect, e.g.
+/// `|effect| effect.radius()`. Their inputs have to be declared as
+/// `D2D_INPUTn_COMPLEX`.
+///
+/// Shaders that always write an alpha of 1 end the list with `opaque`. The
+/// others may pass translucent input through or emit it themselves, so they
+/// report no opaque area to D2D.
+macro_rules! impl_pixel_shader_transform {
+    // Parsing `@transform` as a type is a hard error, so this arm goes first
+    (@transform $impl_wraper:ty, $shader_guid:expr, $shader_bytes:expr, $input_count:expr, |$effect:ident| $margin:expr, $opaque:expr) => {
+        impl windows::Win32::Graphics::Direct2D::ID2D1EffectImpl_Impl for $impl_wraper {
+            fn Initialize(
+                &self,
+                effectcontext: Option<&windows::Win32::Graphics::Direct2D::ID2D1EffectContext>,
+                transformgraph: Option<&windows::Win32::Graphics::Direct2D::ID2D1TransformGraph>,
+            ) -> windows::core::Result<()> {
+                use windows_core::Interface;
+                let (Some(effect_context), Some(transform_graph)) = (effectcontext, transformgraph)
+                else {
+                    return windows::Win32::Foundation::E_INVALIDARG.ok();
+                };
+                unsafe {
+                    effect_context.LoadPixelShader(&$shader_guid, $shader_bytes)?;
+                    // Our base vtable is the IUnknown/IInspectable one
+                    let unknown = std::mem::transmute::<&Self, windows::core::IUnknown>(self);
+                    let transform_node: windows::Win32::Graphics::Direct2D::ID2D1TransformNode =
+                        unknown.cast()?;
+                    std::mem::forget(unknown); // Don't mess up our ref count
+                    transform_graph.SetSingleTransformNode(&transform_node)?;
+                }
+                Ok(())
+            }
+
+            fn PrepareForRender(
+                &self,
+                _changetype: windows::Win32::Graphics::Direct2D::D2D1_CHANGE_TYPE,
+            ) -> windows::core::Result<()> {
+                if let Some(draw_info) = self.this.draw_info.as_ref() {
+                    let len = std::mem::size_of_val(&self.this.constants);
+                    if len == 0 {
+                        return Ok(());
+                    }
+                    unsafe {
+                        let slice = std::slice::from_raw_parts(
+                            &self.this.constants as *const _ as *const u8,
+                            len,
+                        );
+                        draw_info.SetPixelShaderConstantBuffer(slice)?;
+                    }
+                    Ok(())
+                } else {
+                    // D2D always hands us the draw info before rendering
+                    windows::Win32::Foundation::E_UNEXPECTED.ok()
+                }
+            }
+
+            fn SetGraph(
+                &self,
+                _transformgraph: Option<&windows::Win32::Graphics::Direct2D::ID2D1TransformGraph>,
+            ) -> windows::core::Result<()> {
+                // We always have a single transform node
+                windows::Win32::Foundation::E_NOTIMPL.ok()
+            }
+        }
+
+        impl windows::Win32::Graphics::Direct2D::ID2D1DrawTransform_Impl for $impl_wraper {
+            fn SetDrawInfo(
+                &self,
+                drawinfo: Option<&windows::Win32::Graphics::Direct2D::ID2D1DrawInfo>,
+            ) -> windows::core::Result<()> {
+                if let Some(draw_info) = drawinfo {
+                    unsafe {
+                        // TODO: Safely do this
+                        ((self as *const Self as *mut Self).as_mut().unwrap())
+                            .this
+                            .draw_info = Some(draw_info.clone());
+                        draw_info.SetPixelShader(
+                            &$shader_guid,
+                            windows::Win32::Graphics::Direct2D::D2D1_PIXEL_OPTIONS_NONE,
+                        )?;
+                    }
+                    Ok(())
+                } else {
+                    windows::Win32::Foundation::E_INVALIDARG.ok()
+                }
+            }
+        }
+
+        impl windows::Win32::Graphics::Direct2D::ID2D1Transform_Impl for $impl_wraper {
+            fn MapOutputRectToInputRects(
+                &self,
+                outputrect: *const windows::Win32::Foundation::RECT,
+                inputrects: *mut windows::Win32::Foundation::RECT,
+                inputrectscount: u32,
+            ) -> windows::core::Result<()> {
+                use windows::Win32::Foundation::E_INVALIDARG;
+                if inputrectscount != $input_count || inputrects.is_null() {
+                    return E_INVALIDARG.ok();
+                }
+
+                let output_rect = unsafe {
+                    outputrect
+                        .as_ref()
+                        .map(|x| Ok(x))
+                        .unwrap_or(Err(E_INVALIDARG))?
+                };
+                let input_rects =
+                    unsafe { std::slice::from_raw_parts_mut(inputrects, inputrectscount as usize) };
+                let $effect = &self.this;
+                let margin: i32 = $margin;
+                for input_rect in input_rects {
+                    *input_rect = windows::Win32::Foundation::RECT {
+                        left: output_rect.left.saturating_sub(margin),
+                        top: output_rect.top.saturating_sub(margin),
+                        right: output_rect.right.saturating_add(margin),
+                        bottom: output_rect.bottom.saturating_add(margin),
+                    };
+                }
+
+                Ok(())
+            }
+
+            fn MapInputRectsToOutputRect(
+                &self,
+                inputrects: *const windows::Win32::Foundation::RECT,
+                _inputopaquesubrects: *const windows::Win32::Foundation::RECT,
+                inputrectcount: u32,
+                outputrect: *mut windows::Win32::Foundation::RECT,
+                outputopaquesubrect: *mut windows::Win32::Foundation::RECT,
+            ) -> windows::core::Result<()> {
+                use windows::Win32::Foundation::E_INVALIDARG;
+                if inputrectcount != $input_count {
+                    return E_INVALIDARG.ok();
+                }
+
+                // All of our inputs cover the same area, so the first one
+                // decides the output bounds.
+                let input_rect = unsafe {
+                    inputrects
+                        .as_ref()
+                        .map(|x| Ok(x))
+                        .unwrap_or(Err(E_INVALIDARG))?
+                };
+                let output_rect = unsafe {
+                    outputrect
+                        .as_mut()
+                        .map(|x| Ok(x))
+                        .unwrap_or(Err(E_INVALIDARG))?
+                };
+                *output_rect = *input_rect;
+                let output_opaque_rect = unsafe {
+                    outputopaquesubrect
+                        .as_mut()
+                        .map(|x| Ok(x))
+                        .unwrap_or(Err(E_INVALIDARG))?
+                };
+                *output_opaque_rect = if $opaque {
+                    *input_rect
+                } else {
+                    windows::Win32::Foundation::RECT::default()
+                };
+
+                Ok(())
+            }
+
+            fn MapInvalidRect(
+                &self,
+                _inputindex: u32,
+                invalidinputrect: &windows::Win32::Foundation::RECT,
+            ) -> windows::core::Result<windows::Win32::Foundation::RECT> {
+                // Every output pixel within the margin read the invalid input
+                let $effect = &self.this;
+                let margin: i32 = $margin;
+                Ok(windows::Win32::Foundation::RECT {
+                    left: invalidinputrect.left.saturating_sub(margin),
+                    top: invalidinputrect.top.saturating_sub(margin),
+                    right: invalidinputrect.right.saturating_add(margin),
+                    bottom: invalidinputrect.bottom.saturating_add(margin),
+                })
+            }
+        }
+
+        impl windows::Win32::Graphics::Direct2D::ID2D1TransformNode_Impl for $impl_wraper {
+            fn GetInputCount(&self) -> u32 {
+                $input_count
+            }
+        }
+    };
+    ($impl_wraper:ty, $shader_guid:expr, $shader_bytes:expr, $input_count:expr) => {
+        impl_pixel_shader_transform!(
+            @transform $impl_wraper,
+            $shader_guid,
+            $shader_bytes,
+            $input_count,
+            |_effect| 0,
+            false
+        );
+    };
+    ($impl_wraper:ty, $shader_guid:expr, $shader_bytes:expr, $input_count:expr, opaque) => {
+        impl_pixel_shader_transform!(
+            @transform $impl_wraper,
+            $shader_guid,
+            $shader_bytes,
+            $input_count,
+            |_effect| 0,
+            true
+        );
+    };
+    ($impl_wraper:ty, $shader_guid:expr, $shader_bytes:expr, $input_count:expr, |$effect:ident| $margin:expr, opaque) => {
+        impl_pixel_shader_transform!(
+            @transform $impl_wraper,
+            $shader_guid,
+            $shader_bytes,
+            $input_count,
+            |$effect| $margin,
+            true
+        );
+    };
+    ($impl_wraper:ty, $shader_guid:expr, $shader_bytes:expr, $input_count:expr, |$effect:ident| $margin:expr) => {
+        impl_pixel_shader_transform!(
+            @transform $impl_wraper,
+            $shader_guid,
+            $shader_bytes,
+            $input_count,
+            |$effect| $margin,
+            false
+        );
+    };
+}
+
+pub mod anisotropic_kuwahara;
+pub mod bilateral;
+pub mod coherence;
+pub mod colorize;
+pub mod edges_to_alpha;
+pub mod flow_blur;
+pub mod flow_dog;
+pub mod flow_tangent;
+pub mod luminance;
+pub mod morphology;
+pub mod quantize;
+pub mod signed_difference;
+pub mod structure_tensor;
 pub mod threshold;
+pub mod unpremultiply;
+pub mod xdog;
+pub mod zero_crossing;
 
 pub fn register_custom_effects(d2d_factory: &ID2D1Factory1) -> Result<()> {
     ThresholdEffect::register(&d2d_factory)?;
+    XDoGEffect::register(&d2d_factory)?;
+    SignedDifferenceEffect::register(&d2d_factory)?;
+    StructureTensorEffect::register(&d2d_factory)?;
+    FlowDoGEffect::register(&d2d_factory)?;
+    FlowBlurEffect::register(&d2d_factory)?;
+    FlowTangentEffect::register(&d2d_factory)?;
+    CoherenceEffect::register(&d2d_factory)?;
+    ZeroCrossingEffect::register(&d2d_factory)?;
+    LuminanceEffect::register(&d2d_factory)?;
+    UnpremultiplyEffect::register(&d2d_factory)?;
+    EdgesToAlphaEffect::register(&d2d_factory)?;
+    ColorizeEffect::register(&d2d_factory)?;
+    QuantizeEffect::register(&d2d_factory)?;
+    BilateralEffect::register(&d2d_factory)?;
+    AnisotropicKuwaharaEffect::register(&d2d_factory)?;
+    MorphologyEffect::register(&d2d_factory)?;
     Ok(())
 }
@@ -0,0 +1,186 @@
+use windows::{
+    core::{implement, w, IUnknown, Result, GUID, HRESULT, PCWSTR},
+    Win32::{
+        Foundation::{E_INVALIDARG, S_OK},
+        Graphics::Direct2D::{
+            ID2D1DrawInfo, ID2D1DrawTransform, ID2D1EffectImpl, ID2D1Factory1,
+            D2D1_PROPERTY_BINDING,
+        },
+    },
+};
+
+pub const MORPHOLOGY_EFFECT_CLSID: GUID = GUID::from_u128(0xFCC67D5F_9278_47EB_92F8_342B2A3F7744);
+pub const MORPHOLOGY_EFFECT_SHADER: GUID = GUID::from_u128(0xE698FB77_3EE2_4701_A3DE_3E6F6DDDB2EF);
+
+pub const MORPHOLOGY_PROP_OPERATION: u32 = 0;
+pub const MORPHOLOGY_PROP_SHAPE: u32 = 1;
+pub const MORPHOLOGY_PROP_RADIUS: u32 = 2;
+
+pub const MORPHOLOGY_OPERATION_ERODE: u32 = 0;
+pub const MORPHOLOGY_OPERATION_DILATE: u32 = 1;
+
+pub const MORPHOLOGY_SHAPE_SQUARE: u32 = 0;
+pub const MORPHOLOGY_SHAPE_DISK: u32 = 1;
+pub const MORPHOLOGY_SHAPE_DIAMOND: u32 = 2;
+
+/// Erosion or dilation of an edge image under a square, disk or diamond
+/// structuring element. `CLSID_D2D1Morphology` only supports rectangles and
+/// counts the transparent area outside the image, which erodes the borders.
+#[implement(ID2D1EffectImpl, ID2D1DrawTransform)]
+pub struct MorphologyEffect {
+    constants: MorphologyEffectConstants,
+    draw_info: Option<ID2D1DrawInfo>,
+}
+
+#[repr(C)]
+struct MorphologyEffectConstants {
+    operation: u32,
+    shape: u32,
+    radius: u32,
+}
+
+impl_pixel_shader_transform!(
+    MorphologyEffect_Impl,
+    MORPHOLOGY_EFFECT_SHADER,
+    shaders::morphology_pixel_shader(),
+    1,
+    |effect| effect.constants.radius as i32
+);
+
+impl MorphologyEffect {
+    fn new() -> Self {
+        Self {
+            constants: MorphologyEffectConstants {
+                operation: MORPHOLOGY_OPERATION_ERODE,
+                shape: MORPHOLOGY_SHAPE_SQUARE,
+                radius: 1,
+            },
+            draw_info: None,
+        }
+    }
+    pub fn register(factory: &ID2D1Factory1) -> Result<()> {
+        let bindings = [
+            D2D1_PROPERTY_BINDING {
+                propertyName: w!("Operation"),
+                setFunction: Some(operation_helpers::value_setter),
+                getFunction: Some(operation_helpers::value_getter),
+            },
+            D2D1_PROPERTY_BINDING {
+                propertyName: w!("Shape"),
+                setFunction: Some(shape_helpers::value_setter),
+                getFunction: Some(shape_helpers::value_getter),
+            },
+            D2D1_PROPERTY_BINDING {
+                propertyName: w!("Radius"),
+                setFunction: Some(radius_helpers::value_setter),
+                getFunction: Some(radius_helpers::value_getter),
+            },
+        ];
+
+        unsafe {
+            factory.RegisterEffectFromString(
+                &MORPHOLOGY_EFFECT_CLSID,
+                MORPHOLOGY_EFFECT_XML,
+                Some(&bindings),
+                Some(Self::create_effect),
+            )?;
+        }
+        Ok(())
+    }
+    unsafe extern "system" fn create_effect(effectimpl: *mut Option<IUnknown>) -> HRESULT {
+        // This gets us the base vtable in MorphologyEffect_Impl
+        let effect_unknown: IUnknown = Self::new().into();
+        if let Some(effectimpl) = effectimpl.as_mut() {
+            *effectimpl = Some(effect_unknown);
+            S_OK
+        } else {
+            E_INVALIDARG
+        }
+    }
+    fn set_operation(&mut self, operation: u32) -> Result<()> {
+        if operation > MORPHOLOGY_OPERATION_DILATE {
+            return E_INVALIDARG.ok();
+        }
+        self.constants.operation = operation;
+        Ok(())
+    }
+    fn get_operation(&self) -> u32 {
+        self.constants.operation
+    }
+    fn set_shape(&mut self, shape: u32) -> Result<()> {
+        if shape > MORPHOLOGY_SHAPE_DIAMOND {
+            return E_INVALIDARG.ok();
+        }
+        self.constants.shape = shape;
+        Ok(())
+    }
+    fn get_shape(&self) -> u32 {
+        self.constants.shape
+    }
+    fn set_radius(&mut self, radius: u32) -> Result<()> {
+        if radius > 32 {
+            return E_INVALIDARG.ok();
+        }
+        self.constants.radius = radius;
+        Ok(())
+    }
+    fn get_radius(&self) -> u32 {
+        self.constants.radius
+    }
+}
+
+const MORPHOLOGY_EFFECT_XML: PCWSTR = w!(r#"<?xml version='1.0'?>
+<Effect>
+    <Property name='DisplayName' type='string' value='MorphologyEffect'/>
+    <Property name='Author' type='string' value='Contoso'/>
+    <Property name='Category' type='string' value='Stylize'/>
+    <Property name='Description' type='string' value='Erodes or dilates an edge image.'/>
+    <Inputs>
+        <Input name='Source'/>
+    </Inputs>
+    <Property name='Operation' type='enum'>
+        <Property name='DisplayName' type='string' value='Operation'/>
+        <Property name='Default' type='enum' value='0' />
+        <Fields>
+            <Field name='Erode' displayname='Erode' index='0' />
+            <Field name='Dilate' displayname='Dilate' index='1' />
+        </Fields>
+    </Property>
+    <Property name='Shape' type='enum'>
+        <Property name='DisplayName' type='string' value='Shape'/>
+        <Property name='Default' type='enum' value='0' />
+        <Fields>
+            <Field name='Square' displayname='Square' index='0' />
+            <Field name='Disk' displayname='Disk' index='1' />
+            <Field name='Diamond' displayname='Diamond' index='2' />
+        </Fields>
+    </Property>
+    <Property name='Radius' type='uint32'>
+        <Property name='DisplayName' type='string' value='Radius'/>
+        <Property name='Min' type='uint32' value='0' />
+        <Property name='Max' type='uint32' value='32' />
+        <Property name='Default' type='uint32' value='1' />
+    </Property>
+</Effect>"#);
+
+create_setter_helpers!(
+    MorphologyEffect_Impl,
+    set_operation,
+    get_operation,
+    u32,
+    operation_helpers
+);
+create_setter_helpers!(
+    MorphologyEffect_Impl,
+    set_shape,
+    get_shape,
+    u32,
+    shape_helpers
+);
+create_setter_helpers!(
+    MorphologyEffect_Impl,
+    set_radius,
+    get_radius,
+    u32,
+    radius_helpers
+);
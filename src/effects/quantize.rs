@@ -0,0 +1,139 @@
+use windows::{
+    core::{implement, w, IUnknown, Result, GUID, HRESULT, PCWSTR},
+    Win32::{
+        Foundation::{E_INVALIDARG, S_OK},
+        Graphics::Direct2D::{
+            ID2D1DrawInfo, ID2D1DrawTransform, ID2D1EffectImpl, ID2D1Factory1,
+            D2D1_PROPERTY_BINDING,
+        },
+    },
+};
+
+pub const QUANTIZE_EFFECT_CLSID: GUID = GUID::from_u128(0x856A2717_387E_4BAB_9685_F54B97BDE9A2);
+pub const QUANTIZE_EFFECT_SHADER: GUID = GUID::from_u128(0xDCFFDEF0_921E_4751_BD3C_2B4704F7551C);
+
+pub const QUANTIZE_PROP_LEVELS: u32 = 0;
+pub const QUANTIZE_PROP_SHARPNESS: u32 = 1;
+
+/// Soft luminance quantization. Snaps the luma of every pixel to one of
+/// `Levels` bands through a `tanh` ramp as steep as `Sharpness`, keeping the
+/// hue.
+#[implement(ID2D1EffectImpl, ID2D1DrawTransform)]
+pub struct QuantizeEffect {
+    constants: QuantizeEffectConstants,
+    draw_info: Option<ID2D1DrawInfo>,
+}
+
+#[repr(C)]
+struct QuantizeEffectConstants {
+    levels: u32,
+    sharpness: f32,
+}
+
+impl_pixel_shader_transform!(
+    QuantizeEffect_Impl,
+    QUANTIZE_EFFECT_SHADER,
+    shaders::quantize_pixel_shader(),
+    1
+);
+
+impl QuantizeEffect {
+    fn new() -> Self {
+        Self {
+            constants: QuantizeEffectConstants {
+                levels: 4,
+                sharpness: 8.0,
+            },
+            draw_info: None,
+        }
+    }
+    pub fn register(factory: &ID2D1Factory1) -> Result<()> {
+        let bindings = [
+            D2D1_PROPERTY_BINDING {
+                propertyName: w!("Levels"),
+                setFunction: Some(levels_helpers::value_setter),
+                getFunction: Some(levels_helpers::value_getter),
+            },
+            D2D1_PROPERTY_BINDING {
+                propertyName: w!("Sharpness"),
+                setFunction: Some(sharpness_helpers::value_setter),
+                getFunction: Some(sharpness_helpers::value_getter),
+            },
+        ];
+
+        unsafe {
+            factory.RegisterEffectFromString(
+                &QUANTIZE_EFFECT_CLSID,
+                QUANTIZE_EFFECT_XML,
+                Some(&bindings),
+                Some(Self::create_effect),
+            )?;
+        }
+        Ok(())
+    }
+    unsafe extern "system" fn create_effect(effectimpl: *mut Option<IUnknown>) -> HRESULT {
+        // This gets us the base vtable in QuantizeEffect_Impl
+        let effect_unknown: IUnknown = Self::new().into();
+        if let Some(effectimpl) = effectimpl.as_mut() {
+            *effectimpl = Some(effect_unknown);
+            S_OK
+        } else {
+            E_INVALIDARG
+        }
+    }
+    fn set_levels(&mut self, levels: u32) -> Result<()> {
+        if levels < 2 {
+            return E_INVALIDARG.ok();
+        }
+        self.constants.levels = levels;
+        Ok(())
+    }
+    fn get_levels(&self) -> u32 {
+        self.constants.levels
+    }
+    fn set_sharpness(&mut self, sharpness: f32) -> Result<()> {
+        self.constants.sharpness = sharpness;
+        Ok(())
+    }
+    fn get_sharpness(&self) -> f32 {
+        self.constants.sharpness
+    }
+}
+
+const QUANTIZE_EFFECT_XML: PCWSTR = w!(r#"<?xml version='1.0'?>
+<Effect>
+    <Property name='DisplayName' type='string' value='QuantizeEffect'/>
+    <Property name='Author' type='string' value='Contoso'/>
+    <Property name='Category' type='string' value='Stylize'/>
+    <Property name='Description' type='string' value='Soft quantization of the luminance into flat bands.'/>
+    <Inputs>
+        <Input name='Source'/>
+    </Inputs>
+    <Property name='Levels' type='uint32'>
+        <Property name='DisplayName' type='string' value='Levels'/>
+        <Property name='Min' type='uint32' value='2' />
+        <Property name='Max' type='uint32' value='256' />
+        <Property name='Default' type='uint32' value='4' />
+    </Property>
+    <Property name='Sharpness' type='float'>
+        <Property name='DisplayName' type='string' value='Sharpness'/>
+        <Property name='Min' type='float' value='0.0' />
+        <Property name='Max' type='float' value='1000.0' />
+        <Property name='Default' type='float' value='8.0' />
+    </Property>
+</Effect>"#);
+
+create_setter_helpers!(
+    QuantizeEffect_Impl,
+    set_levels,
+    get_levels,
+    u32,
+    levels_helpers
+);
+create_setter_helpers!(
+    QuantizeEffect_Impl,
+    set_sharpness,
+    get_sharpness,
+    f32,
+    sharpness_helpers
+);
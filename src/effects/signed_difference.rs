@@ -0,0 +1,138 @@
+use windows::{
+    core::{implement, w, IUnknown, Result, GUID, HRESULT, PCWSTR},
+    Win32::{
+        Foundation::{E_INVALIDARG, S_OK},
+        Graphics::Direct2D::{
+            ID2D1DrawInfo, ID2D1DrawTransform, ID2D1EffectImpl, ID2D1Factory1,
+            D2D1_PROPERTY_BINDING,
+        },
+    },
+};
+
+pub const SIGNED_DIFFERENCE_EFFECT_CLSID: GUID =
+    GUID::from_u128(0x17865152_74F5_4467_A348_B30A8458A830);
+pub const SIGNED_DIFFERENCE_EFFECT_SHADER: GUID =
+    GUID::from_u128(0xD5A66E28_80DD_404D_A402_1BB0B1791EB0);
+
+pub const SIGNED_DIFFERENCE_PROP_GAIN: u32 = 0;
+pub const SIGNED_DIFFERENCE_PROP_BIAS: u32 = 1;
+
+/// `bias + gain * (a - b)`. Unlike the subtract blend this doesn't clamp, so
+/// with float buffer precision the negative half of a difference survives.
+#[implement(ID2D1EffectImpl, ID2D1DrawTransform)]
+pub struct SignedDifferenceEffect {
+    constants: SignedDifferenceEffectConstants,
+    draw_info: Option<ID2D1DrawInfo>,
+}
+
+#[repr(C)]
+struct SignedDifferenceEffectConstants {
+    gain: f32,
+    bias: f32,
+}
+
+impl_pixel_shader_transform!(
+    SignedDifferenceEffect_Impl,
+    SIGNED_DIFFERENCE_EFFECT_SHADER,
+    shaders::signed_difference_pixel_shader(),
+    2
+);
+
+impl SignedDifferenceEffect {
+    fn new() -> Self {
+        Self {
+            constants: SignedDifferenceEffectConstants {
+                gain: 1.0,
+                bias: 0.0,
+            },
+            draw_info: None,
+        }
+    }
+    pub fn register(factory: &ID2D1Factory1) -> Result<()> {
+        let bindings = [
+            D2D1_PROPERTY_BINDING {
+                propertyName: w!("Gain"),
+                setFunction: Some(gain_helpers::value_setter),
+                getFunction: Some(gain_helpers::value_getter),
+            },
+            D2D1_PROPERTY_BINDING {
+                propertyName: w!("Bias"),
+                setFunction: Some(bias_helpers::value_setter),
+                getFunction: Some(bias_helpers::value_getter),
+            },
+        ];
+
+        unsafe {
+            factory.RegisterEffectFromString(
+                &SIGNED_DIFFERENCE_EFFECT_CLSID,
+                SIGNED_DIFFERENCE_EFFECT_XML,
+                Some(&bindings),
+                Some(Self::create_effect),
+            )?;
+        }
+        Ok(())
+    }
+    unsafe extern "system" fn create_effect(effectimpl: *mut Option<IUnknown>) -> HRESULT {
+        // This gets us the base vtable in SignedDifferenceEffect_Impl
+        let effect_unknown: IUnknown = Self::new().into();
+        if let Some(effectimpl) = effectimpl.as_mut() {
+            *effectimpl = Some(effect_unknown);
+            S_OK
+        } else {
+            E_INVALIDARG
+        }
+    }
+    fn set_gain(&mut self, gain: f32) -> Result<()> {
+        self.constants.gain = gain;
+        Ok(())
+    }
+    fn get_gain(&self) -> f32 {
+        self.constants.gain
+    }
+    fn set_bias(&mut self, bias: f32) -> Result<()> {
+        self.constants.bias = bias;
+        Ok(())
+    }
+    fn get_bias(&self) -> f32 {
+        self.constants.bias
+    }
+}
+
+const SIGNED_DIFFERENCE_EFFECT_XML: PCWSTR = w!(r#"<?xml version='1.0'?>
+<Effect>
+    <Property name='DisplayName' type='string' value='SignedDifferenceEffect'/>
+    <Property name='Author' type='string' value='Contoso'/>
+    <Property name='Category' type='string' value='Stylize'/>
+    <Property name='Description' type='string' value='Unclamped, scaled difference of two images.'/>
+    <Inputs>
+        <Input name='A'/>
+        <Input name='B'/>
+    </Inputs>
+    <Property name='Gain' type='float'>
+        <Property name='DisplayName' type='string' value='Gain'/>
+        <Property name='Min' type='float' value='-1000.0' />
+        <Property name='Max' type='float' value='1000.0' />
+        <Property name='Default' type='float' value='1.0' />
+    </Property>
+    <Property name='Bias' type='float'>
+        <Property name='DisplayName' type='string' value='Bias'/>
+        <Property name='Min' type='float' value='-1000.0' />
+        <Property name='Max' type='float' value='1000.0' />
+        <Property name='Default' type='float' value='0.0' />
+    </Property>
+</Effect>"#);
+
+create_setter_helpers!(
+    SignedDifferenceEffect_Impl,
+    set_gain,
+    get_gain,
+    f32,
+    gain_helpers
+);
+create_setter_helpers!(
+    SignedDifferenceEffect_Impl,
+    set_bias,
+    get_bias,
+    f32,
+    bias_helpers
+);
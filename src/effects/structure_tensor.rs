@@ -0,0 +1,74 @@
+use windows::{
+    core::{implement, w, IUnknown, Result, GUID, HRESULT, PCWSTR},
+    Win32::{
+        Foundation::{E_INVALIDARG, S_OK},
+        Graphics::Direct2D::{ID2D1DrawInfo, ID2D1DrawTransform, ID2D1EffectImpl, ID2D1Factory1},
+    },
+};
+
+pub const STRUCTURE_TENSOR_EFFECT_CLSID: GUID =
+    GUID::from_u128(0x0F85915C_F784_46D1_B1E1_19C684E3C15D);
+pub const STRUCTURE_TENSOR_EFFECT_SHADER: GUID =
+    GUID::from_u128(0x25011840_DF61_4AF7_AC9C_2E64A8DEF1B1);
+
+/// Sobel gradients of the input's luminance packed as a structure tensor
+/// `[E, 0.5 + 0.5 * F, G, 1]`. Blurring the result gives the edge tangent
+/// flow used by the flow-based DoG effects.
+#[implement(ID2D1EffectImpl, ID2D1DrawTransform)]
+pub struct StructureTensorEffect {
+    constants: StructureTensorEffectConstants,
+    draw_info: Option<ID2D1DrawInfo>,
+}
+
+#[repr(C)]
+struct StructureTensorEffectConstants {}
+
+impl_pixel_shader_transform!(
+    StructureTensorEffect_Impl,
+    STRUCTURE_TENSOR_EFFECT_SHADER,
+    shaders::structure_tensor_pixel_shader(),
+    1,
+    |_effect| 1,
+    opaque
+);
+
+impl StructureTensorEffect {
+    fn new() -> Self {
+        Self {
+            constants: StructureTensorEffectConstants {},
+            draw_info: None,
+        }
+    }
+    pub fn register(factory: &ID2D1Factory1) -> Result<()> {
+        unsafe {
+            factory.RegisterEffectFromString(
+                &STRUCTURE_TENSOR_EFFECT_CLSID,
+                STRUCTURE_TENSOR_EFFECT_XML,
+                None,
+                Some(Self::create_effect),
+            )?;
+        }
+        Ok(())
+    }
+    unsafe extern "system" fn create_effect(effectimpl: *mut Option<IUnknown>) -> HRESULT {
+        // This gets us the base vtable in StructureTensorEffect_Impl
+        let effect_unknown: IUnknown = Self::new().into();
+        if let Some(effectimpl) = effectimpl.as_mut() {
+            *effectimpl = Some(effect_unknown);
+            S_OK
+        } else {
+            E_INVALIDARG
+        }
+    }
+}
+
+const STRUCTURE_TENSOR_EFFECT_XML: PCWSTR = w!(r#"<?xml version='1.0'?>
+<Effect>
+    <Property name='DisplayName' type='string' value='StructureTensorEffect'/>
+    <Property name='Author' type='string' value='Contoso'/>
+    <Property name='Category' type='string' value='Stylize'/>
+    <Property name='Description' type='string' value='Packs the luminance gradients of an image into a structure tensor.'/>
+    <Inputs>
+        <Input name='Source'/>
+    </Inputs>
+</Effect>"#);
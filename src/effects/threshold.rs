@@ -1,20 +1,25 @@
 use windows::{
     core::{implement, w, IUnknown, Result, GUID, HRESULT, PCWSTR},
     Win32::{
-        Foundation::{E_INVALIDARG, RECT, S_OK},
+        Foundation::{E_INVALIDARG, S_OK},
         Graphics::Direct2D::{
-            ID2D1DrawInfo, ID2D1DrawTransform, ID2D1DrawTransform_Impl, ID2D1EffectContext,
-            ID2D1EffectImpl, ID2D1EffectImpl_Impl, ID2D1Factory1, ID2D1TransformGraph,
-            ID2D1TransformNode, ID2D1TransformNode_Impl, ID2D1Transform_Impl, D2D1_CHANGE_TYPE,
-            D2D1_PIXEL_OPTIONS_NONE, D2D1_PROPERTY_BINDING,
+            ID2D1DrawInfo, ID2D1DrawTransform, ID2D1EffectImpl, ID2D1Factory1,
+            D2D1_PROPERTY_BINDING,
         },
     },
 };
-use windows_core::Interface;
 
 pub const THRESHOLD_EFFECT_CLSID: GUID = GUID::from_u128(0xFB3AF5AA_6F03_4754_A676_BACB2D082069);
 pub const THRESHOLD_EFFECT_SHADER: GUID = GUID::from_u128(0x397DBC73_8831_4C02_9ECD_56036DAEA108);
 
+pub const THRESHOLD_PROP_THRESHOLD: u32 = 0;
+pub const THRESHOLD_PROP_MODE: u32 = 1;
+pub const THRESHOLD_PROP_WIDTH: u32 = 2;
+
+pub const THRESHOLD_MODE_HARD: u32 = 0;
+pub const THRESHOLD_MODE_SMOOTHSTEP: u32 = 1;
+pub const THRESHOLD_MODE_TANH: u32 = 2;
+
 #[implement(ID2D1EffectImpl, ID2D1DrawTransform)]
 pub struct ThresholdEffect {
     constants: ThresholdEffectConstants,
@@ -24,152 +29,46 @@ pub struct ThresholdEffect {
 #[repr(C)]
 struct ThresholdEffectConstants {
     threshold: f32,
+    mode: u32,
+    width: f32,
 }
 
-impl ID2D1EffectImpl_Impl for ThresholdEffect_Impl {
-    fn Initialize(
-        &self,
-        effectcontext: Option<&ID2D1EffectContext>,
-        transformgraph: Option<&ID2D1TransformGraph>,
-    ) -> Result<()> {
-        let effect_context = effectcontext.unwrap();
-        let transform_graph = transformgraph.unwrap();
-        unsafe {
-            effect_context
-                .LoadPixelShader(&THRESHOLD_EFFECT_SHADER, shaders::threshold_pixel_shader())?;
-            // Our base vtable is the IUnknown/IInspectable one
-            let unknown = std::mem::transmute::<&Self, IUnknown>(self);
-            let transform_node: ID2D1TransformNode = unknown.cast()?;
-            std::mem::forget(unknown); // Don't mess up our ref count
-            transform_graph.SetSingleTransformNode(&transform_node)?;
-        }
-        Ok(())
-    }
-
-    fn PrepareForRender(&self, _changetype: D2D1_CHANGE_TYPE) -> Result<()> {
-        if let Some(draw_info) = self.this.draw_info.as_ref() {
-            unsafe {
-                let len = std::mem::size_of::<ThresholdEffectConstants>();
-                let slice =
-                    std::slice::from_raw_parts(&self.this.constants as *const _ as *const u8, len);
-                draw_info.SetPixelShaderConstantBuffer(slice)?;
-            }
-            Ok(())
-        } else {
-            panic!()
-        }
-    }
-
-    fn SetGraph(&self, _transformgraph: Option<&ID2D1TransformGraph>) -> Result<()> {
-        todo!()
-    }
-}
-
-impl ID2D1DrawTransform_Impl for ThresholdEffect_Impl {
-    fn SetDrawInfo(&self, drawinfo: Option<&ID2D1DrawInfo>) -> Result<()> {
-        if let Some(draw_info) = drawinfo {
-            unsafe {
-                // TODO: Safely do this
-                ((self as *const Self as *mut Self).as_mut().unwrap())
-                    .this
-                    .draw_info = Some(draw_info.clone());
-                draw_info.SetPixelShader(&THRESHOLD_EFFECT_SHADER, D2D1_PIXEL_OPTIONS_NONE)?;
-            }
-            Ok(())
-        } else {
-            E_INVALIDARG.ok()
-        }
-    }
-}
-
-impl ID2D1Transform_Impl for ThresholdEffect_Impl {
-    fn MapOutputRectToInputRects(
-        &self,
-        outputrect: *const RECT,
-        inputrects: *mut RECT,
-        inputrectscount: u32,
-    ) -> Result<()> {
-        if inputrectscount != 1 {
-            return E_INVALIDARG.ok();
-        }
-
-        let output_rect = unsafe {
-            outputrect
-                .as_ref()
-                .map(|x| Ok(x))
-                .unwrap_or(Err(E_INVALIDARG))?
-        };
-        let input_rect = unsafe {
-            inputrects
-                .as_mut()
-                .map(|x| Ok(x))
-                .unwrap_or(Err(E_INVALIDARG))?
-        };
-        *input_rect = *output_rect;
-
-        Ok(())
-    }
-
-    fn MapInputRectsToOutputRect(
-        &self,
-        inputrects: *const RECT,
-        _inputopaquesubrects: *const RECT,
-        inputrectcount: u32,
-        outputrect: *mut RECT,
-        outputopaquesubrect: *mut RECT,
-    ) -> Result<()> {
-        if inputrectcount != 1 {
-            return E_INVALIDARG.ok();
-        }
-
-        let input_rect = unsafe {
-            inputrects
-                .as_ref()
-                .map(|x| Ok(x))
-                .unwrap_or(Err(E_INVALIDARG))?
-        };
-        let output_rect = unsafe {
-            outputrect
-                .as_mut()
-                .map(|x| Ok(x))
-                .unwrap_or(Err(E_INVALIDARG))?
-        };
-        *output_rect = *input_rect;
-        let output_opaque_rect = unsafe {
-            outputopaquesubrect
-                .as_mut()
-                .map(|x| Ok(x))
-                .unwrap_or(Err(E_INVALIDARG))?
-        };
-        *output_opaque_rect = *input_rect;
-
-        Ok(())
-    }
-
-    fn MapInvalidRect(&self, _inputindex: u32, _invalidinputrect: &RECT) -> Result<RECT> {
-        todo!()
-    }
-}
-
-impl ID2D1TransformNode_Impl for ThresholdEffect_Impl {
-    fn GetInputCount(&self) -> u32 {
-        1
-    }
-}
+impl_pixel_shader_transform!(
+    ThresholdEffect_Impl,
+    THRESHOLD_EFFECT_SHADER,
+    shaders::threshold_pixel_shader(),
+    1
+);
 
 impl ThresholdEffect {
     fn new() -> Self {
         Self {
-            constants: ThresholdEffectConstants { threshold: 0.0 },
+            constants: ThresholdEffectConstants {
+                threshold: 0.0,
+                mode: THRESHOLD_MODE_HARD,
+                width: 0.01,
+            },
             draw_info: None,
         }
     }
     pub fn register(factory: &ID2D1Factory1) -> Result<()> {
-        let bindings = [D2D1_PROPERTY_BINDING {
-            propertyName: w!("Threshold"),
-            setFunction: Some(threshold_helpers::value_setter),
-            getFunction: Some(threshold_helpers::value_getter),
-        }];
+        let bindings = [
+            D2D1_PROPERTY_BINDING {
+                propertyName: w!("Threshold"),
+                setFunction: Some(threshold_helpers::value_setter),
+                getFunction: Some(threshold_helpers::value_getter),
+            },
+            D2D1_PROPERTY_BINDING {
+                propertyName: w!("Mode"),
+                setFunction: Some(mode_helpers::value_setter),
+                getFunction: Some(mode_helpers::value_getter),
+            },
+            D2D1_PROPERTY_BINDING {
+                propertyName: w!("Width"),
+                setFunction: Some(width_helpers::value_setter),
+                getFunction: Some(width_helpers::value_getter),
+            },
+        ];
 
         unsafe {
             factory.RegisterEffectFromString(
@@ -198,6 +97,23 @@ impl ThresholdEffect {
     fn get_threshold(&self) -> f32 {
         self.constants.threshold
     }
+    fn set_mode(&mut self, mode: u32) -> Result<()> {
+        if mode > THRESHOLD_MODE_TANH {
+            return E_INVALIDARG.ok();
+        }
+        self.constants.mode = mode;
+        Ok(())
+    }
+    fn get_mode(&self) -> u32 {
+        self.constants.mode
+    }
+    fn set_width(&mut self, width: f32) -> Result<()> {
+        self.constants.width = width;
+        Ok(())
+    }
+    fn get_width(&self) -> f32 {
+        self.constants.width
+    }
 }
 
 // https://learn.microsoft.com/en-us/windows/win32/direct2d/custom-effects#define-a-public-registration-method
@@ -220,6 +136,21 @@ const SAMPLE_EFFECT_XML: PCWSTR = w!(r#"<?xml version='1.0'?>
         <Property name='Max' type='float' value='1000.0' />
         <Property name='Default' type='float' value='0.0' />
     </Property>
+    <Property name='Mode' type='enum'>
+        <Property name='DisplayName' type='string' value='Mode'/>
+        <Property name='Default' type='enum' value='0' />
+        <Fields>
+            <Field name='Hard' displayname='Hard' index='0' />
+            <Field name='Smoothstep' displayname='Smoothstep' index='1' />
+            <Field name='Tanh' displayname='Tanh' index='2' />
+        </Fields>
+    </Property>
+    <Property name='Width' type='float'>
+        <Property name='DisplayName' type='string' value='Width'/>
+        <Property name='Min' type='float' value='0.0' />
+        <Property name='Max' type='float' value='1000.0' />
+        <Property name='Default' type='float' value='0.01' />
+    </Property>
 </Effect>"#);
 
 create_setter_helpers!(
@@ -229,3 +160,11 @@ create_setter_helpers!(
     f32,
     threshold_helpers
 );
+create_setter_helpers!(ThresholdEffect_Impl, set_mode, get_mode, u32, mode_helpers);
+create_setter_helpers!(
+    ThresholdEffect_Impl,
+    set_width,
+    get_width,
+    f32,
+    width_helpers
+);
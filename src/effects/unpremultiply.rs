@@ -0,0 +1,73 @@
+use windows::{
+    core::{implement, w, IUnknown, Result, GUID, HRESULT, PCWSTR},
+    Win32::{
+        Foundation::{E_INVALIDARG, S_OK},
+        Graphics::Direct2D::{ID2D1DrawInfo, ID2D1DrawTransform, ID2D1EffectImpl, ID2D1Factory1},
+    },
+};
+
+pub const UNPREMULTIPLY_EFFECT_CLSID: GUID =
+    GUID::from_u128(0xB1A93315_7700_439C_AE5C_FC1450514A84);
+pub const UNPREMULTIPLY_EFFECT_SHADER: GUID =
+    GUID::from_u128(0x43C10EF8_5BCE_4535_96DC_BB730B22095F);
+
+/// Divides the color by alpha and makes the pixel opaque. Unlike
+/// `CLSID_D2D1UnPremultiply` the result is a valid premultiplied image, so
+/// later effects see the straight color of translucent pixels.
+#[implement(ID2D1EffectImpl, ID2D1DrawTransform)]
+pub struct UnpremultiplyEffect {
+    constants: UnpremultiplyEffectConstants,
+    draw_info: Option<ID2D1DrawInfo>,
+}
+
+#[repr(C)]
+struct UnpremultiplyEffectConstants {}
+
+impl_pixel_shader_transform!(
+    UnpremultiplyEffect_Impl,
+    UNPREMULTIPLY_EFFECT_SHADER,
+    shaders::unpremultiply_pixel_shader(),
+    1,
+    opaque
+);
+
+impl UnpremultiplyEffect {
+    fn new() -> Self {
+        Self {
+            constants: UnpremultiplyEffectConstants {},
+            draw_info: None,
+        }
+    }
+    pub fn register(factory: &ID2D1Factory1) -> Result<()> {
+        unsafe {
+            factory.RegisterEffectFromString(
+                &UNPREMULTIPLY_EFFECT_CLSID,
+                UNPREMULTIPLY_EFFECT_XML,
+                None,
+                Some(Self::create_effect),
+            )?;
+        }
+        Ok(())
+    }
+    unsafe extern "system" fn create_effect(effectimpl: *mut Option<IUnknown>) -> HRESULT {
+        // This gets us the base vtable in UnpremultiplyEffect_Impl
+        let effect_unknown: IUnknown = Self::new().into();
+        if let Some(effectimpl) = effectimpl.as_mut() {
+            *effectimpl = Some(effect_unknown);
+            S_OK
+        } else {
+            E_INVALIDARG
+        }
+    }
+}
+
+const UNPREMULTIPLY_EFFECT_XML: PCWSTR = w!(r#"<?xml version='1.0'?>
+<Effect>
+    <Property name='DisplayName' type='string' value='UnpremultiplyEffect'/>
+    <Property name='Author' type='string' value='Contoso'/>
+    <Property name='Category' type='string' value='Stylize'/>
+    <Property name='Description' type='string' value='Replaces translucent pixels with their opaque straight color.'/>
+    <Inputs>
+        <Input name='Source'/>
+    </Inputs>
+</Effect>"#);
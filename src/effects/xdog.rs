@@ -0,0 +1,158 @@
+use windows::{
+    core::{implement, w, IUnknown, Result, GUID, HRESULT, PCWSTR},
+    Win32::{
+        Foundation::{E_INVALIDARG, S_OK},
+        Graphics::Direct2D::{
+            ID2D1DrawInfo, ID2D1DrawTransform, ID2D1EffectImpl, ID2D1Factory1,
+            D2D1_PROPERTY_BINDING,
+        },
+    },
+};
+
+pub const XDOG_EFFECT_CLSID: GUID = GUID::from_u128(0x6C3E0B1D_2F5A_4B8E_9D47_1A0C5E7F3B92);
+pub const XDOG_EFFECT_SHADER: GUID = GUID::from_u128(0xA41F7C2E_93D8_4E65_B0A3_5D2E8F614C07);
+
+pub const XDOG_PROP_SHARPNESS: u32 = 0;
+pub const XDOG_PROP_EPSILON: u32 = 1;
+pub const XDOG_PROP_PHI: u32 = 2;
+
+/// Extended Difference of Gaussians. Input 0 is the image blurred with σ and
+/// input 1 the image blurred with kσ.
+#[implement(ID2D1EffectImpl, ID2D1DrawTransform)]
+pub struct XDoGEffect {
+    constants: XDoGEffectConstants,
+    draw_info: Option<ID2D1DrawInfo>,
+}
+
+#[repr(C)]
+struct XDoGEffectConstants {
+    sharpness: f32,
+    epsilon: f32,
+    phi: f32,
+}
+
+impl_pixel_shader_transform!(
+    XDoGEffect_Impl,
+    XDOG_EFFECT_SHADER,
+    shaders::xdog_pixel_shader(),
+    2
+);
+
+impl XDoGEffect {
+    fn new() -> Self {
+        Self {
+            constants: XDoGEffectConstants {
+                sharpness: 20.0,
+                epsilon: 0.5,
+                phi: 10.0,
+            },
+            draw_info: None,
+        }
+    }
+    pub fn register(factory: &ID2D1Factory1) -> Result<()> {
+        let bindings = [
+            D2D1_PROPERTY_BINDING {
+                propertyName: w!("Sharpness"),
+                setFunction: Some(sharpness_helpers::value_setter),
+                getFunction: Some(sharpness_helpers::value_getter),
+            },
+            D2D1_PROPERTY_BINDING {
+                propertyName: w!("Epsilon"),
+                setFunction: Some(epsilon_helpers::value_setter),
+                getFunction: Some(epsilon_helpers::value_getter),
+            },
+            D2D1_PROPERTY_BINDING {
+                propertyName: w!("Phi"),
+                setFunction: Some(phi_helpers::value_setter),
+                getFunction: Some(phi_helpers::value_getter),
+            },
+        ];
+
+        unsafe {
+            factory.RegisterEffectFromString(
+                &XDOG_EFFECT_CLSID,
+                XDOG_EFFECT_XML,
+                Some(&bindings),
+                Some(Self::create_effect),
+            )?;
+        }
+        Ok(())
+    }
+    unsafe extern "system" fn create_effect(effectimpl: *mut Option<IUnknown>) -> HRESULT {
+        // This gets us the base vtable in XDoGEffect_Impl
+        let effect_unknown: IUnknown = Self::new().into();
+        if let Some(effectimpl) = effectimpl.as_mut() {
+            *effectimpl = Some(effect_unknown);
+            S_OK
+        } else {
+            E_INVALIDARG
+        }
+    }
+    fn set_sharpness(&mut self, sharpness: f32) -> Result<()> {
+        self.constants.sharpness = sharpness;
+        Ok(())
+    }
+    fn get_sharpness(&self) -> f32 {
+        self.constants.sharpness
+    }
+    fn set_epsilon(&mut self, epsilon: f32) -> Result<()> {
+        self.constants.epsilon = epsilon;
+        Ok(())
+    }
+    fn get_epsilon(&self) -> f32 {
+        self.constants.epsilon
+    }
+    fn set_phi(&mut self, phi: f32) -> Result<()> {
+        self.constants.phi = phi;
+        Ok(())
+    }
+    fn get_phi(&self) -> f32 {
+        self.constants.phi
+    }
+}
+
+const XDOG_EFFECT_XML: PCWSTR = w!(r#"<?xml version='1.0'?>
+<Effect>
+    <Property name='DisplayName' type='string' value='XDoGEffect'/>
+    <Property name='Author' type='string' value='Contoso'/>
+    <Property name='Category' type='string' value='Stylize'/>
+    <Property name='Description' type='string' value='Extended Difference of Gaussians with a soft threshold.'/>
+    <Inputs>
+        <Input name='Narrow'/>
+        <Input name='Wide'/>
+    </Inputs>
+    <Property name='Sharpness' type='float'>
+        <Property name='DisplayName' type='string' value='Sharpness'/>
+        <Property name='Min' type='float' value='0.0' />
+        <Property name='Max' type='float' value='1000.0' />
+        <Property name='Default' type='float' value='20.0' />
+    </Property>
+    <Property name='Epsilon' type='float'>
+        <Property name='DisplayName' type='string' value='Epsilon'/>
+        <Property name='Min' type='float' value='-1000.0' />
+        <Property name='Max' type='float' value='1000.0' />
+        <Property name='Default' type='float' value='0.5' />
+    </Property>
+    <Property name='Phi' type='float'>
+        <Property name='DisplayName' type='string' value='Phi'/>
+        <Property name='Min' type='float' value='0.0' />
+        <Property name='Max' type='float' value='1000.0' />
+        <Property name='Default' type='float' value='10.0' />
+    </Property>
+</Effect>"#);
+
+create_setter_helpers!(
+    XDoGEffect_Impl,
+    set_sharpness,
+    get_sharpness,
+    f32,
+    sharpness_helpers
+);
+create_setter_helpers!(
+    XDoGEffect_Impl,
+    set_epsilon,
+    get_epsilon,
+    f32,
+    epsilon_helpers
+);
+create_setter_helpers!(XDoGEffect_Impl, set_phi, get_phi, f32, phi_helpers);
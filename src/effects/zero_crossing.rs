@@ -0,0 +1,111 @@
+use windows::{
+    core::{implement, w, IUnknown, Result, GUID, HRESULT, PCWSTR},
+    Win32::{
+        Foundation::{E_INVALIDARG, S_OK},
+        Graphics::Direct2D::{
+            ID2D1DrawInfo, ID2D1DrawTransform, ID2D1EffectImpl, ID2D1Factory1,
+            D2D1_PROPERTY_BINDING,
+        },
+    },
+};
+
+pub const ZERO_CROSSING_EFFECT_CLSID: GUID =
+    GUID::from_u128(0xACD743CD_A65A_418E_B9C8_EBBA8C5D15DF);
+pub const ZERO_CROSSING_EFFECT_SHADER: GUID =
+    GUID::from_u128(0xEE8CACAD_1F18_4898_A5AE_719E2566D1C4);
+
+pub const ZERO_CROSSING_PROP_MIN_SLOPE: u32 = 0;
+
+/// Marks the pixels where the luminance of a signed response changes sign,
+/// keeping only the side of each crossing that is closer to zero. Needs
+/// float buffer precision, since unorm buffers clamp negative values.
+#[implement(ID2D1EffectImpl, ID2D1DrawTransform)]
+pub struct ZeroCrossingEffect {
+    constants: ZeroCrossingEffectConstants,
+    draw_info: Option<ID2D1DrawInfo>,
+}
+
+#[repr(C)]
+struct ZeroCrossingEffectConstants {
+    min_slope: f32,
+}
+
+impl_pixel_shader_transform!(
+    ZeroCrossingEffect_Impl,
+    ZERO_CROSSING_EFFECT_SHADER,
+    shaders::zero_crossing_pixel_shader(),
+    1,
+    |_effect| 1,
+    opaque
+);
+
+impl ZeroCrossingEffect {
+    fn new() -> Self {
+        Self {
+            constants: ZeroCrossingEffectConstants { min_slope: 0.002 },
+            draw_info: None,
+        }
+    }
+    pub fn register(factory: &ID2D1Factory1) -> Result<()> {
+        let bindings = [D2D1_PROPERTY_BINDING {
+            propertyName: w!("MinSlope"),
+            setFunction: Some(min_slope_helpers::value_setter),
+            getFunction: Some(min_slope_helpers::value_getter),
+        }];
+
+        unsafe {
+            factory.RegisterEffectFromString(
+                &ZERO_CROSSING_EFFECT_CLSID,
+                ZERO_CROSSING_EFFECT_XML,
+                Some(&bindings),
+                Some(Self::create_effect),
+            )?;
+        }
+        Ok(())
+    }
+    unsafe extern "system" fn create_effect(effectimpl: *mut Option<IUnknown>) -> HRESULT {
+        // This gets us the base vtable in ZeroCrossingEffect_Impl
+        let effect_unknown: IUnknown = Self::new().into();
+        if let Some(effectimpl) = effectimpl.as_mut() {
+            *effectimpl = Some(effect_unknown);
+            S_OK
+        } else {
+            E_INVALIDARG
+        }
+    }
+    fn set_min_slope(&mut self, min_slope: f32) -> Result<()> {
+        if min_slope < 0.0 {
+            return E_INVALIDARG.ok();
+        }
+        self.constants.min_slope = min_slope;
+        Ok(())
+    }
+    fn get_min_slope(&self) -> f32 {
+        self.constants.min_slope
+    }
+}
+
+const ZERO_CROSSING_EFFECT_XML: PCWSTR = w!(r#"<?xml version='1.0'?>
+<Effect>
+    <Property name='DisplayName' type='string' value='ZeroCrossingEffect'/>
+    <Property name='Author' type='string' value='Contoso'/>
+    <Property name='Category' type='string' value='Stylize'/>
+    <Property name='Description' type='string' value='Thin edges where a signed response crosses zero.'/>
+    <Inputs>
+        <Input name='Source'/>
+    </Inputs>
+    <Property name='MinSlope' type='float'>
+        <Property name='DisplayName' type='string' value='MinSlope'/>
+        <Property name='Min' type='float' value='0.0' />
+        <Property name='Max' type='float' value='1000.0' />
+        <Property name='Default' type='float' value='0.002' />
+    </Property>
+</Effect>"#);
+
+create_setter_helpers!(
+    ZeroCrossingEffect_Impl,
+    set_min_slope,
+    get_min_slope,
+    f32,
+    min_slope_helpers
+);
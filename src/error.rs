@@ -0,0 +1,64 @@
+use std::fmt;
+
+use crate::graph::GraphError;
+
+/// Errors produced while loading, processing or saving images.
+#[derive(Debug)]
+pub enum Error {
+    Io(std::io::Error),
+    Graph(GraphError),
+    Image(image::ImageError),
+    #[cfg(windows)]
+    Windows(windows::core::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(error) => error.fmt(f),
+            Error::Graph(error) => error.fmt(f),
+            Error::Image(error) => error.fmt(f),
+            #[cfg(windows)]
+            Error::Windows(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(error) => Some(error),
+            Error::Graph(error) => Some(error),
+            Error::Image(error) => Some(error),
+            #[cfg(windows)]
+            Error::Windows(error) => Some(error),
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+impl From<GraphError> for Error {
+    fn from(error: GraphError) -> Self {
+        Error::Graph(error)
+    }
+}
+
+impl From<image::ImageError> for Error {
+    fn from(error: image::ImageError) -> Self {
+        Error::Image(error)
+    }
+}
+
+#[cfg(windows)]
+impl From<windows::core::Error> for Error {
+    fn from(error: windows::core::Error) -> Self {
+        Error::Windows(error)
+    }
+}
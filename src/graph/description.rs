@@ -0,0 +1,792 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    ops::RangeInclusive,
+};
+
+use serde::{de::IgnoredAny, Deserialize};
+
+use super::{Graph, GraphError, Node, Op, Source, SOURCE_INPUT};
+use crate::cpu::{
+    parse_color, BlendMode, EdgePolarity, Gradient, LuminanceModel, MorphologyOperation, Precision,
+    StructuringElement, TangentEncoding, ThresholdMode, ThresholdSelection,
+};
+
+/// The unvalidated contents of a graph file.
+#[derive(Clone, Debug, Deserialize)]
+pub struct GraphDescription {
+    pub output: String,
+    /// `unorm8`, `float16` or `float32`, see [`Precision`]
+    #[serde(default)]
+    pub precision: Option<String>,
+    pub nodes: Vec<NodeDescription>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct NodeDescription {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub inputs: Vec<String>,
+    #[serde(flatten)]
+    pub properties: BTreeMap<String, PropertyValue>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum PropertyValue {
+    Number(f64),
+    Text(String),
+    /// Booleans, arrays and tables, which no property takes. Keeping them
+    /// lets the error name the node and property.
+    Other(IgnoredAny),
+}
+
+impl GraphDescription {
+    pub fn from_toml(text: &str) -> Result<Self, GraphError> {
+        toml::from_str(text).map_err(|error| GraphError::Parse(error.to_string()))
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, GraphError> {
+        serde_json::from_str(text).map_err(|error| GraphError::Parse(error.to_string()))
+    }
+
+    pub(super) fn build(self) -> Result<Graph, GraphError> {
+        let precision = match self.precision.as_deref() {
+            None | Some("unorm8") => Precision::Unorm8,
+            Some("float16") => Precision::Float16,
+            Some("float32") => Precision::Float32,
+            Some(precision) => return Err(GraphError::UnknownPrecision(precision.to_owned())),
+        };
+
+        let mut indices = HashMap::new();
+        for (index, node) in self.nodes.iter().enumerate() {
+            if node.id == SOURCE_INPUT || indices.insert(node.id.as_str(), index).is_some() {
+                return Err(GraphError::DuplicateNode(node.id.clone()));
+            }
+        }
+
+        // Resolve every node's op and inputs before ordering them
+        let mut ops = Vec::with_capacity(self.nodes.len());
+        let mut inputs = Vec::with_capacity(self.nodes.len());
+        for node in &self.nodes {
+            let op = parse_op(node)?;
+            if node.inputs.len() != op.input_count() {
+                return Err(GraphError::InputCount {
+                    node: node.id.clone(),
+                    expected: op.input_count(),
+                    actual: node.inputs.len(),
+                });
+            }
+            let node_inputs = node
+                .inputs
+                .iter()
+                .map(|input| {
+                    if input == SOURCE_INPUT {
+                        Ok(Source::Input)
+                    } else {
+                        indices
+                            .get(input.as_str())
+                            .map(|index| Source::Node(*index))
+                            .ok_or_else(|| GraphError::MissingInput {
+                                node: node.id.clone(),
+                                input: input.clone(),
+                            })
+                    }
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            ops.push(op);
+            inputs.push(node_inputs);
+        }
+
+        let output = *indices
+            .get(self.output.as_str())
+            .ok_or_else(|| GraphError::MissingOutput(self.output.clone()))?;
+
+        let order = topological_order(&self.nodes, &inputs)?;
+        let mut new_indices = vec![0; self.nodes.len()];
+        for (new_index, old_index) in order.iter().enumerate() {
+            new_indices[*old_index] = new_index;
+        }
+        let mut ops: Vec<_> = ops.into_iter().map(Some).collect();
+        let nodes = order
+            .iter()
+            .map(|&index| Node {
+                id: self.nodes[index].id.clone(),
+                op: ops[index].take().unwrap(),
+                inputs: inputs[index]
+                    .iter()
+                    .map(|source| match source {
+                        Source::Input => Source::Input,
+                        Source::Node(old_index) => Source::Node(new_indices[*old_index]),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Ok(Graph {
+            nodes,
+            output: new_indices[output],
+            precision,
+        })
+    }
+}
+
+/// Orders nodes so that every node comes after its inputs, keeping the
+/// declaration order where possible.
+fn topological_order(
+    nodes: &[NodeDescription],
+    inputs: &[Vec<Source>],
+) -> Result<Vec<usize>, GraphError> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum State {
+        Unvisited,
+        Visiting,
+        Done,
+    }
+
+    fn visit(
+        index: usize,
+        nodes: &[NodeDescription],
+        inputs: &[Vec<Source>],
+        states: &mut [State],
+        order: &mut Vec<usize>,
+    ) -> Result<(), GraphError> {
+        match states[index] {
+            State::Done => return Ok(()),
+            State::Visiting => return Err(GraphError::Cycle(nodes[index].id.clone())),
+            State::Unvisited => {}
+        }
+        states[index] = State::Visiting;
+        for source in &inputs[index] {
+            if let Source::Node(input) = source {
+                visit(*input, nodes, inputs, states, order)?;
+            }
+        }
+        states[index] = State::Done;
+        order.push(index);
+        Ok(())
+    }
+
+    let mut states = vec![State::Unvisited; nodes.len()];
+    let mut order = Vec::with_capacity(nodes.len());
+    for index in 0..nodes.len() {
+        visit(index, nodes, inputs, &mut states, &mut order)?;
+    }
+    Ok(order)
+}
+
+fn parse_op(node: &NodeDescription) -> Result<Op, GraphError> {
+    let mut properties = Properties::new(node);
+    let op = match node.kind.as_str() {
+        "gaussian_blur" => Op::GaussianBlur {
+            standard_deviation: properties.non_negative("standard_deviation", 3.0)?,
+        },
+        "blend" => Op::Blend {
+            mode: properties.choice(
+                "mode",
+                BlendMode::Subtract,
+                &[
+                    ("subtract", BlendMode::Subtract),
+                    ("multiply", BlendMode::Multiply),
+                    ("screen", BlendMode::Screen),
+                    ("darken", BlendMode::Darken),
+                    ("lighten", BlendMode::Lighten),
+                    ("difference", BlendMode::Difference),
+                ],
+            )?,
+        },
+        "cross_fade" => Op::CrossFade {
+            weight: properties.number_in("weight", 0.5, 0.0..=1.0)?,
+        },
+        "threshold" => {
+            let threshold = properties.number_in("threshold", 0.0, 0.0..=1000.0)?;
+            let mode = properties.choice(
+                "mode",
+                ThresholdMode::Hard,
+                &[
+                    ("hard", ThresholdMode::Hard),
+                    ("smoothstep", ThresholdMode::Smoothstep { width: 0.01 }),
+                    ("tanh", ThresholdMode::Tanh { width: 0.01 }),
+                ],
+            )?;
+            Op::Threshold {
+                threshold,
+                mode: threshold_width(&mut properties, mode)?,
+            }
+        }
+        "hysteresis" => {
+            let low = properties.number_in("low", 0.005, 0.0..=1000.0)?;
+            let high = properties.number_in("high", 0.01, 0.0..=1000.0)?;
+            if low > high {
+                return Err(properties.invalid("low", format!("at most high ({})", high)));
+            }
+            Op::Hysteresis { low, high }
+        }
+        "auto_threshold" => {
+            let selection = match properties.choice(
+                "method",
+                ThresholdSelection::Otsu,
+                &[
+                    ("otsu", ThresholdSelection::Otsu),
+                    (
+                        "percentile",
+                        ThresholdSelection::Percentile { fraction: 0.1 },
+                    ),
+                    ("density", ThresholdSelection::Density { fraction: 0.1 }),
+                ],
+            )? {
+                ThresholdSelection::Otsu => {
+                    properties.reject("fraction", "method percentile and density")?;
+                    ThresholdSelection::Otsu
+                }
+                ThresholdSelection::Percentile { fraction } => ThresholdSelection::Percentile {
+                    fraction: properties.positive("fraction", fraction, 1.0)?,
+                },
+                ThresholdSelection::Density { fraction } => ThresholdSelection::Density {
+                    fraction: properties.positive("fraction", fraction, 1.0)?,
+                },
+            };
+            // Hysteresis isn't a mode of the threshold effect
+            let mode = properties.choice(
+                "mode",
+                Some(ThresholdMode::Hard),
+                &[
+                    ("hard", Some(ThresholdMode::Hard)),
+                    (
+                        "smoothstep",
+                        Some(ThresholdMode::Smoothstep { width: 0.01 }),
+                    ),
+                    ("tanh", Some(ThresholdMode::Tanh { width: 0.01 })),
+                    ("hysteresis", None),
+                ],
+            )?;
+            match mode {
+                Some(mode) => {
+                    properties.reject("low_ratio", "mode hysteresis")?;
+                    Op::AutoThreshold {
+                        selection,
+                        mode: threshold_width(&mut properties, mode)?,
+                        low_ratio: None,
+                    }
+                }
+                None => {
+                    properties.reject("width", "mode smoothstep and tanh")?;
+                    Op::AutoThreshold {
+                        selection,
+                        mode: ThresholdMode::Hard,
+                        low_ratio: Some(properties.number_in("low_ratio", 0.5, 0.0..=1.0)?),
+                    }
+                }
+            }
+        }
+        "xdog" => Op::Xdog {
+            sharpness: properties.non_negative("sharpness", 20.0)?,
+            epsilon: properties.number("epsilon", 0.5)?,
+            phi: properties.non_negative("phi", 10.0)?,
+        },
+        "zero_crossing" => Op::ZeroCrossing {
+            min_slope: properties.non_negative("min_slope", 0.002)?,
+        },
+        "signed_difference" => Op::SignedDifference {
+            gain: properties.number("gain", 1.0)?,
+            bias: properties.number("bias", 0.0)?,
+        },
+        "grayscale" => Op::Grayscale,
+        "luminance" => Op::Luminance {
+            model: properties.choice(
+                "model",
+                LuminanceModel::Rec601,
+                &[
+                    ("rec601", LuminanceModel::Rec601),
+                    ("rec709", LuminanceModel::Rec709),
+                    ("lab", LuminanceModel::Lab),
+                ],
+            )?,
+        },
+        "bilateral" => Op::Bilateral {
+            spatial_sigma: properties.positive("spatial_sigma", 3.0, 50.0)?,
+            range_sigma: properties.positive("range_sigma", 0.1, f32::INFINITY)?,
+        },
+        "quantize" => Op::Quantize {
+            levels: properties.integer("levels", 4, 2..=256)?,
+            sharpness: properties.non_negative("sharpness", 8.0)?,
+        },
+        "morphology" => Op::Morphology {
+            operation: properties.choice(
+                "operation",
+                MorphologyOperation::Erode,
+                &[
+                    ("erode", MorphologyOperation::Erode),
+                    ("dilate", MorphologyOperation::Dilate),
+                ],
+            )?,
+            shape: properties.choice(
+                "shape",
+                StructuringElement::Square,
+                &[
+                    ("square", StructuringElement::Square),
+                    ("disk", StructuringElement::Disk),
+                    ("diamond", StructuringElement::Diamond),
+                ],
+            )?,
+            radius: properties.integer("radius", 1, 0..=32)?,
+        },
+        "remove_small_components" => Op::RemoveSmallComponents {
+            min_area: properties.integer("min_area", 16, 0..=u32::MAX)?,
+            polarity: properties.choice(
+                "polarity",
+                EdgePolarity::Light,
+                &[("light", EdgePolarity::Light), ("dark", EdgePolarity::Dark)],
+            )?,
+        },
+        "skeletonize" => Op::Skeletonize {
+            polarity: properties.choice(
+                "polarity",
+                EdgePolarity::Light,
+                &[("light", EdgePolarity::Light), ("dark", EdgePolarity::Dark)],
+            )?,
+        },
+        "unpremultiply" => Op::Unpremultiply,
+        "alpha_mask" => Op::AlphaMask,
+        "edges_to_alpha" => Op::EdgesToAlpha {
+            polarity: properties.choice(
+                "polarity",
+                EdgePolarity::Light,
+                &[("light", EdgePolarity::Light), ("dark", EdgePolarity::Dark)],
+            )?,
+        },
+        "colorize" => {
+            let polarity = properties.choice(
+                "polarity",
+                EdgePolarity::Light,
+                &[("light", EdgePolarity::Light), ("dark", EdgePolarity::Dark)],
+            )?;
+            let ink = properties.color("ink", [0.0, 0.0, 0.0, 1.0])?;
+            let paper = properties.color("paper", [1.0, 1.0, 1.0, 1.0])?;
+            let gradient = match properties.text("gradient")? {
+                Some(text) => {
+                    Gradient::parse(text).map_err(|error| properties.invalid("gradient", error))?
+                }
+                None => Gradient::ink_on_paper(ink, paper),
+            };
+            Op::Colorize { polarity, gradient }
+        }
+        "structure_tensor" => Op::StructureTensor,
+        "flow_dog" => {
+            let sigma_1 = properties.positive("sigma_1", 3.0, f32::INFINITY)?;
+            let sigma_2 = properties.positive("sigma_2", 5.0, f32::INFINITY)?;
+            if sigma_2 <= sigma_1 {
+                return Err(
+                    properties.invalid("sigma_2", format!("larger than sigma_1 ({})", sigma_1))
+                );
+            }
+            Op::FlowDog { sigma_1, sigma_2 }
+        }
+        "flow_blur" => Op::FlowBlur {
+            standard_deviation: properties.non_negative("standard_deviation", 3.0)?,
+        },
+        "anisotropic_kuwahara" => Op::AnisotropicKuwahara {
+            radius: properties.positive("radius", 6.0, 50.0)?,
+            sharpness: properties.non_negative("sharpness", 8.0)?,
+            alpha: properties.positive("alpha", 1.0, f32::INFINITY)?,
+        },
+        "flow_tangent" => Op::FlowTangent {
+            encoding: properties.choice(
+                "encoding",
+                TangentEncoding::Hue,
+                &[
+                    ("hue", TangentEncoding::Hue),
+                    ("vector", TangentEncoding::Vector),
+                ],
+            )?,
+        },
+        "coherence" => Op::Coherence,
+        kind => {
+            return Err(GraphError::UnknownNodeType {
+                node: node.id.clone(),
+                kind: kind.to_owned(),
+            })
+        }
+    };
+    properties.finish()?;
+    Ok(op)
+}
+
+/// Reads the `width` of a soft threshold `mode`, and rejects it for a hard
+/// one, which has no width.
+fn threshold_width(
+    properties: &mut Properties,
+    mode: ThresholdMode,
+) -> Result<ThresholdMode, GraphError> {
+    Ok(match mode {
+        ThresholdMode::Hard => {
+            properties.reject("width", "mode smoothstep and tanh")?;
+            ThresholdMode::Hard
+        }
+        ThresholdMode::Smoothstep { width } => ThresholdMode::Smoothstep {
+            width: properties.non_negative("width", width)?,
+        },
+        ThresholdMode::Tanh { width } => ThresholdMode::Tanh {
+            width: properties.non_negative("width", width)?,
+        },
+    })
+}
+
+/// Hands out a node's properties, remembering which ones were used so that
+/// leftovers can be reported as unknown.
+struct Properties<'a> {
+    node: &'a str,
+    values: BTreeMap<&'a str, &'a PropertyValue>,
+}
+
+impl<'a> Properties<'a> {
+    fn new(node: &'a NodeDescription) -> Self {
+        Self {
+            node: &node.id,
+            values: node
+                .properties
+                .iter()
+                .map(|(name, value)| (name.as_str(), value))
+                .collect(),
+        }
+    }
+
+    fn number(&mut self, name: &str, default: f32) -> Result<f32, GraphError> {
+        match self.values.remove(name) {
+            None => Ok(default),
+            Some(PropertyValue::Number(value)) if value.is_finite() => Ok(*value as f32),
+            Some(_) => Err(self.invalid(name, "a number".to_owned())),
+        }
+    }
+
+    fn non_negative(&mut self, name: &str, default: f32) -> Result<f32, GraphError> {
+        let value = self.number(name, default)?;
+        if value < 0.0 {
+            return Err(self.invalid(name, "a non-negative number".to_owned()));
+        }
+        Ok(value)
+    }
+
+    /// A number larger than zero and at most `max`.
+    fn positive(&mut self, name: &str, default: f32, max: f32) -> Result<f32, GraphError> {
+        let value = self.number(name, default)?;
+        if value <= 0.0 || value > max {
+            let expected = if max.is_finite() {
+                format!("a positive number up to {}", max)
+            } else {
+                "a positive number".to_owned()
+            };
+            return Err(self.invalid(name, expected));
+        }
+        Ok(value)
+    }
+
+    fn number_in(
+        &mut self,
+        name: &str,
+        default: f32,
+        range: RangeInclusive<f32>,
+    ) -> Result<f32, GraphError> {
+        let value = self.number(name, default)?;
+        if !range.contains(&value) {
+            return Err(self.invalid(
+                name,
+                format!("a number from {} to {}", range.start(), range.end()),
+            ));
+        }
+        Ok(value)
+    }
+
+    fn integer(
+        &mut self,
+        name: &str,
+        default: u32,
+        range: RangeInclusive<u32>,
+    ) -> Result<u32, GraphError> {
+        match self.values.remove(name) {
+            None => Ok(default),
+            Some(PropertyValue::Number(value))
+                if value.fract() == 0.0 && *value >= 0.0 && range.contains(&(*value as u32)) =>
+            {
+                Ok(*value as u32)
+            }
+            Some(_) => Err(self.invalid(
+                name,
+                format!("a whole number from {} to {}", range.start(), range.end()),
+            )),
+        }
+    }
+
+    fn text(&mut self, name: &str) -> Result<Option<&'a str>, GraphError> {
+        match self.values.remove(name) {
+            None => Ok(None),
+            Some(PropertyValue::Text(text)) => Ok(Some(text)),
+            Some(_) => Err(self.invalid(name, "a string".to_owned())),
+        }
+    }
+
+    fn color(&mut self, name: &str, default: [f32; 4]) -> Result<[f32; 4], GraphError> {
+        match self.text(name)? {
+            None => Ok(default),
+            Some(text) => parse_color(text)
+                .map_err(|_| self.invalid(name, "a color like #rrggbb or #rrggbbaa".to_owned())),
+        }
+    }
+
+    fn choice<T: Copy>(
+        &mut self,
+        name: &str,
+        default: T,
+        choices: &[(&str, T)],
+    ) -> Result<T, GraphError> {
+        let value = match self.values.remove(name) {
+            None => return Ok(default),
+            Some(value) => value,
+        };
+        if let PropertyValue::Text(text) = value {
+            if let Some((_, choice)) = choices.iter().find(|(choice, _)| choice == text) {
+                return Ok(*choice);
+            }
+        }
+        let names: Vec<_> = choices.iter().map(|(choice, _)| *choice).collect();
+        Err(self.invalid(name, format!("one of {}", names.join(", "))))
+    }
+
+    /// Fails if the node sets a property that its other settings don't use.
+    fn reject(&mut self, name: &str, applies_to: &str) -> Result<(), GraphError> {
+        match self.values.remove(name) {
+            None => Ok(()),
+            Some(_) => Err(GraphError::InapplicableProperty {
+                node: self.node.to_owned(),
+                property: name.to_owned(),
+                applies_to: applies_to.to_owned(),
+            }),
+        }
+    }
+
+    fn invalid(&self, name: &str, expected: String) -> GraphError {
+        GraphError::InvalidProperty {
+            node: self.node.to_owned(),
+            property: name.to_owned(),
+            expected,
+        }
+    }
+
+    fn finish(self) -> Result<(), GraphError> {
+        match self.values.keys().next() {
+            Some(property) => Err(GraphError::UnknownProperty {
+                node: self.node.to_owned(),
+                property: (*property).to_owned(),
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a graph from TOML nodes with `output` as the output node.
+    fn build(output: &str, nodes: &str) -> Result<Graph, GraphError> {
+        GraphDescription::from_toml(&format!("output = \"{}\"\n{}", output, nodes))?.build()
+    }
+
+    fn node(id: &str, kind: &str, inputs: &[&str], properties: &str) -> String {
+        let inputs: Vec<_> = inputs
+            .iter()
+            .map(|input| format!("\"{}\"", input))
+            .collect();
+        format!(
+            "[[nodes]]\nid = \"{}\"\ntype = \"{}\"\ninputs = [{}]\n{}\n",
+            id,
+            kind,
+            inputs.join(", "),
+            properties
+        )
+    }
+
+    fn invalid_property(error: GraphError) -> String {
+        match error {
+            GraphError::InvalidProperty { node, property, .. } => format!("{}.{}", node, property),
+            error => panic!("unexpected error: {}", error),
+        }
+    }
+
+    #[test]
+    fn nodes_are_ordered_after_their_inputs() {
+        let graph = build(
+            "subtract",
+            &[
+                node("subtract", "blend", &["blur_1", "blur_2"], ""),
+                node("blur_2", "gaussian_blur", &["blur_1"], ""),
+                node("blur_1", "gaussian_blur", &["input"], ""),
+            ]
+            .concat(),
+        )
+        .unwrap();
+        let ids: Vec<_> = graph.nodes().iter().map(|node| node.id.as_str()).collect();
+        assert_eq!(ids, ["blur_1", "blur_2", "subtract"]);
+        assert_eq!(graph.output(), 2);
+    }
+
+    #[test]
+    fn cycles_are_rejected() {
+        let error = build(
+            "a",
+            &[
+                node("a", "gaussian_blur", &["b"], ""),
+                node("b", "gaussian_blur", &["a"], ""),
+            ]
+            .concat(),
+        )
+        .unwrap_err();
+        assert!(matches!(error, GraphError::Cycle(_)));
+        let error = build("a", &node("a", "gaussian_blur", &["a"], "")).unwrap_err();
+        assert!(matches!(error, GraphError::Cycle(node) if node == "a"));
+    }
+
+    #[test]
+    fn missing_inputs_and_outputs_are_rejected() {
+        let error = build("a", &node("a", "gaussian_blur", &["nothing"], "")).unwrap_err();
+        assert!(
+            matches!(error, GraphError::MissingInput { node, input } if node == "a" && input == "nothing")
+        );
+        let error = build("b", &node("a", "gaussian_blur", &["input"], "")).unwrap_err();
+        assert!(matches!(error, GraphError::MissingOutput(node) if node == "b"));
+        let error = build("a", &node("a", "blend", &["input"], "")).unwrap_err();
+        assert!(matches!(
+            error,
+            GraphError::InputCount {
+                expected: 2,
+                actual: 1,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn duplicate_and_reserved_ids_are_rejected() {
+        let blur = node("a", "gaussian_blur", &["input"], "");
+        let error = build("a", &[blur.clone(), blur].concat()).unwrap_err();
+        assert!(matches!(error, GraphError::DuplicateNode(node) if node == "a"));
+        let error = build("input", &node("input", "grayscale", &["input"], "")).unwrap_err();
+        assert!(matches!(error, GraphError::DuplicateNode(node) if node == "input"));
+    }
+
+    #[test]
+    fn unknown_types_and_properties_are_rejected() {
+        let error = build("a", &node("a", "sharpen", &["input"], "")).unwrap_err();
+        assert!(matches!(error, GraphError::UnknownNodeType { kind, .. } if kind == "sharpen"));
+        let error = build("a", &node("a", "gaussian_blur", &["input"], "sigma = 2.0")).unwrap_err();
+        assert!(
+            matches!(error, GraphError::UnknownProperty { node, property } if node == "a" && property == "sigma")
+        );
+    }
+
+    #[test]
+    fn properties_of_unsupported_types_name_the_node() {
+        let error =
+            build("a", &node("a", "gaussian_blur", &["input"], "bogus = true")).unwrap_err();
+        assert!(
+            matches!(error, GraphError::UnknownProperty { property, .. } if property == "bogus")
+        );
+        let error = build(
+            "a",
+            &node(
+                "a",
+                "gaussian_blur",
+                &["input"],
+                "standard_deviation = [1.0]",
+            ),
+        )
+        .unwrap_err();
+        assert_eq!(invalid_property(error), "a.standard_deviation");
+    }
+
+    #[test]
+    fn out_of_range_properties_are_rejected() {
+        for (kind, properties) in [
+            ("gaussian_blur", "standard_deviation = -1.0"),
+            ("threshold", "threshold = 1001.0"),
+            ("cross_fade", "weight = 1.5"),
+            ("bilateral", "spatial_sigma = 51.0"),
+            ("quantize", "levels = 1"),
+            ("quantize", "levels = 2.5"),
+            ("morphology", "radius = 33"),
+            ("blend", "mode = \"overlay\""),
+            ("colorize", "ink = \"red\""),
+        ] {
+            let inputs: &[&str] = if kind == "blend" || kind == "cross_fade" {
+                &["input", "input"]
+            } else {
+                &["input"]
+            };
+            let error = build("a", &node("a", kind, inputs, properties)).unwrap_err();
+            let property = properties.split(' ').next().unwrap();
+            assert_eq!(invalid_property(error), format!("a.{}", property));
+        }
+    }
+
+    #[test]
+    fn properties_are_checked_against_each_other() {
+        let error = build(
+            "a",
+            &node("a", "hysteresis", &["input"], "low = 0.9\nhigh = 0.1"),
+        )
+        .unwrap_err();
+        assert_eq!(invalid_property(error), "a.low");
+        let error = build(
+            "a",
+            &node(
+                "a",
+                "flow_dog",
+                &["input", "input"],
+                "sigma_1 = 2.0\nsigma_2 = 2.0",
+            ),
+        )
+        .unwrap_err();
+        assert_eq!(invalid_property(error), "a.sigma_2");
+    }
+
+    #[test]
+    fn properties_the_node_does_not_use_are_rejected() {
+        for properties in [
+            "fraction = 0.2",
+            "method = \"percentile\"\nlow_ratio = 0.3",
+            "mode = \"hysteresis\"\nwidth = 0.1",
+            "mode = \"hard\"\nwidth = 0.1",
+        ] {
+            let error =
+                build("a", &node("a", "auto_threshold", &["input"], properties)).unwrap_err();
+            assert!(
+                matches!(error, GraphError::InapplicableProperty { .. }),
+                "{}",
+                properties
+            );
+        }
+        let error = build("a", &node("a", "threshold", &["input"], "width = 0.1")).unwrap_err();
+        assert!(
+            matches!(error, GraphError::InapplicableProperty { property, .. } if property == "width")
+        );
+        let graph = build(
+            "a",
+            &node(
+                "a",
+                "auto_threshold",
+                &["input"],
+                "method = \"density\"\nfraction = 0.2\nmode = \"hysteresis\"\nlow_ratio = 0.3",
+            ),
+        )
+        .unwrap();
+        assert!(matches!(
+            graph.nodes()[0].op,
+            Op::AutoThreshold {
+                selection: ThresholdSelection::Density { fraction },
+                low_ratio: Some(low_ratio),
+                ..
+            } if fraction == 0.2 && low_ratio == 0.3
+        ));
+    }
+}
@@ -0,0 +1,919 @@
+//! Effect graphs that can be described in a TOML or JSON file.
+//!
+//! A description lists the nodes of the graph and names the one whose result
+//! is the output:
+//!
+//! ```toml
+//! output = "grayscale"
+//!
+//! [[nodes]]
+//! id = "blur_1"
+//! type = "gaussian_blur"
+//! inputs = ["input"]
+//! standard_deviation = 3.0
+//! ```
+//!
+//! An optional top-level `precision` of `unorm8` (the default), `float16` or
+//! `float32` picks the precision intermediate images are stored at.
+//!
+//! Each node's `inputs` are the edges leading into it, naming either another
+//! node or `input` for the source image. The node types and their properties
+//! are:
+//!
+//! | type                      | inputs                  | properties                                         |
+//! |---------------------------|-------------------------|----------------------------------------------------|
+//! | `gaussian_blur`           | 1                       | `standard_deviation`                               |
+//! | `blend`                   | 2 (destination, source) | `mode`                                             |
+//! | `cross_fade`              | 2 (destination, source) | `weight`                                           |
+//! | `threshold`               | 1                       | `threshold`, `mode`, `width`                       |
+//! | `hysteresis`              | 1                       | `low`, `high`                                      |
+//! | `auto_threshold`          | 1                       | `method`, `fraction`, `mode`, `width`, `low_ratio` |
+//! | `signed_difference`       | 2 (a, b)                | `gain`, `bias`                                     |
+//! | `xdog`                    | 2 (narrow, wide)        | `sharpness`, `epsilon`, `phi`                      |
+//! | `zero_crossing`           | 1                       | `min_slope`                                        |
+//! | `grayscale`               | 1                       |                                                    |
+//! | `luminance`               | 1                       | `model`                                            |
+//! | `bilateral`               | 1                       | `spatial_sigma`, `range_sigma`                     |
+//! | `quantize`                | 1                       | `levels`, `sharpness`                              |
+//! | `morphology`              | 1                       | `operation`, `shape`, `radius`                     |
+//! | `remove_small_components` | 1                       | `min_area`, `polarity`                             |
+//! | `skeletonize`             | 1                       | `polarity`                                         |
+//! | `unpremultiply`           | 1                       |                                                    |
+//! | `alpha_mask`              | 2 (image, mask)         |                                                    |
+//! | `edges_to_alpha`          | 1                       | `polarity`                                         |
+//! | `colorize`                | 1                       | `polarity`, `ink`, `paper`, `gradient`             |
+//! | `structure_tensor`        | 1                       |                                                    |
+//! | `flow_dog`                | 2 (image, tensor)       | `sigma_1`, `sigma_2`                               |
+//! | `flow_blur`               | 2 (image, tensor)       | `standard_deviation`                               |
+//! | `anisotropic_kuwahara`    | 2 (image, tensor)       | `radius`, `sharpness`, `alpha`                     |
+//! | `flow_tangent`            | 1 (tensor)              | `encoding`                                         |
+//! | `coherence`               | 1 (tensor)              |                                                    |
+//!
+//! `hysteresis` thresholds like Canny: responses of at least `high` are
+//! edges, and so are responses of at least `low` that connect to them. It
+//! has to follow lines across the whole image, so the D2D backend copies its
+//! input back and runs it on the CPU.
+//!
+//! `auto_threshold` picks the threshold for each image from the histogram
+//! of its input: `otsu` uses Otsu's method, `percentile` keeps the strongest
+//! `fraction` of the positive responses and `density` turns `fraction` of
+//! the image into edges. Its `mode` is one of the `threshold` modes or
+//! `hysteresis`, which keeps responses down to `low_ratio` times the picked
+//! value. Like `hysteresis` it runs on the CPU. `fraction`, `width` and
+//! `low_ratio` are rejected when the method or mode doesn't use them, and so
+//! is the `width` of a hard `threshold`.
+//!
+//! `luminance` converts to `rec601` or `rec709` luma, or to `lab` lightness
+//! L*. Putting it in front of the blurs makes every later node work on a
+//! single gray channel.
+//!
+//! `bilateral` smooths an image while keeping its edges, weighting
+//! neighbours by a Gaussian of their distance with `spatial_sigma` and one of
+//! their color difference with `range_sigma`. Chaining a few of them in front
+//! of the blurs keeps noise from turning into speckles.
+//!
+//! `unpremultiply` replaces translucent pixels with their opaque straight
+//! color, so soft alpha doesn't darken the image before it's filtered.
+//! `alpha_mask` scales an image by the alpha of its mask, e.g. the source
+//! image, and `edges_to_alpha` draws the `light` or `dark` lines of an edge
+//! image in black on a transparent background.
+//!
+//! `colorize` maps how much of a line each pixel is through a gradient from
+//! `paper` to `ink`, which are colors like `#rrggbb` or `#rrggbbaa`. A
+//! `gradient` such as `"#ffffff, #ff8800 0.3, #000000"` replaces the two.
+//!
+//! `blend` with a mode of `multiply` draws such lines over the source image
+//! for a toon look, and `cross_fade` mixes a `weight` of its source back
+//! over its destination to tone the lines down. See `graphs/toon.toml`.
+//! `quantize` flattens the source image into `levels` bands of lightness to
+//! draw them over, with steps as steep as `sharpness`.
+//!
+//! `morphology` cleans up an edge image: `erode` keeps the darkest and
+//! `dilate` the lightest pixel within `radius`, using a `square`, `disk` or
+//! `diamond` shaped neighbourhood. An `erode` followed by a `dilate` opens
+//! light lines, removing specks and spurs, and the reverse closes them,
+//! bridging small gaps. `remove_small_components` drops `light` or `dark`
+//! lines made of fewer than `min_area` connected pixels, and `skeletonize`
+//! thins them to a single pixel. Both of those need to see whole lines, so
+//! the D2D backend copies their input back and runs them on the CPU.
+//!
+//! `structure_tensor` packs the luminance gradients of its input into an
+//! image. Blurring that with `gaussian_blur` gives the edge tangent flow that
+//! `flow_dog` and `flow_blur` follow. `graphs/fdog.toml` builds the flow-based
+//! Difference of Gaussians out of them. The tensor holds squared gradients,
+//! which 8-bit buffers round to zero for all but strong edges, so graphs
+//! using it need `float16` or `float32` precision. `flow_dog` keeps the sign
+//! of its response at those precisions, like an unbiased
+//! `signed_difference`. `flow_tangent` draws the direction of the flow,
+//! either as a `hue` or as a `vector` in red and green, and `coherence` how
+//! clearly oriented it is.
+//!
+//! `anisotropic_kuwahara` follows the same flow to smooth an image into
+//! painterly strokes. Its elliptic kernel is `radius` wide in flat areas and
+//! stretches along edges, more so for smaller values of `alpha`, and
+//! `sharpness` decides how crisp the strokes are. `graphs/kuwahara.toml` runs
+//! it on its own, and in front of the blurs it works like `bilateral`.
+//!
+//! `zero_crossing` marks where the luminance of a signed response, such as an
+//! unbiased `signed_difference`, changes sign. It needs `float16` or `float32`
+//! precision, since 8-bit buffers clamp the negative half of the response.
+
+mod description;
+
+use std::{fmt, path::Path};
+
+use crate::{
+    cpu::{
+        BlendMode, EdgePolarity, Gradient, LuminanceModel, MorphologyOperation, Precision,
+        StructuringElement, TangentEncoding, ThresholdMode, ThresholdSelection,
+    },
+    AlphaMode, ColorMode, Composite, DogParams, Mode, MorphologyMode, Prefilter,
+};
+
+pub use description::GraphDescription;
+
+/// Name that refers to the image being processed in a node's inputs.
+pub const SOURCE_INPUT: &str = "input";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Op {
+    GaussianBlur {
+        standard_deviation: f32,
+    },
+    Blend {
+        mode: BlendMode,
+    },
+    /// `weight` of the source over `1 - weight` of the destination
+    CrossFade {
+        weight: f32,
+    },
+    Threshold {
+        threshold: f32,
+        mode: ThresholdMode,
+    },
+    /// Edges of at least `high`, grown into connected values of at least
+    /// `low`
+    Hysteresis {
+        low: f32,
+        high: f32,
+    },
+    /// Threshold picked from the histogram of the input, or a hysteresis
+    /// down to `low_ratio` times that value
+    AutoThreshold {
+        selection: ThresholdSelection,
+        mode: ThresholdMode,
+        low_ratio: Option<f32>,
+    },
+    Xdog {
+        sharpness: f32,
+        epsilon: f32,
+        phi: f32,
+    },
+    /// `bias + gain * (a - b)` without clamping
+    SignedDifference {
+        gain: f32,
+        bias: f32,
+    },
+    /// Marr–Hildreth edges where the input changes sign
+    ZeroCrossing {
+        min_slope: f32,
+    },
+    Grayscale,
+    Luminance {
+        model: LuminanceModel,
+    },
+    /// Painterly smoothing along the flow of the tensor
+    AnisotropicKuwahara {
+        radius: f32,
+        sharpness: f32,
+        alpha: f32,
+    },
+    /// Edge-preserving smoothing
+    Bilateral {
+        spatial_sigma: f32,
+        range_sigma: f32,
+    },
+    /// Luma snapped to `levels` flat bands, keeping the hue
+    Quantize {
+        levels: u32,
+        sharpness: f32,
+    },
+    /// Minimum or maximum under a structuring element
+    Morphology {
+        operation: MorphologyOperation,
+        shape: StructuringElement,
+        radius: u32,
+    },
+    /// Lines of fewer than `min_area` connected pixels turned into background
+    RemoveSmallComponents {
+        min_area: u32,
+        polarity: EdgePolarity,
+    },
+    /// Lines thinned to one pixel
+    Skeletonize {
+        polarity: EdgePolarity,
+    },
+    Unpremultiply,
+    /// The image scaled by the alpha of the mask
+    AlphaMask,
+    EdgesToAlpha {
+        polarity: EdgePolarity,
+    },
+    Colorize {
+        polarity: EdgePolarity,
+        gradient: Gradient,
+    },
+    StructureTensor,
+    FlowDog {
+        sigma_1: f32,
+        sigma_2: f32,
+    },
+    FlowBlur {
+        standard_deviation: f32,
+    },
+    FlowTangent {
+        encoding: TangentEncoding,
+    },
+    Coherence,
+}
+
+impl Op {
+    fn gaussian_blur(standard_deviation: f32) -> Self {
+        Op::GaussianBlur { standard_deviation }
+    }
+
+    pub fn input_count(&self) -> usize {
+        match self {
+            Op::GaussianBlur { .. }
+            | Op::Threshold { .. }
+            | Op::Hysteresis { .. }
+            | Op::AutoThreshold { .. }
+            | Op::ZeroCrossing { .. }
+            | Op::Grayscale
+            | Op::Luminance { .. }
+            | Op::Bilateral { .. }
+            | Op::Quantize { .. }
+            | Op::Morphology { .. }
+            | Op::RemoveSmallComponents { .. }
+            | Op::Skeletonize { .. }
+            | Op::Unpremultiply
+            | Op::EdgesToAlpha { .. }
+            | Op::Colorize { .. }
+            | Op::StructureTensor
+            | Op::FlowTangent { .. }
+            | Op::Coherence => 1,
+            Op::Blend { .. }
+            | Op::CrossFade { .. }
+            | Op::Xdog { .. }
+            | Op::SignedDifference { .. }
+            | Op::AlphaMask
+            | Op::FlowDog { .. }
+            | Op::FlowBlur { .. }
+            | Op::AnisotropicKuwahara { .. } => 2,
+        }
+    }
+}
+
+/// Where a node input comes from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Source {
+    /// The image being processed
+    Input,
+    /// The output of the node at this index in [`Graph::nodes`]
+    Node(usize),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Node {
+    pub id: String,
+    pub op: Op,
+    pub inputs: Vec<Source>,
+}
+
+impl Node {
+    fn new(id: &str, op: Op, inputs: Vec<Source>) -> Self {
+        Self {
+            id: id.to_owned(),
+            op,
+            inputs,
+        }
+    }
+}
+
+/// A validated effect graph. Nodes are stored in topological order, so every
+/// node only refers to nodes that come before it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Graph {
+    nodes: Vec<Node>,
+    output: usize,
+    precision: Precision,
+}
+
+impl Graph {
+    /// Loads a graph description, picking the format from the extension.
+    pub fn load<P: AsRef<Path>>(path: P) -> crate::Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)?;
+        let description = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => GraphDescription::from_json(&text)?,
+            _ => GraphDescription::from_toml(&text)?,
+        };
+        Ok(Self::from_description(description)?)
+    }
+
+    pub fn from_description(description: GraphDescription) -> Result<Self, GraphError> {
+        description.build()
+    }
+
+    /// The graph `DogPipeline` runs for a set of parameters.
+    pub fn from_params(params: &DogParams) -> Self {
+        let (threshold_id, threshold) = match (params.auto_threshold, params.low_threshold) {
+            (Some(selection), low) => (
+                "auto_threshold",
+                Op::AutoThreshold {
+                    selection,
+                    mode: params.threshold_mode,
+                    // The hysteresis keeps its proportions
+                    low_ratio: low.map(|low| {
+                        if low < params.threshold {
+                            low / params.threshold
+                        } else {
+                            1.0
+                        }
+                    }),
+                },
+            ),
+            (None, Some(low)) => (
+                "hysteresis",
+                Op::Hysteresis {
+                    low,
+                    high: params.threshold,
+                },
+            ),
+            (None, None) => (
+                "threshold",
+                Op::Threshold {
+                    threshold: params.threshold,
+                    mode: params.threshold_mode,
+                },
+            ),
+        };
+        let mut nodes = match params.mode {
+            Mode::Dog => vec![
+                Node::new(
+                    "blur_1",
+                    Op::gaussian_blur(params.sigma_1),
+                    vec![Source::Input],
+                ),
+                Node::new(
+                    "blur_2",
+                    Op::gaussian_blur(params.sigma_2),
+                    vec![Source::Input],
+                ),
+                // The subtract blend clamps, which only loses something when
+                // the buffers can hold negative values
+                match params.precision {
+                    Precision::Unorm8 => Node::new(
+                        "subtract",
+                        Op::Blend {
+                            mode: BlendMode::Subtract,
+                        },
+                        vec![Source::Node(0), Source::Node(1)],
+                    ),
+                    Precision::Float16 | Precision::Float32 => Node::new(
+                        "difference",
+                        Op::SignedDifference {
+                            gain: 1.0,
+                            bias: 0.0,
+                        },
+                        vec![Source::Node(0), Source::Node(1)],
+                    ),
+                },
+                Node::new(threshold_id, threshold, vec![Source::Node(2)]),
+            ],
+            Mode::Xdog => vec![
+                Node::new(
+                    "blur_1",
+                    Op::gaussian_blur(params.sigma_1),
+                    vec![Source::Input],
+                ),
+                Node::new(
+                    "blur_2",
+                    Op::gaussian_blur(params.sigma_2),
+                    vec![Source::Input],
+                ),
+                Node::new(
+                    "xdog",
+                    Op::Xdog {
+                        sharpness: params.sharpness,
+                        epsilon: params.epsilon,
+                        phi: params.phi,
+                    },
+                    vec![Source::Node(0), Source::Node(1)],
+                ),
+            ],
+            Mode::Fdog => vec![
+                Node::new("structure_tensor", Op::StructureTensor, vec![Source::Input]),
+                Node::new(
+                    "flow",
+                    Op::gaussian_blur(params.tensor_sigma),
+                    vec![Source::Node(0)],
+                ),
+                Node::new(
+                    "flow_dog",
+                    Op::FlowDog {
+                        sigma_1: params.sigma_1,
+                        sigma_2: params.sigma_2,
+                    },
+                    vec![Source::Input, Source::Node(1)],
+                ),
+                Node::new(
+                    "flow_blur",
+                    Op::FlowBlur {
+                        standard_deviation: params.flow_sigma,
+                    },
+                    vec![Source::Node(2), Source::Node(1)],
+                ),
+                Node::new(threshold_id, threshold, vec![Source::Node(3)]),
+            ],
+            Mode::ZeroCrossing => vec![
+                Node::new(
+                    "blur_1",
+                    Op::gaussian_blur(params.sigma_1),
+                    vec![Source::Input],
+                ),
+                Node::new(
+                    "blur_2",
+                    Op::gaussian_blur(params.sigma_2),
+                    vec![Source::Input],
+                ),
+                Node::new(
+                    "difference",
+                    Op::SignedDifference {
+                        gain: 1.0,
+                        bias: 0.0,
+                    },
+                    vec![Source::Node(0), Source::Node(1)],
+                ),
+                Node::new(
+                    "zero_crossing",
+                    Op::ZeroCrossing {
+                        min_slope: params.min_slope,
+                    },
+                    vec![Source::Node(2)],
+                ),
+            ],
+        };
+        match params.color_mode {
+            // Colorizing converts to grayscale on its own
+            ColorMode::Grayscale if params.colorize.is_none() => {
+                let edges = nodes.len() - 1;
+                nodes.push(Node::new(
+                    "grayscale",
+                    Op::Grayscale,
+                    vec![Source::Node(edges)],
+                ));
+            }
+            // Everything after the luminance node is gray already
+            ColorMode::Luminance(model) => prepend(
+                &mut nodes,
+                Node::new("luminance", Op::Luminance { model }, vec![Source::Input]),
+            ),
+            ColorMode::Grayscale | ColorMode::Color => {}
+        }
+        // Smooth the color image, ahead of any conversion to luminance
+        let prefilter = match params.prefilter {
+            Some(prefilter) => {
+                prefilter_nodes(prefilter, params.prefilter_iterations, params.tensor_sigma)
+            }
+            None => Vec::new(),
+        };
+        let smoothed = usize::from(params.unpremultiply) + prefilter.len();
+        let smoothed = match prefilter.is_empty() {
+            true => Source::Input,
+            false => Source::Node(smoothed - 1),
+        };
+        prepend_all(&mut nodes, prefilter);
+        if params.unpremultiply {
+            prepend(
+                &mut nodes,
+                Node::new("unpremultiply", Op::Unpremultiply, vec![Source::Input]),
+            );
+        }
+        // XDoG draws dark lines on white, the other modes light ones
+        let polarity = match params.mode {
+            Mode::Xdog => EdgePolarity::Dark,
+            Mode::Dog | Mode::Fdog | Mode::ZeroCrossing => EdgePolarity::Light,
+        };
+        // Clean up the lines, whichever way round they are drawn
+        if let Some(morphology) = params.morphology {
+            let (thin, thicken) = match polarity {
+                EdgePolarity::Light => (MorphologyOperation::Erode, MorphologyOperation::Dilate),
+                EdgePolarity::Dark => (MorphologyOperation::Dilate, MorphologyOperation::Erode),
+            };
+            let steps: &[(&str, MorphologyOperation)] = match morphology.mode {
+                MorphologyMode::Erode => &[("erode", thin)],
+                MorphologyMode::Dilate => &[("dilate", thicken)],
+                MorphologyMode::Open => &[("open_erode", thin), ("open_dilate", thicken)],
+                MorphologyMode::Close => &[("close_dilate", thicken), ("close_erode", thin)],
+            };
+            for (id, operation) in steps {
+                let lines = nodes.len() - 1;
+                nodes.push(Node::new(
+                    id,
+                    Op::Morphology {
+                        operation: *operation,
+                        shape: morphology.shape,
+                        radius: morphology.radius,
+                    },
+                    vec![Source::Node(lines)],
+                ));
+            }
+        }
+        if params.min_component_area > 0 {
+            let lines = nodes.len() - 1;
+            nodes.push(Node::new(
+                "remove_small_components",
+                Op::RemoveSmallComponents {
+                    min_area: params.min_component_area,
+                    polarity,
+                },
+                vec![Source::Node(lines)],
+            ));
+        }
+        if params.skeletonize {
+            let lines = nodes.len() - 1;
+            nodes.push(Node::new(
+                "skeletonize",
+                Op::Skeletonize { polarity },
+                vec![Source::Node(lines)],
+            ));
+        }
+        let edges = nodes.len() - 1;
+        // Quantizing blends the lines over the bands with the composite if
+        // one is given, and multiplies them otherwise
+        let composite = match (params.composite, params.quantize) {
+            (None, Some(_)) => Some(Composite::default()),
+            (composite, _) => composite,
+        };
+        // Blending needs lines that darken or lighten the image depending on
+        // the mode: black on white for the darkening modes, white on black
+        // for the others
+        let colorize = match (&params.colorize, &composite) {
+            (Some(gradient), _) => Some(gradient.clone()),
+            (None, Some(composite)) => {
+                let black = [0.0, 0.0, 0.0, 1.0];
+                let white = [1.0, 1.0, 1.0, 1.0];
+                Some(match composite.mode {
+                    BlendMode::Multiply | BlendMode::Darken => Gradient::ink_on_paper(black, white),
+                    BlendMode::Screen
+                    | BlendMode::Lighten
+                    | BlendMode::Difference
+                    | BlendMode::Subtract => Gradient::ink_on_paper(white, black),
+                })
+            }
+            (None, None) => None,
+        };
+        if let Some(gradient) = colorize {
+            nodes.push(Node::new(
+                "colorize",
+                Op::Colorize { polarity, gradient },
+                vec![Source::Node(edges)],
+            ));
+        }
+        if let Some(composite) = composite {
+            let lines = nodes.len() - 1;
+            // The flat color regions go under the lines
+            let image = match params.quantize {
+                Some(quantize) => {
+                    // Bands of the smoothed image have cleaner borders
+                    nodes.push(Node::new(
+                        "quantize",
+                        Op::Quantize {
+                            levels: quantize.levels,
+                            sharpness: quantize.sharpness,
+                        },
+                        vec![smoothed],
+                    ));
+                    Source::Node(lines + 1)
+                }
+                None => Source::Input,
+            };
+            nodes.push(Node::new(
+                "composite",
+                Op::Blend {
+                    mode: composite.mode,
+                },
+                vec![image, Source::Node(lines)],
+            ));
+            if composite.opacity < 1.0 {
+                let blended = nodes.len() - 1;
+                nodes.push(Node::new(
+                    "opacity",
+                    Op::CrossFade {
+                        weight: composite.opacity,
+                    },
+                    vec![image, Source::Node(blended)],
+                ));
+            }
+        }
+        let colored = nodes.len() - 1;
+        match params.alpha_mode {
+            AlphaMode::Opaque => {}
+            AlphaMode::Preserve => nodes.push(Node::new(
+                "alpha_mask",
+                Op::AlphaMask,
+                vec![Source::Node(colored), Source::Input],
+            )),
+            AlphaMode::Transparent => {
+                nodes.push(Node::new(
+                    "edges_to_alpha",
+                    Op::EdgesToAlpha { polarity },
+                    vec![Source::Node(edges)],
+                ));
+                // Keep the colors, only where there are lines
+                if colored != edges {
+                    nodes.push(Node::new(
+                        "alpha_mask",
+                        Op::AlphaMask,
+                        vec![Source::Node(colored), Source::Node(colored + 1)],
+                    ));
+                }
+            }
+        }
+        let output = nodes.len() - 1;
+        // Zero crossings need the negative half of the response, and the
+        // structure tensor needs more than 8 bits to keep weak gradients
+        let uses_flow = params.mode == Mode::Fdog
+            || matches!(
+                params.prefilter,
+                Some(Prefilter::AnisotropicKuwahara { .. })
+            );
+        let precision = match params.precision {
+            Precision::Unorm8 if params.mode == Mode::ZeroCrossing || uses_flow => {
+                Precision::Float16
+            }
+            precision => precision,
+        };
+        Self {
+            nodes,
+            output,
+            precision,
+        }
+    }
+
+    /// A graph that computes the edge tangent flow the same way `--mode fdog`
+    /// does. Its nodes are the raw and smoothed structure tensor, followed by
+    /// the tangent and coherence of the smoothed one, stored at `precision`
+    /// but at least at `float16`.
+    pub fn flow_field(tensor_sigma: f32, encoding: TangentEncoding, precision: Precision) -> Self {
+        let nodes = vec![
+            Node::new("structure_tensor", Op::StructureTensor, vec![Source::Input]),
+            Node::new(
+                "tensor",
+                Op::gaussian_blur(tensor_sigma),
+                vec![Source::Node(0)],
+            ),
+            Node::new(
+                "tangent",
+                Op::FlowTangent { encoding },
+                vec![Source::Node(1)],
+            ),
+            Node::new("coherence", Op::Coherence, vec![Source::Node(1)]),
+        ];
+        let precision = match precision {
+            Precision::Unorm8 => Precision::Float16,
+            precision => precision,
+        };
+        Self {
+            nodes,
+            output: 2,
+            precision,
+        }
+    }
+
+    /// Adds a node drawing the signed response around mid gray next to every
+    /// subtract blend and unbiased `signed_difference`, named after it with a
+    /// `_signed` suffix. Dumping the graph then shows the negative half of the
+    /// response, which the blend and 8-bit files clamp away. Existing nodes
+    /// keep their indices.
+    pub fn with_signed_differences(&self, gain: f32) -> Self {
+        let mut graph = self.clone();
+        for node in &self.nodes {
+            let scale = match node.op {
+                Op::Blend {
+                    mode: BlendMode::Subtract,
+                } => 1.0,
+                Op::SignedDifference { gain, bias: 0.0 } => gain,
+                _ => continue,
+            };
+            let mut id = format!("{}_signed", node.id);
+            while graph.nodes.iter().any(|node| node.id == id) {
+                id.push('_');
+            }
+            graph.nodes.push(Node {
+                id,
+                op: Op::SignedDifference {
+                    gain: 0.5 * gain * scale,
+                    bias: 0.5,
+                },
+                inputs: node.inputs.clone(),
+            });
+        }
+        graph
+    }
+
+    /// Precision intermediate images are stored at.
+    pub fn precision(&self) -> Precision {
+        self.precision
+    }
+
+    pub fn set_precision(&mut self, precision: Precision) {
+        self.precision = precision;
+    }
+
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    /// Index of the node whose result is the graph's output.
+    pub fn output(&self) -> usize {
+        self.output
+    }
+}
+
+/// Inserts `node` in front of `nodes`, feeding it the source image in place
+/// of the nodes that read the source image so far.
+fn prepend(nodes: &mut Vec<Node>, node: Node) {
+    prepend_all(nodes, vec![node]);
+}
+
+/// Inserts a chain of nodes in front of `nodes`, which then read the result
+/// of the chain's last node in place of the source image.
+fn prepend_all(nodes: &mut Vec<Node>, chain: Vec<Node>) {
+    let count = chain.len();
+    if count == 0 {
+        return;
+    }
+    for node in nodes.iter_mut() {
+        for source in &mut node.inputs {
+            *source = match *source {
+                Source::Input => Source::Node(count - 1),
+                Source::Node(index) => Source::Node(index + count),
+            };
+        }
+    }
+    nodes.splice(0..0, chain);
+}
+
+/// The nodes smoothing the source image `iterations` times, each pass
+/// working on the result of the last.
+fn prefilter_nodes(prefilter: Prefilter, iterations: u32, tensor_sigma: f32) -> Vec<Node> {
+    let mut nodes = Vec::new();
+    let (name, op, flow) = match prefilter {
+        Prefilter::Bilateral {
+            spatial_sigma,
+            range_sigma,
+        } => (
+            "bilateral",
+            Op::Bilateral {
+                spatial_sigma,
+                range_sigma,
+            },
+            None,
+        ),
+        Prefilter::AnisotropicKuwahara {
+            radius,
+            sharpness,
+            alpha,
+        } => {
+            // The flow of the unfiltered image steers every pass
+            nodes.push(Node::new(
+                "kuwahara_tensor",
+                Op::StructureTensor,
+                vec![Source::Input],
+            ));
+            nodes.push(Node::new(
+                "kuwahara_flow",
+                Op::gaussian_blur(tensor_sigma),
+                vec![Source::Node(0)],
+            ));
+            (
+                "kuwahara",
+                Op::AnisotropicKuwahara {
+                    radius,
+                    sharpness,
+                    alpha,
+                },
+                Some(Source::Node(1)),
+            )
+        }
+    };
+    let mut image = Source::Input;
+    for iteration in 1..=iterations {
+        let id = match iterations {
+            1 => name.to_owned(),
+            _ => format!("{}_{}", name, iteration),
+        };
+        let mut inputs = vec![image];
+        inputs.extend(flow);
+        nodes.push(Node::new(&id, op.clone(), inputs));
+        image = Source::Node(nodes.len() - 1);
+    }
+    nodes
+}
+
+/// Problems found while reading or validating a graph description.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GraphError {
+    Parse(String),
+    DuplicateNode(String),
+    UnknownNodeType {
+        node: String,
+        kind: String,
+    },
+    UnknownProperty {
+        node: String,
+        property: String,
+    },
+    InvalidProperty {
+        node: String,
+        property: String,
+        expected: String,
+    },
+    InapplicableProperty {
+        node: String,
+        property: String,
+        applies_to: String,
+    },
+    InputCount {
+        node: String,
+        expected: usize,
+        actual: usize,
+    },
+    MissingInput {
+        node: String,
+        input: String,
+    },
+    Cycle(String),
+    MissingOutput(String),
+    UnknownPrecision(String),
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GraphError::Parse(message) => write!(f, "could not parse graph: {}", message),
+            GraphError::DuplicateNode(node) => write!(f, "node '{}' is defined twice", node),
+            GraphError::UnknownNodeType { node, kind } => {
+                write!(f, "node '{}' has unknown type '{}'", node, kind)
+            }
+            GraphError::UnknownProperty { node, property } => {
+                write!(f, "node '{}' has unknown property '{}'", node, property)
+            }
+            GraphError::InvalidProperty {
+                node,
+                property,
+                expected,
+            } => write!(
+                f,
+                "property '{}' of node '{}' must be {}",
+                property, node, expected
+            ),
+            GraphError::InapplicableProperty {
+                node,
+                property,
+                applies_to,
+            } => write!(
+                f,
+                "property '{}' of node '{}' only applies to {}",
+                property, node, applies_to
+            ),
+            GraphError::InputCount {
+                node,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "node '{}' takes {} inputs but {} were given",
+                node, expected, actual
+            ),
+            GraphError::MissingInput { node, input } => write!(
+                f,
+                "input '{}' of node '{}' is not a node in the graph",
+                input, node
+            ),
+            GraphError::Cycle(node) => write!(f, "node '{}' is part of a cycle", node),
+            GraphError::MissingOutput(node) => {
+                write!(f, "output node '{}' is not in the graph", node)
+            }
+            GraphError::UnknownPrecision(precision) => write!(
+                f,
+                "unknown precision '{}', expected unorm8, float16 or float32",
+                precision
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GraphError {}
@@ -0,0 +1,287 @@
+//! Pure Rust image decoding and encoding.
+//!
+//! PNG, JPEG, BMP, TIFF and WebP files can be decoded. Output is encoded as
+//! PNG, JPEG, TIFF, BMP or lossless WebP, picked from the file extension
+//! unless [`SaveOptions::format`] forces one.
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufReader, Cursor, Write},
+    path::Path,
+};
+
+use image::{
+    codecs::{bmp::BmpEncoder, jpeg::JpegEncoder, tiff::TiffEncoder, webp::WebPEncoder},
+    error::{ImageFormatHint, UnsupportedError},
+    DynamicImage, ImageError, ImageFormat, ImageReader, Rgba32FImage,
+};
+
+use crate::{bitmap::Bitmap, cpu::Image, Result};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Png,
+    Jpeg,
+    Tiff,
+    Bmp,
+    /// Lossless WebP
+    WebP,
+}
+
+impl OutputFormat {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        match ImageFormat::from_path(path).ok()? {
+            ImageFormat::Png => Some(OutputFormat::Png),
+            ImageFormat::Jpeg => Some(OutputFormat::Jpeg),
+            ImageFormat::Tiff => Some(OutputFormat::Tiff),
+            ImageFormat::Bmp => Some(OutputFormat::Bmp),
+            ImageFormat::WebP => Some(OutputFormat::WebP),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PngCompression {
+    Fast,
+    #[default]
+    Balanced,
+    High,
+}
+
+/// Pixel layout of the encoded file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputColor {
+    /// Color with alpha, or color only for JPEG
+    #[default]
+    Rgba8,
+    /// 8-bit luminance without alpha
+    Gray8,
+    /// 1 bit per pixel black and white, PNG only
+    Gray1,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SaveOptions {
+    /// Replace an existing file at the output path
+    pub overwrite: bool,
+    /// Format to encode, or `None` to infer it from the file extension
+    pub format: Option<OutputFormat>,
+    /// JPEG quality from 1 to 100
+    pub jpeg_quality: u8,
+    pub png_compression: PngCompression,
+    pub color: OutputColor,
+}
+
+impl Default for SaveOptions {
+    fn default() -> Self {
+        Self {
+            overwrite: true,
+            format: None,
+            jpeg_quality: 90,
+            png_compression: PngCompression::default(),
+            color: OutputColor::default(),
+        }
+    }
+}
+
+/// Decodes the file at `path`, detecting the format from its contents.
+pub fn load_bitmap_from_path<P: AsRef<Path>>(path: P) -> Result<Bitmap> {
+    let source = decode(path.as_ref())?.into_rgba8();
+    Ok(Bitmap::from_rgba8(&source))
+}
+
+/// Encodes `bitmap` to `path`, creating any missing parent directories. No
+/// partial file is left behind if encoding or writing fails.
+pub fn save_bitmap_to_path<P: AsRef<Path>>(
+    bitmap: &Bitmap,
+    path: P,
+    options: &SaveOptions,
+) -> Result<()> {
+    encode(bitmap.to_rgba8().into(), path.as_ref(), options)
+}
+
+/// Decodes the file at `path` straight to float precision, so 16-bit sources
+/// keep their extra bits.
+pub fn load_image_from_path<P: AsRef<Path>>(path: P) -> Result<Image> {
+    let source = decode(path.as_ref())?.into_rgba32f();
+    let pixels = source
+        .pixels()
+        .map(|pixel| {
+            let [r, g, b, a] = pixel.0;
+            [r * a, g * a, b * a, a]
+        })
+        .collect();
+    Ok(Image::from_pixels(source.width(), source.height(), pixels))
+}
+
+/// Same as [`save_bitmap_to_path`] for an [`Image`]. The pixels are
+/// unpremultiplied at float precision before they are rounded to 8 bits, so
+/// translucent colors survive.
+pub fn save_image_to_path<P: AsRef<Path>>(
+    image: &Image,
+    path: P,
+    options: &SaveOptions,
+) -> Result<()> {
+    let pixels = image
+        .pixels()
+        .iter()
+        .flat_map(|&[r, g, b, a]| {
+            if a > 0.0 {
+                [r / a, g / a, b / a, a]
+            } else {
+                [0.0; 4]
+            }
+        })
+        .collect();
+    let source = Rgba32FImage::from_raw(image.width(), image.height(), pixels)
+        .expect("an image has four channels per pixel");
+    encode(source.into(), path.as_ref(), options)
+}
+
+pub fn to_unorm8(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+fn decode(path: &Path) -> Result<DynamicImage> {
+    let reader = ImageReader::new(BufReader::new(File::open(path)?)).with_guessed_format()?;
+    Ok(reader.decode()?)
+}
+
+fn encode(source: DynamicImage, path: &Path, options: &SaveOptions) -> Result<()> {
+    let format = match options.format {
+        Some(format) => format,
+        None => OutputFormat::from_path(path).ok_or_else(|| {
+            ImageError::Unsupported(UnsupportedError::from(ImageFormatHint::PathExtension(
+                path.extension().unwrap_or_default().into(),
+            )))
+        })?,
+    };
+    if options.color == OutputColor::Gray1 && format != OutputFormat::Png {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "1-bit output is only supported for PNG",
+        )
+        .into());
+    }
+
+    let output: DynamicImage = match options.color {
+        OutputColor::Rgba8 => source.into_rgba8().into(),
+        OutputColor::Gray8 | OutputColor::Gray1 => source.into_luma8().into(),
+    };
+
+    // Encode in memory first so that an encoder error leaves no truncated
+    // file behind
+    let mut buffer = Cursor::new(Vec::new());
+    match format {
+        OutputFormat::Png => encode_png(&output, &mut buffer, options)?,
+        OutputFormat::Jpeg => {
+            // JPEG has no alpha channel
+            let output = match output {
+                DynamicImage::ImageRgba8(_) => output.into_rgb8().into(),
+                output => output,
+            };
+            output.write_with_encoder(JpegEncoder::new_with_quality(
+                &mut buffer,
+                options.jpeg_quality,
+            ))?
+        }
+        OutputFormat::Tiff => output.write_with_encoder(TiffEncoder::new(&mut buffer))?,
+        OutputFormat::Bmp => output.write_with_encoder(BmpEncoder::new(&mut buffer))?,
+        OutputFormat::WebP => output.write_with_encoder(WebPEncoder::new_lossless(&mut buffer))?,
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = if options.overwrite {
+        File::create(path)?
+    } else {
+        // create_new fails atomically if something is already at the path
+        OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .map_err(|error| match error.kind() {
+                io::ErrorKind::AlreadyExists => {
+                    io::Error::new(error.kind(), format!("'{}' already exists", path.display()))
+                }
+                _ => error,
+            })?
+    };
+    if let Err(error) = file.write_all(buffer.get_ref()) {
+        // Don't leave a partial image behind, but only remove regular files
+        // so that writing to e.g. a device can't delete it
+        if file.metadata().is_ok_and(|metadata| metadata.is_file()) {
+            drop(file);
+            let _ = fs::remove_file(path);
+        }
+        return Err(error.into());
+    }
+
+    Ok(())
+}
+
+fn encode_png<W: Write>(output: &DynamicImage, writer: W, options: &SaveOptions) -> Result<()> {
+    let mut encoder = png::Encoder::new(writer, output.width(), output.height());
+    encoder.set_compression(match options.png_compression {
+        PngCompression::Fast => png::Compression::Fast,
+        PngCompression::Balanced => png::Compression::Balanced,
+        PngCompression::High => png::Compression::High,
+    });
+    let data = match options.color {
+        OutputColor::Rgba8 => {
+            encoder.set_color(png::ColorType::Rgba);
+            encoder.set_depth(png::BitDepth::Eight);
+            output.as_bytes().to_vec()
+        }
+        OutputColor::Gray8 => {
+            encoder.set_color(png::ColorType::Grayscale);
+            encoder.set_depth(png::BitDepth::Eight);
+            output.as_bytes().to_vec()
+        }
+        OutputColor::Gray1 => {
+            encoder.set_color(png::ColorType::Grayscale);
+            encoder.set_depth(png::BitDepth::One);
+            pack_bits(output.as_bytes(), output.width() as usize)
+        }
+    };
+    let mut png_writer = encoder.write_header().map_err(io::Error::other)?;
+    png_writer
+        .write_image_data(&data)
+        .map_err(io::Error::other)?;
+    png_writer.finish().map_err(io::Error::other)?;
+    Ok(())
+}
+
+/// Packs 8-bit luminance rows into 1 bit per pixel, most significant bit
+/// first, with every row padded to a whole byte.
+fn pack_bits(luma: &[u8], width: usize) -> Vec<u8> {
+    luma.chunks_exact(width)
+        .flat_map(|row| {
+            row.chunks(8).map(|pixels| {
+                pixels
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, value)| **value >= 128)
+                    .fold(0u8, |byte, (i, _)| byte | (0x80 >> i))
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn translucent_colors_are_unpremultiplied_at_float_precision() {
+        let alpha = 5.0 / 255.0;
+        let image = Image::from_pixels(1, 1, vec![[0.5 * alpha, 0.25 * alpha, alpha, alpha]]);
+        let path = std::env::temp_dir().join(format!("dogfun_io_test_{}.png", std::process::id()));
+        save_image_to_path(&image, &path, &SaveOptions::default()).unwrap();
+        let saved = decode(&path).unwrap().into_rgba8();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(saved.get_pixel(0, 0).0, [128, 64, 255, 5]);
+    }
+}
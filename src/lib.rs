@@ -0,0 +1,31 @@
+//! Messing around with the Difference of Gaussians.
+//!
+//! [`DogPipeline`] runs the effect graph either through Direct2D or on the
+//! portable CPU implementation in [`cpu`].
+
+mod bitmap;
+pub mod cpu;
+#[cfg(windows)]
+pub mod d2d;
+#[cfg(windows)]
+pub mod d3d11;
+#[cfg(windows)]
+pub mod effects;
+mod error;
+pub mod graph;
+#[cfg(windows)]
+pub mod imaging;
+pub mod io;
+mod pipeline;
+
+pub use bitmap::Bitmap;
+pub use cpu::{
+    BlendMode, EdgePolarity, Gradient, Image, LuminanceModel, MorphologyOperation, Precision,
+    StructuringElement, TangentEncoding, ThresholdMode, ThresholdSelection,
+};
+pub use error::{Error, Result};
+pub use graph::Graph;
+pub use pipeline::{
+    AlphaMode, Backend, ColorMode, Composite, DogParams, DogPipeline, Mode, Morphology,
+    MorphologyMode, PickedThreshold, Prefilter, Quantize,
+};
@@ -1,190 +1,127 @@
-mod d2d;
-mod d3d11;
-mod effects;
-mod imaging;
-
-use d2d::{create_d2d_device, create_d2d_factory};
-use d3d11::create_d3d_device;
-use effects::{register_custom_effects, threshold::THRESHOLD_EFFECT_CLSID};
-use imaging::{create_texture_from_bitmap, load_bitmap_from_path, save_texture_to_path};
-use windows::{
-    core::{Interface, Result},
-    Win32::{
-        Graphics::{
-            Direct2D::{
-                CLSID_D2D1Blend, CLSID_D2D1GaussianBlur, CLSID_D2D1Grayscale,
-                Common::{
-                    D2D1_BLEND_MODE_SUBTRACT, D2D1_BORDER_MODE_HARD,
-                    D2D1_COMPOSITE_MODE_SOURCE_OVER,
-                },
-                ID2D1Bitmap, ID2D1DeviceContext, ID2D1Effect, ID2D1Image, D2D1_BLEND_PROP_MODE,
-                D2D1_DEVICE_CONTEXT_OPTIONS_NONE, D2D1_GAUSSIANBLUR_PROP_BORDER_MODE,
-                D2D1_GAUSSIANBLUR_PROP_STANDARD_DEVIATION, D2D1_INTERPOLATION_MODE_LINEAR,
-                D2D1_PROPERTY_TYPE_FLOAT, D2D1_PROPERTY_TYPE_UNKNOWN,
-            },
-            Direct3D11::{
-                D3D11_BIND_RENDER_TARGET, D3D11_BIND_SHADER_RESOURCE, D3D11_TEXTURE2D_DESC,
-            },
-            Dxgi::IDXGISurface,
+mod batch;
+mod cli;
+
+use std::error::Error;
+
+use batch::Job;
+use cli::Args;
+use dogfun::{io, DogPipeline, Graph};
+
+fn main() {
+    let args = Args::parse_and_validate();
+    let jobs = args.jobs().unwrap_or_else(|error| error.exit());
+
+    let mut graph = match &args.graph {
+        Some(path) => match Graph::load(path) {
+            Ok(graph) => graph,
+            Err(error) => {
+                eprintln!("error: {}: {}", path.display(), error);
+                std::process::exit(1);
+            }
         },
-        System::WinRT::{RoInitialize, RO_INIT_MULTITHREADED},
-    },
-};
-
-fn main() -> Result<()> {
-    let args: Vec<_> = std::env::args().skip(1).collect();
-    let image_path = args.get(0).expect("Expected input file path!");
-
-    unsafe {
-        RoInitialize(RO_INIT_MULTITHREADED)?;
+        None => Graph::from_params(&args.params()),
+    };
+    if let Some(precision) = args.precision() {
+        graph.set_precision(precision);
     }
-
-    // Init D3D11 and D2D
-    let d3d_device = create_d3d_device()?;
-    let d2d_factory = create_d2d_factory()?;
-    register_custom_effects(&d2d_factory)?;
-    let d2d_device = create_d2d_device(&d2d_factory, &d3d_device)?;
-    let d2d_context = unsafe { d2d_device.CreateDeviceContext(D2D1_DEVICE_CONTEXT_OPTIONS_NONE)? };
-
-    // Load and decode the input image
-    let software_bitmap = load_bitmap_from_path(&image_path)?;
-
-    // Create our input texture
-    let input_texture = create_texture_from_bitmap(&d3d_device, &software_bitmap)?;
-
-    // Create our input bitmap
-    let input_bitmap = {
-        let surface: IDXGISurface = input_texture.cast()?;
-        unsafe { d2d_context.CreateBitmapFromDxgiSurface(&surface, None)? }
+    let graph = if args.dump_nodes {
+        graph.with_signed_differences(args.dump_gain)
+    } else {
+        graph
     };
-
-    // Create our output texture
-    let output_texture = {
-        let mut desc = D3D11_TEXTURE2D_DESC::default();
-        unsafe {
-            input_texture.GetDesc(&mut desc);
-        }
-        desc.BindFlags = (D3D11_BIND_SHADER_RESOURCE.0 | D3D11_BIND_RENDER_TARGET.0) as u32;
-
-        unsafe {
-            let mut texture = None;
-            d3d_device.CreateTexture2D(&desc, None, Some(&mut texture))?;
-            texture.unwrap()
+    let pipeline = match DogPipeline::from_graph(args.backend(), &graph) {
+        Ok(pipeline) => pipeline,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            std::process::exit(1);
         }
     };
-
-    // Create our output bitmap
-    let output_bitmap = {
-        let surface: IDXGISurface = output_texture.cast()?;
-        unsafe { d2d_context.CreateBitmapFromDxgiSurface(&surface, None)? }
+    let flow_field = args.flow_field(graph.precision());
+    let flow_pipeline = if args.export_flow {
+        match DogPipeline::from_graph(args.backend(), &flow_field) {
+            Ok(pipeline) => Some(pipeline),
+            Err(error) => {
+                eprintln!("error: {}", error);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        None
     };
-
-    // Setup our effect graph
-    unsafe {
-        d2d_context.SetTarget(&output_bitmap);
-    }
-    let blur_1 = create_gaussian_blur(&d2d_context, &input_bitmap, 3.0)?;
-    let blur_1_image: ID2D1Image = blur_1.cast()?;
-    let blur_2 = create_gaussian_blur(&d2d_context, &input_bitmap, 5.0)?;
-    let blur_2_image: ID2D1Image = blur_2.cast()?;
-    let subtract_effect = create_subtract_effect(&d2d_context, &blur_1_image, &blur_2_image)?;
-    let subtract_image: ID2D1Image = subtract_effect.cast()?;
-    let threshold_effect = create_threshold(&d2d_context, &subtract_image, 0.01)?;
-    let threshold_image: ID2D1Image = threshold_effect.cast()?;
-    let grayscale = create_grayscale(&d2d_context, &threshold_image)?;
-    let grayscale_image: ID2D1Image = grayscale.cast()?;
-
-    // Draw
-    unsafe {
-        d2d_context.BeginDraw();
-        d2d_context.Clear(None);
-        d2d_context.DrawImage(
-            &grayscale_image,
-            None,
-            None,
-            D2D1_INTERPOLATION_MODE_LINEAR,
-            D2D1_COMPOSITE_MODE_SOURCE_OVER,
-        );
-        d2d_context.EndDraw(None, None)?;
+    // The smoothed tensor, tangent and coherence nodes
+    let flow_nodes = [1, 2, 3];
+
+    let save_options = args.save_options();
+    let failures = run_batch(&args, &jobs, |job| {
+        let image = io::load_image_from_path(&job.input)?;
+        let nodes: Vec<usize> = if args.dump_nodes {
+            (0..graph.nodes().len()).collect()
+        } else {
+            vec![graph.output()]
+        };
+        let (mut images, thresholds) = pipeline.process_nodes_with_thresholds(&image, &nodes)?;
+        // Automatic thresholds are reported so they can be reused
+        for picked in &thresholds {
+            println!(
+                "{}: {} picked a threshold of {}",
+                job.input.display(),
+                graph.nodes()[picked.node].id,
+                picked.threshold
+            );
+        }
+        let output = if args.dump_nodes {
+            let width = (nodes.len() - 1).to_string().len().max(2);
+            for (index, (node, image)) in graph.nodes().iter().zip(&images).enumerate() {
+                let path = job.extra_output(&format!("{:0width$}_{}", index, node.id));
+                io::save_image_to_path(image, &path, &save_options)?;
+            }
+            images.swap_remove(graph.output())
+        } else {
+            images.swap_remove(0)
+        };
+        io::save_image_to_path(&output, &job.output, &save_options)?;
+        if let Some(flow_pipeline) = &flow_pipeline {
+            let images = flow_pipeline.process_nodes(&image, &flow_nodes)?;
+            for (node, image) in flow_nodes.iter().zip(&images) {
+                let path = job.extra_output(&flow_field.nodes()[*node].id);
+                io::save_image_to_path(image, &path, &save_options)?;
+            }
+        }
+        Ok::<(), dogfun::Error>(())
+    });
+    if failures > 0 {
+        eprintln!("{} of {} images failed", failures, jobs.len());
+        std::process::exit(1);
     }
 
-    // Save the output
-    save_texture_to_path(&output_texture, "dog.png")?;
-
     println!("Done!");
-
-    Ok(())
-}
-
-fn create_gaussian_blur(
-    d2d_context: &ID2D1DeviceContext,
-    input: &ID2D1Bitmap,
-    standard_deviation: f32,
-) -> Result<ID2D1Effect> {
-    let effect = unsafe { d2d_context.CreateEffect(&CLSID_D2D1GaussianBlur)? };
-
-    unsafe {
-        effect.SetInput(0, input, None);
-        let value = standard_deviation.to_le_bytes();
-        effect.SetValue(
-            D2D1_GAUSSIANBLUR_PROP_STANDARD_DEVIATION.0 as u32,
-            D2D1_PROPERTY_TYPE_FLOAT,
-            &value,
-        )?;
-        let value = D2D1_BORDER_MODE_HARD.0.to_le_bytes();
-        effect.SetValue(
-            D2D1_GAUSSIANBLUR_PROP_BORDER_MODE.0 as u32,
-            D2D1_PROPERTY_TYPE_UNKNOWN,
-            &value,
-        )?;
-    }
-
-    Ok(effect)
 }
 
-fn create_subtract_effect(
-    d2d_context: &ID2D1DeviceContext,
-    input_1: &ID2D1Image,
-    input_2: &ID2D1Image,
-) -> Result<ID2D1Effect> {
-    let effect = unsafe { d2d_context.CreateEffect(&CLSID_D2D1Blend)? };
-
-    unsafe {
-        effect.SetInput(0, input_1, None);
-        effect.SetInput(1, input_2, None);
-        let value = D2D1_BLEND_MODE_SUBTRACT.0.to_le_bytes();
-        effect.SetValue(
-            D2D1_BLEND_PROP_MODE.0 as u32,
-            D2D1_PROPERTY_TYPE_UNKNOWN,
-            &value,
-        )?;
-    }
-
-    Ok(effect)
-}
-
-fn create_grayscale(d2d_context: &ID2D1DeviceContext, input: &ID2D1Image) -> Result<ID2D1Effect> {
-    let effect = unsafe { d2d_context.CreateEffect(&CLSID_D2D1Grayscale)? };
-
-    unsafe {
-        effect.SetInput(0, input, None);
-    }
-
-    Ok(effect)
-}
-
-fn create_threshold(
-    d2d_context: &ID2D1DeviceContext,
-    input: &ID2D1Image,
-    threshold: f32,
-) -> Result<ID2D1Effect> {
-    let effect = unsafe { d2d_context.CreateEffect(&THRESHOLD_EFFECT_CLSID)? };
-
-    unsafe {
-        effect.SetInput(0, input, None);
-        let value = threshold.to_le_bytes();
-        effect.SetValue(0, D2D1_PROPERTY_TYPE_FLOAT, &value)?;
+/// Runs `process` over every job, reporting failures without stopping the
+/// batch. Returns the number of jobs that failed.
+fn run_batch<E, F>(args: &Args, jobs: &[Job], process: F) -> usize
+where
+    E: Into<Box<dyn Error>>,
+    F: Fn(&Job) -> Result<(), E>,
+{
+    let mut failures = 0;
+    for job in jobs {
+        let result: Result<(), Box<dyn Error>> = if args.no_clobber && job.output.exists() {
+            Err(format!("'{}' already exists", job.output.display()).into())
+        } else {
+            process(job).map_err(Into::into)
+        };
+        match result {
+            Ok(()) => {
+                if jobs.len() > 1 {
+                    println!("{} -> {}", job.input.display(), job.output.display());
+                }
+            }
+            Err(error) => {
+                eprintln!("error: {}: {}", job.input.display(), error);
+                failures += 1;
+            }
+        }
     }
-
-    Ok(effect)
+    failures
 }
@@ -0,0 +1,130 @@
+use super::PickedThreshold;
+use crate::{
+    cpu::{Context, Image},
+    graph::{Graph, Op, Source},
+};
+
+/// Evaluates an effect graph with the CPU implementation of each effect.
+pub struct CpuPipeline {
+    context: Context,
+    graph: Graph,
+}
+
+impl CpuPipeline {
+    pub fn new(graph: &Graph) -> Self {
+        Self {
+            context: Context::with_precision(graph.precision()),
+            graph: graph.clone(),
+        }
+    }
+
+    pub fn process(&self, image: &Image) -> Image {
+        let (mut results, _) = self.evaluate(image);
+        results.swap_remove(self.graph.output())
+    }
+
+    pub fn process_nodes_with_thresholds(
+        &self,
+        image: &Image,
+        nodes: &[usize],
+    ) -> (Vec<Image>, Vec<PickedThreshold>) {
+        let (results, thresholds) = self.evaluate(image);
+        let images = nodes.iter().map(|node| results[*node].clone()).collect();
+        (images, thresholds)
+    }
+
+    /// Runs every node of the graph, returning their results in order along
+    /// with the thresholds automatic threshold nodes picked.
+    fn evaluate(&self, image: &Image) -> (Vec<Image>, Vec<PickedThreshold>) {
+        let context = &self.context;
+
+        let mut results: Vec<Image> = Vec::with_capacity(self.graph.nodes().len());
+        let mut thresholds = Vec::new();
+        for (index, node) in self.graph.nodes().iter().enumerate() {
+            let inputs: Vec<&Image> = node
+                .inputs
+                .iter()
+                .map(|source| match source {
+                    Source::Input => image,
+                    Source::Node(index) => &results[*index],
+                })
+                .collect();
+            let (result, threshold) = apply(context, &node.op, &inputs);
+            if let Some(threshold) = threshold {
+                thresholds.push(PickedThreshold {
+                    node: index,
+                    threshold,
+                });
+            }
+            results.push(result);
+        }
+        (results, thresholds)
+    }
+}
+
+/// Runs a single node of a graph on the CPU. Automatic threshold nodes also
+/// return the threshold they picked.
+pub(super) fn apply(context: &Context, op: &Op, inputs: &[&Image]) -> (Image, Option<f32>) {
+    let result = match op {
+        Op::GaussianBlur { standard_deviation } => {
+            context.gaussian_blur(inputs[0], *standard_deviation)
+        }
+        Op::Blend { mode } => context.blend(inputs[0], inputs[1], *mode),
+        Op::CrossFade { weight } => context.cross_fade(inputs[0], inputs[1], *weight),
+        Op::Threshold { threshold, mode } => context.threshold(inputs[0], *threshold, *mode),
+        Op::Hysteresis { low, high } => context.hysteresis(inputs[0], *low, *high),
+        Op::AutoThreshold {
+            selection,
+            mode,
+            low_ratio,
+        } => {
+            let (result, threshold) =
+                context.auto_threshold(inputs[0], *selection, *mode, *low_ratio);
+            return (result, Some(threshold));
+        }
+        Op::Xdog {
+            sharpness,
+            epsilon,
+            phi,
+        } => context.xdog(inputs[0], inputs[1], *sharpness, *epsilon, *phi),
+        Op::SignedDifference { gain, bias } => {
+            context.signed_difference(inputs[0], inputs[1], *gain, *bias)
+        }
+        Op::ZeroCrossing { min_slope } => context.zero_crossing(inputs[0], *min_slope),
+        Op::Grayscale => context.grayscale(inputs[0]),
+        Op::Luminance { model } => context.luminance(inputs[0], *model),
+        Op::AnisotropicKuwahara {
+            radius,
+            sharpness,
+            alpha,
+        } => context.anisotropic_kuwahara(inputs[0], inputs[1], *radius, *sharpness, *alpha),
+        Op::Bilateral {
+            spatial_sigma,
+            range_sigma,
+        } => context.bilateral(inputs[0], *spatial_sigma, *range_sigma),
+        Op::Quantize { levels, sharpness } => context.quantize(inputs[0], *levels, *sharpness),
+        Op::Morphology {
+            operation,
+            shape,
+            radius,
+        } => context.morphology(inputs[0], *operation, *shape, *radius),
+        Op::RemoveSmallComponents { min_area, polarity } => {
+            context.remove_small_components(inputs[0], *min_area, *polarity)
+        }
+        Op::Skeletonize { polarity } => context.skeletonize(inputs[0], *polarity),
+        Op::Unpremultiply => context.unpremultiply(inputs[0]),
+        Op::AlphaMask => context.alpha_mask(inputs[0], inputs[1]),
+        Op::EdgesToAlpha { polarity } => context.edges_to_alpha(inputs[0], *polarity),
+        Op::Colorize { polarity, gradient } => context.colorize(inputs[0], *polarity, gradient),
+        Op::StructureTensor => context.structure_tensor(inputs[0]),
+        Op::FlowDog { sigma_1, sigma_2 } => {
+            context.flow_dog(inputs[0], inputs[1], *sigma_1, *sigma_2)
+        }
+        Op::FlowBlur { standard_deviation } => {
+            context.flow_blur(inputs[0], inputs[1], *standard_deviation)
+        }
+        Op::FlowTangent { encoding } => context.flow_tangent(inputs[0], *encoding),
+        Op::Coherence => context.coherence(inputs[0]),
+    };
+    (result, None)
+}
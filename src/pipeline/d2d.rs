@@ -0,0 +1,826 @@
+use windows::{
+    core::{Interface, Result},
+    Win32::Graphics::{
+        Direct2D::{
+            CLSID_D2D1AlphaMask, CLSID_D2D1Blend, CLSID_D2D1CrossFade, CLSID_D2D1GaussianBlur,
+            CLSID_D2D1Grayscale,
+            Common::{
+                D2D1_BLEND_MODE_DARKEN, D2D1_BLEND_MODE_DIFFERENCE, D2D1_BLEND_MODE_LIGHTEN,
+                D2D1_BLEND_MODE_MULTIPLY, D2D1_BLEND_MODE_SCREEN, D2D1_BLEND_MODE_SUBTRACT,
+                D2D1_BORDER_MODE_HARD, D2D1_COMPOSITE_MODE_SOURCE_OVER,
+            },
+            ID2D1DeviceContext, ID2D1Effect, ID2D1Image, D2D1_BLEND_PROP_MODE,
+            D2D1_BUFFER_PRECISION, D2D1_BUFFER_PRECISION_16BPC_FLOAT,
+            D2D1_BUFFER_PRECISION_32BPC_FLOAT, D2D1_BUFFER_PRECISION_8BPC_UNORM,
+            D2D1_CROSSFADE_PROP_WEIGHT, D2D1_DEVICE_CONTEXT_OPTIONS_NONE,
+            D2D1_GAUSSIANBLUR_PROP_BORDER_MODE, D2D1_GAUSSIANBLUR_PROP_STANDARD_DEVIATION,
+            D2D1_INTERPOLATION_MODE_LINEAR, D2D1_PROPERTY_TYPE_BLOB, D2D1_PROPERTY_TYPE_ENUM,
+            D2D1_PROPERTY_TYPE_FLOAT, D2D1_PROPERTY_TYPE_UINT32, D2D1_PROPERTY_TYPE_UNKNOWN,
+            D2D1_RENDERING_CONTROLS,
+        },
+        Direct3D11::{
+            ID3D11Device, ID3D11Texture2D, D3D11_BIND_RENDER_TARGET, D3D11_BIND_SHADER_RESOURCE,
+            D3D11_TEXTURE2D_DESC,
+        },
+        Dxgi::{
+            Common::{
+                DXGI_FORMAT, DXGI_FORMAT_B8G8R8A8_UNORM, DXGI_FORMAT_R16G16B16A16_FLOAT,
+                DXGI_FORMAT_R32G32B32A32_FLOAT,
+            },
+            IDXGISurface,
+        },
+    },
+};
+
+use crate::{
+    bitmap::Bitmap,
+    cpu::{
+        BlendMode, Context, EdgePolarity, Gradient, Image, LuminanceModel, MorphologyOperation,
+        Precision, StructuringElement, TangentEncoding, ThresholdMode,
+    },
+    d2d::{create_d2d_device, create_d2d_factory},
+    d3d11::create_d3d_device,
+    effects::{
+        anisotropic_kuwahara::{
+            ANISOTROPIC_KUWAHARA_EFFECT_CLSID, ANISOTROPIC_KUWAHARA_PROP_ALPHA,
+            ANISOTROPIC_KUWAHARA_PROP_RADIUS, ANISOTROPIC_KUWAHARA_PROP_SHARPNESS,
+        },
+        bilateral::{
+            BILATERAL_EFFECT_CLSID, BILATERAL_PROP_RANGE_SIGMA, BILATERAL_PROP_SPATIAL_SIGMA,
+        },
+        coherence::COHERENCE_EFFECT_CLSID,
+        colorize::{
+            COLORIZE_EFFECT_CLSID, COLORIZE_MAX_STOPS, COLORIZE_POLARITY_DARK,
+            COLORIZE_POLARITY_LIGHT, COLORIZE_PROP_COLORS, COLORIZE_PROP_POLARITY,
+            COLORIZE_PROP_POSITIONS, COLORIZE_PROP_STOP_COUNT,
+        },
+        edges_to_alpha::{
+            EDGES_TO_ALPHA_EFFECT_CLSID, EDGES_TO_ALPHA_POLARITY_DARK,
+            EDGES_TO_ALPHA_POLARITY_LIGHT, EDGES_TO_ALPHA_PROP_POLARITY,
+        },
+        flow_blur::{FLOW_BLUR_EFFECT_CLSID, FLOW_BLUR_PROP_STANDARD_DEVIATION},
+        flow_dog::{FLOW_DOG_EFFECT_CLSID, FLOW_DOG_PROP_SIGMA_1, FLOW_DOG_PROP_SIGMA_2},
+        flow_tangent::{
+            FLOW_TANGENT_EFFECT_CLSID, FLOW_TANGENT_ENCODING_HUE, FLOW_TANGENT_ENCODING_VECTOR,
+            FLOW_TANGENT_PROP_ENCODING,
+        },
+        luminance::{
+            LUMINANCE_EFFECT_CLSID, LUMINANCE_MODEL_LAB, LUMINANCE_MODEL_REC601,
+            LUMINANCE_MODEL_REC709, LUMINANCE_PROP_MODEL,
+        },
+        morphology::{
+            MORPHOLOGY_EFFECT_CLSID, MORPHOLOGY_OPERATION_DILATE, MORPHOLOGY_OPERATION_ERODE,
+            MORPHOLOGY_PROP_OPERATION, MORPHOLOGY_PROP_RADIUS, MORPHOLOGY_PROP_SHAPE,
+            MORPHOLOGY_SHAPE_DIAMOND, MORPHOLOGY_SHAPE_DISK, MORPHOLOGY_SHAPE_SQUARE,
+        },
+        quantize::{QUANTIZE_EFFECT_CLSID, QUANTIZE_PROP_LEVELS, QUANTIZE_PROP_SHARPNESS},
+        register_custom_effects,
+        signed_difference::{
+            SIGNED_DIFFERENCE_EFFECT_CLSID, SIGNED_DIFFERENCE_PROP_BIAS,
+            SIGNED_DIFFERENCE_PROP_GAIN,
+        },
+        structure_tensor::STRUCTURE_TENSOR_EFFECT_CLSID,
+        threshold::{
+            THRESHOLD_EFFECT_CLSID, THRESHOLD_MODE_HARD, THRESHOLD_MODE_SMOOTHSTEP,
+            THRESHOLD_MODE_TANH, THRESHOLD_PROP_MODE, THRESHOLD_PROP_THRESHOLD,
+            THRESHOLD_PROP_WIDTH,
+        },
+        unpremultiply::UNPREMULTIPLY_EFFECT_CLSID,
+        xdog::{XDOG_EFFECT_CLSID, XDOG_PROP_EPSILON, XDOG_PROP_PHI, XDOG_PROP_SHARPNESS},
+        zero_crossing::{ZERO_CROSSING_EFFECT_CLSID, ZERO_CROSSING_PROP_MIN_SLOPE},
+    },
+    graph::{Graph, Op, Source},
+    imaging::{create_texture_from_bitmap, read_texture_to_image},
+};
+
+use super::{cpu::apply, PickedThreshold};
+
+/// The D2D device, context and effect graph, created once and reused for
+/// every image.
+pub struct D2DPipeline {
+    d3d_device: ID3D11Device,
+    d2d_context: ID2D1DeviceContext,
+    /// Runs the nodes that D2D can't
+    context: Context,
+    /// Effect inputs that the source image gets bound to
+    input_bindings: Vec<(ID2D1Effect, u32)>,
+    /// The output of every node in the graph, or `None` for host nodes
+    images: Vec<Option<ID2D1Image>>,
+    /// Nodes that run on the CPU, in graph order
+    host_nodes: Vec<HostNode>,
+    output: usize,
+    /// Format of the textures nodes are drawn into
+    format: DXGI_FORMAT,
+}
+
+/// A node that has to see the whole image at once, like removing small
+/// components, which no pixel shader can do. Its inputs are drawn and read
+/// back, the node runs on the CPU and its result is uploaded again for the
+/// effects that read it.
+struct HostNode {
+    index: usize,
+    op: Op,
+    inputs: Vec<Source>,
+    /// Effect inputs that the node's result gets bound to
+    bindings: Vec<(ID2D1Effect, u32)>,
+}
+
+impl D2DPipeline {
+    pub fn new(graph: &Graph) -> Result<Self> {
+        // Init D3D11 and D2D
+        let d3d_device = create_d3d_device()?;
+        let d2d_factory = create_d2d_factory()?;
+        register_custom_effects(&d2d_factory)?;
+        let d2d_device = create_d2d_device(&d2d_factory, &d3d_device)?;
+        let d2d_context =
+            unsafe { d2d_device.CreateDeviceContext(D2D1_DEVICE_CONTEXT_OPTIONS_NONE)? };
+
+        // Intermediate buffers follow the graph's precision, and so do the
+        // textures we read nodes back from
+        let (buffer_precision, format) = precision_formats(graph.precision());
+        unsafe {
+            let mut controls = D2D1_RENDERING_CONTROLS::default();
+            d2d_context.GetRenderingControls(&mut controls);
+            controls.bufferPrecision = buffer_precision;
+            d2d_context.SetRenderingControls(&controls);
+        }
+
+        // Setup our effect graph, the input bitmap is bound per image
+        let mut input_bindings = Vec::new();
+        let mut images: Vec<Option<ID2D1Image>> = Vec::with_capacity(graph.nodes().len());
+        let mut host_nodes: Vec<HostNode> = Vec::new();
+        for (node_index, node) in graph.nodes().iter().enumerate() {
+            let effect = match &node.op {
+                Op::GaussianBlur { standard_deviation } => {
+                    create_gaussian_blur(&d2d_context, *standard_deviation)?
+                }
+                Op::Blend { mode } => create_blend_effect(&d2d_context, *mode)?,
+                Op::CrossFade { weight } => create_cross_fade(&d2d_context, *weight)?,
+                Op::Threshold { threshold, mode } => {
+                    create_threshold(&d2d_context, *threshold, *mode)?
+                }
+                Op::Xdog {
+                    sharpness,
+                    epsilon,
+                    phi,
+                } => create_xdog(&d2d_context, *sharpness, *epsilon, *phi)?,
+                Op::SignedDifference { gain, bias } => {
+                    create_signed_difference(&d2d_context, *gain, *bias)?
+                }
+                Op::ZeroCrossing { min_slope } => create_zero_crossing(&d2d_context, *min_slope)?,
+                Op::Grayscale => create_grayscale(&d2d_context)?,
+                Op::Luminance { model } => create_luminance(&d2d_context, *model)?,
+                Op::AnisotropicKuwahara {
+                    radius,
+                    sharpness,
+                    alpha,
+                } => create_anisotropic_kuwahara(&d2d_context, *radius, *sharpness, *alpha)?,
+                Op::Bilateral {
+                    spatial_sigma,
+                    range_sigma,
+                } => create_bilateral(&d2d_context, *spatial_sigma, *range_sigma)?,
+                Op::Quantize { levels, sharpness } => {
+                    create_quantize(&d2d_context, *levels, *sharpness)?
+                }
+                Op::Morphology {
+                    operation,
+                    shape,
+                    radius,
+                } => create_morphology(&d2d_context, *operation, *shape, *radius)?,
+                Op::Hysteresis { .. }
+                | Op::AutoThreshold { .. }
+                | Op::RemoveSmallComponents { .. }
+                | Op::Skeletonize { .. } => {
+                    host_nodes.push(HostNode {
+                        index: node_index,
+                        op: node.op.clone(),
+                        inputs: node.inputs.clone(),
+                        bindings: Vec::new(),
+                    });
+                    images.push(None);
+                    continue;
+                }
+                Op::Unpremultiply => create_unpremultiply(&d2d_context)?,
+                Op::AlphaMask => create_alpha_mask(&d2d_context)?,
+                Op::EdgesToAlpha { polarity } => create_edges_to_alpha(&d2d_context, *polarity)?,
+                Op::Colorize { polarity, gradient } => {
+                    create_colorize(&d2d_context, *polarity, gradient)?
+                }
+                Op::StructureTensor => create_structure_tensor(&d2d_context)?,
+                Op::FlowDog { sigma_1, sigma_2 } => {
+                    create_flow_dog(&d2d_context, *sigma_1, *sigma_2)?
+                }
+                Op::FlowBlur { standard_deviation } => {
+                    create_flow_blur(&d2d_context, *standard_deviation)?
+                }
+                Op::FlowTangent { encoding } => create_flow_tangent(&d2d_context, *encoding)?,
+                Op::Coherence => create_coherence(&d2d_context)?,
+            };
+            for (index, source) in node.inputs.iter().enumerate() {
+                match source {
+                    Source::Input => input_bindings.push((effect.clone(), index as u32)),
+                    Source::Node(node_index) => match &images[*node_index] {
+                        Some(image) => unsafe {
+                            effect.SetInput(index as u32, image, None);
+                        },
+                        // Bound once the host node has run
+                        None => host_nodes
+                            .iter_mut()
+                            .find(|host| host.index == *node_index)
+                            .unwrap()
+                            .bindings
+                            .push((effect.clone(), index as u32)),
+                    },
+                }
+            }
+            images.push(Some(effect.cast()?));
+        }
+
+        Ok(Self {
+            d3d_device,
+            d2d_context,
+            context: Context::with_precision(graph.precision()),
+            input_bindings,
+            images,
+            host_nodes,
+            output: graph.output(),
+            format,
+        })
+    }
+
+    pub fn process(&self, image: &Image) -> Result<Image> {
+        let (mut images, _) = self.process_nodes_with_thresholds(image, &[self.output])?;
+        Ok(images.swap_remove(0))
+    }
+
+    pub fn process_nodes_with_thresholds(
+        &self,
+        image: &Image,
+        nodes: &[usize],
+    ) -> Result<(Vec<Image>, Vec<PickedThreshold>)> {
+        let d3d_device = &self.d3d_device;
+        let d2d_context = &self.d2d_context;
+
+        // Create our input texture
+        let input_texture = create_texture_from_bitmap(d3d_device, &Bitmap::from_image(image))?;
+
+        // Create our input bitmap
+        let input_bitmap = {
+            let surface: IDXGISurface = input_texture.cast()?;
+            unsafe { d2d_context.CreateBitmapFromDxgiSurface(&surface, None)? }
+        };
+
+        // Bind this image to the graph
+        unsafe {
+            for (effect, index) in &self.input_bindings {
+                effect.SetInput(*index, &input_bitmap, None);
+            }
+        }
+
+        // Run the host nodes in order, binding each result before the nodes
+        // after it get drawn
+        let mut host_images = Vec::with_capacity(self.host_nodes.len());
+        let mut thresholds = Vec::new();
+        for host in &self.host_nodes {
+            let drawn = host
+                .inputs
+                .iter()
+                .map(|source| match source {
+                    Source::Input => Ok(None),
+                    Source::Node(index) => self
+                        .read_node(&input_texture, *index, &host_images)
+                        .map(Some),
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let inputs: Vec<&Image> = drawn
+                .iter()
+                .map(|drawn| drawn.as_ref().unwrap_or(image))
+                .collect();
+            let (result, threshold) = apply(&self.context, &host.op, &inputs);
+            if let Some(threshold) = threshold {
+                thresholds.push(PickedThreshold {
+                    node: host.index,
+                    threshold,
+                });
+            }
+            if !host.bindings.is_empty() {
+                let texture = create_texture_from_bitmap(d3d_device, &Bitmap::from_image(&result))?;
+                let bitmap = {
+                    let surface: IDXGISurface = texture.cast()?;
+                    unsafe { d2d_context.CreateBitmapFromDxgiSurface(&surface, None)? }
+                };
+                unsafe {
+                    for (effect, index) in &host.bindings {
+                        effect.SetInput(*index, &bitmap, None);
+                    }
+                }
+            }
+            host_images.push(result);
+        }
+
+        let mut outputs = Vec::with_capacity(nodes.len());
+        for node in nodes {
+            outputs.push(self.read_node(&input_texture, *node, &host_images)?);
+        }
+        Ok((outputs, thresholds))
+    }
+
+    /// Draws a node and reads it back, or returns the result of a host node
+    /// that has already run.
+    fn read_node(
+        &self,
+        input_texture: &ID3D11Texture2D,
+        node: usize,
+        host_images: &[Image],
+    ) -> Result<Image> {
+        match &self.images[node] {
+            Some(image) => {
+                let output_texture = self.draw(input_texture, image)?;
+                read_texture_to_image(&self.d3d_device, &output_texture)
+            }
+            None => {
+                let host = self
+                    .host_nodes
+                    .iter()
+                    .position(|host| host.index == node)
+                    .unwrap();
+                Ok(host_images[host].clone())
+            }
+        }
+    }
+
+    /// Draws `image` into a new texture the size of the input.
+    fn draw(&self, input_texture: &ID3D11Texture2D, image: &ID2D1Image) -> Result<ID3D11Texture2D> {
+        let d3d_device = &self.d3d_device;
+        let d2d_context = &self.d2d_context;
+
+        // Create our output texture
+        let output_texture = {
+            let mut desc = D3D11_TEXTURE2D_DESC::default();
+            unsafe {
+                input_texture.GetDesc(&mut desc);
+            }
+            desc.Format = self.format;
+            desc.BindFlags = (D3D11_BIND_SHADER_RESOURCE.0 | D3D11_BIND_RENDER_TARGET.0) as u32;
+
+            unsafe {
+                let mut texture = None;
+                d3d_device.CreateTexture2D(&desc, None, Some(&mut texture))?;
+                texture.unwrap()
+            }
+        };
+
+        // Create our output bitmap
+        let output_bitmap = {
+            let surface: IDXGISurface = output_texture.cast()?;
+            unsafe { d2d_context.CreateBitmapFromDxgiSurface(&surface, None)? }
+        };
+
+        // Draw
+        unsafe {
+            d2d_context.SetTarget(&output_bitmap);
+            d2d_context.BeginDraw();
+            d2d_context.Clear(None);
+            d2d_context.DrawImage(
+                image,
+                None,
+                None,
+                D2D1_INTERPOLATION_MODE_LINEAR,
+                D2D1_COMPOSITE_MODE_SOURCE_OVER,
+            );
+            d2d_context.EndDraw(None, None)?;
+        }
+
+        Ok(output_texture)
+    }
+}
+
+/// D2D buffer precision and output texture format for a graph precision.
+fn precision_formats(precision: Precision) -> (D2D1_BUFFER_PRECISION, DXGI_FORMAT) {
+    match precision {
+        Precision::Unorm8 => (D2D1_BUFFER_PRECISION_8BPC_UNORM, DXGI_FORMAT_B8G8R8A8_UNORM),
+        Precision::Float16 => (
+            D2D1_BUFFER_PRECISION_16BPC_FLOAT,
+            DXGI_FORMAT_R16G16B16A16_FLOAT,
+        ),
+        Precision::Float32 => (
+            D2D1_BUFFER_PRECISION_32BPC_FLOAT,
+            DXGI_FORMAT_R32G32B32A32_FLOAT,
+        ),
+    }
+}
+
+pub fn create_gaussian_blur(
+    d2d_context: &ID2D1DeviceContext,
+    standard_deviation: f32,
+) -> Result<ID2D1Effect> {
+    let effect = unsafe { d2d_context.CreateEffect(&CLSID_D2D1GaussianBlur)? };
+
+    unsafe {
+        let value = standard_deviation.to_le_bytes();
+        effect.SetValue(
+            D2D1_GAUSSIANBLUR_PROP_STANDARD_DEVIATION.0 as u32,
+            D2D1_PROPERTY_TYPE_FLOAT,
+            &value,
+        )?;
+        let value = D2D1_BORDER_MODE_HARD.0.to_le_bytes();
+        effect.SetValue(
+            D2D1_GAUSSIANBLUR_PROP_BORDER_MODE.0 as u32,
+            D2D1_PROPERTY_TYPE_UNKNOWN,
+            &value,
+        )?;
+    }
+
+    Ok(effect)
+}
+
+pub fn create_blend_effect(
+    d2d_context: &ID2D1DeviceContext,
+    mode: BlendMode,
+) -> Result<ID2D1Effect> {
+    let effect = unsafe { d2d_context.CreateEffect(&CLSID_D2D1Blend)? };
+
+    let mode = match mode {
+        BlendMode::Subtract => D2D1_BLEND_MODE_SUBTRACT,
+        BlendMode::Multiply => D2D1_BLEND_MODE_MULTIPLY,
+        BlendMode::Screen => D2D1_BLEND_MODE_SCREEN,
+        BlendMode::Darken => D2D1_BLEND_MODE_DARKEN,
+        BlendMode::Lighten => D2D1_BLEND_MODE_LIGHTEN,
+        BlendMode::Difference => D2D1_BLEND_MODE_DIFFERENCE,
+    };
+
+    unsafe {
+        let value = mode.0.to_le_bytes();
+        effect.SetValue(
+            D2D1_BLEND_PROP_MODE.0 as u32,
+            D2D1_PROPERTY_TYPE_UNKNOWN,
+            &value,
+        )?;
+    }
+
+    Ok(effect)
+}
+
+pub fn create_cross_fade(d2d_context: &ID2D1DeviceContext, weight: f32) -> Result<ID2D1Effect> {
+    let effect = unsafe { d2d_context.CreateEffect(&CLSID_D2D1CrossFade)? };
+
+    unsafe {
+        let value = weight.to_le_bytes();
+        effect.SetValue(
+            D2D1_CROSSFADE_PROP_WEIGHT.0 as u32,
+            D2D1_PROPERTY_TYPE_FLOAT,
+            &value,
+        )?;
+    }
+
+    Ok(effect)
+}
+
+pub fn create_grayscale(d2d_context: &ID2D1DeviceContext) -> Result<ID2D1Effect> {
+    let effect = unsafe { d2d_context.CreateEffect(&CLSID_D2D1Grayscale)? };
+
+    Ok(effect)
+}
+
+pub fn create_threshold(
+    d2d_context: &ID2D1DeviceContext,
+    threshold: f32,
+    mode: ThresholdMode,
+) -> Result<ID2D1Effect> {
+    let effect = unsafe { d2d_context.CreateEffect(&THRESHOLD_EFFECT_CLSID)? };
+
+    let (mode, width) = match mode {
+        ThresholdMode::Hard => (THRESHOLD_MODE_HARD, 0.0),
+        ThresholdMode::Smoothstep { width } => (THRESHOLD_MODE_SMOOTHSTEP, width),
+        ThresholdMode::Tanh { width } => (THRESHOLD_MODE_TANH, width),
+    };
+
+    unsafe {
+        let value = threshold.to_le_bytes();
+        effect.SetValue(THRESHOLD_PROP_THRESHOLD, D2D1_PROPERTY_TYPE_FLOAT, &value)?;
+        let value = mode.to_le_bytes();
+        effect.SetValue(THRESHOLD_PROP_MODE, D2D1_PROPERTY_TYPE_ENUM, &value)?;
+        let value = width.to_le_bytes();
+        effect.SetValue(THRESHOLD_PROP_WIDTH, D2D1_PROPERTY_TYPE_FLOAT, &value)?;
+    }
+
+    Ok(effect)
+}
+
+pub fn create_xdog(
+    d2d_context: &ID2D1DeviceContext,
+    sharpness: f32,
+    epsilon: f32,
+    phi: f32,
+) -> Result<ID2D1Effect> {
+    let effect = unsafe { d2d_context.CreateEffect(&XDOG_EFFECT_CLSID)? };
+
+    unsafe {
+        let value = sharpness.to_le_bytes();
+        effect.SetValue(XDOG_PROP_SHARPNESS, D2D1_PROPERTY_TYPE_FLOAT, &value)?;
+        let value = epsilon.to_le_bytes();
+        effect.SetValue(XDOG_PROP_EPSILON, D2D1_PROPERTY_TYPE_FLOAT, &value)?;
+        let value = phi.to_le_bytes();
+        effect.SetValue(XDOG_PROP_PHI, D2D1_PROPERTY_TYPE_FLOAT, &value)?;
+    }
+
+    Ok(effect)
+}
+
+pub fn create_signed_difference(
+    d2d_context: &ID2D1DeviceContext,
+    gain: f32,
+    bias: f32,
+) -> Result<ID2D1Effect> {
+    let effect = unsafe { d2d_context.CreateEffect(&SIGNED_DIFFERENCE_EFFECT_CLSID)? };
+
+    unsafe {
+        let value = gain.to_le_bytes();
+        effect.SetValue(
+            SIGNED_DIFFERENCE_PROP_GAIN,
+            D2D1_PROPERTY_TYPE_FLOAT,
+            &value,
+        )?;
+        let value = bias.to_le_bytes();
+        effect.SetValue(
+            SIGNED_DIFFERENCE_PROP_BIAS,
+            D2D1_PROPERTY_TYPE_FLOAT,
+            &value,
+        )?;
+    }
+
+    Ok(effect)
+}
+
+pub fn create_zero_crossing(
+    d2d_context: &ID2D1DeviceContext,
+    min_slope: f32,
+) -> Result<ID2D1Effect> {
+    let effect = unsafe { d2d_context.CreateEffect(&ZERO_CROSSING_EFFECT_CLSID)? };
+
+    unsafe {
+        let value = min_slope.to_le_bytes();
+        effect.SetValue(
+            ZERO_CROSSING_PROP_MIN_SLOPE,
+            D2D1_PROPERTY_TYPE_FLOAT,
+            &value,
+        )?;
+    }
+
+    Ok(effect)
+}
+
+pub fn create_luminance(
+    d2d_context: &ID2D1DeviceContext,
+    model: LuminanceModel,
+) -> Result<ID2D1Effect> {
+    let effect = unsafe { d2d_context.CreateEffect(&LUMINANCE_EFFECT_CLSID)? };
+
+    let model = match model {
+        LuminanceModel::Rec601 => LUMINANCE_MODEL_REC601,
+        LuminanceModel::Rec709 => LUMINANCE_MODEL_REC709,
+        LuminanceModel::Lab => LUMINANCE_MODEL_LAB,
+    };
+
+    unsafe {
+        let value = model.to_le_bytes();
+        effect.SetValue(LUMINANCE_PROP_MODEL, D2D1_PROPERTY_TYPE_ENUM, &value)?;
+    }
+
+    Ok(effect)
+}
+
+pub fn create_anisotropic_kuwahara(
+    d2d_context: &ID2D1DeviceContext,
+    radius: f32,
+    sharpness: f32,
+    alpha: f32,
+) -> Result<ID2D1Effect> {
+    let effect = unsafe { d2d_context.CreateEffect(&ANISOTROPIC_KUWAHARA_EFFECT_CLSID)? };
+
+    unsafe {
+        let value = radius.to_le_bytes();
+        effect.SetValue(
+            ANISOTROPIC_KUWAHARA_PROP_RADIUS,
+            D2D1_PROPERTY_TYPE_FLOAT,
+            &value,
+        )?;
+        let value = sharpness.to_le_bytes();
+        effect.SetValue(
+            ANISOTROPIC_KUWAHARA_PROP_SHARPNESS,
+            D2D1_PROPERTY_TYPE_FLOAT,
+            &value,
+        )?;
+        let value = alpha.to_le_bytes();
+        effect.SetValue(
+            ANISOTROPIC_KUWAHARA_PROP_ALPHA,
+            D2D1_PROPERTY_TYPE_FLOAT,
+            &value,
+        )?;
+    }
+
+    Ok(effect)
+}
+
+pub fn create_bilateral(
+    d2d_context: &ID2D1DeviceContext,
+    spatial_sigma: f32,
+    range_sigma: f32,
+) -> Result<ID2D1Effect> {
+    let effect = unsafe { d2d_context.CreateEffect(&BILATERAL_EFFECT_CLSID)? };
+
+    unsafe {
+        let value = spatial_sigma.to_le_bytes();
+        effect.SetValue(
+            BILATERAL_PROP_SPATIAL_SIGMA,
+            D2D1_PROPERTY_TYPE_FLOAT,
+            &value,
+        )?;
+        let value = range_sigma.to_le_bytes();
+        effect.SetValue(BILATERAL_PROP_RANGE_SIGMA, D2D1_PROPERTY_TYPE_FLOAT, &value)?;
+    }
+
+    Ok(effect)
+}
+
+pub fn create_quantize(
+    d2d_context: &ID2D1DeviceContext,
+    levels: u32,
+    sharpness: f32,
+) -> Result<ID2D1Effect> {
+    let effect = unsafe { d2d_context.CreateEffect(&QUANTIZE_EFFECT_CLSID)? };
+
+    unsafe {
+        let value = levels.to_le_bytes();
+        effect.SetValue(QUANTIZE_PROP_LEVELS, D2D1_PROPERTY_TYPE_UINT32, &value)?;
+        let value = sharpness.to_le_bytes();
+        effect.SetValue(QUANTIZE_PROP_SHARPNESS, D2D1_PROPERTY_TYPE_FLOAT, &value)?;
+    }
+
+    Ok(effect)
+}
+
+pub fn create_morphology(
+    d2d_context: &ID2D1DeviceContext,
+    operation: MorphologyOperation,
+    shape: StructuringElement,
+    radius: u32,
+) -> Result<ID2D1Effect> {
+    let effect = unsafe { d2d_context.CreateEffect(&MORPHOLOGY_EFFECT_CLSID)? };
+
+    let operation = match operation {
+        MorphologyOperation::Erode => MORPHOLOGY_OPERATION_ERODE,
+        MorphologyOperation::Dilate => MORPHOLOGY_OPERATION_DILATE,
+    };
+    let shape = match shape {
+        StructuringElement::Square => MORPHOLOGY_SHAPE_SQUARE,
+        StructuringElement::Disk => MORPHOLOGY_SHAPE_DISK,
+        StructuringElement::Diamond => MORPHOLOGY_SHAPE_DIAMOND,
+    };
+
+    unsafe {
+        let value = operation.to_le_bytes();
+        effect.SetValue(MORPHOLOGY_PROP_OPERATION, D2D1_PROPERTY_TYPE_ENUM, &value)?;
+        let value = shape.to_le_bytes();
+        effect.SetValue(MORPHOLOGY_PROP_SHAPE, D2D1_PROPERTY_TYPE_ENUM, &value)?;
+        let value = radius.to_le_bytes();
+        effect.SetValue(MORPHOLOGY_PROP_RADIUS, D2D1_PROPERTY_TYPE_UINT32, &value)?;
+    }
+
+    Ok(effect)
+}
+
+pub fn create_unpremultiply(d2d_context: &ID2D1DeviceContext) -> Result<ID2D1Effect> {
+    let effect = unsafe { d2d_context.CreateEffect(&UNPREMULTIPLY_EFFECT_CLSID)? };
+
+    Ok(effect)
+}
+
+pub fn create_alpha_mask(d2d_context: &ID2D1DeviceContext) -> Result<ID2D1Effect> {
+    let effect = unsafe { d2d_context.CreateEffect(&CLSID_D2D1AlphaMask)? };
+
+    Ok(effect)
+}
+
+pub fn create_edges_to_alpha(
+    d2d_context: &ID2D1DeviceContext,
+    polarity: EdgePolarity,
+) -> Result<ID2D1Effect> {
+    let effect = unsafe { d2d_context.CreateEffect(&EDGES_TO_ALPHA_EFFECT_CLSID)? };
+
+    let polarity = match polarity {
+        EdgePolarity::Light => EDGES_TO_ALPHA_POLARITY_LIGHT,
+        EdgePolarity::Dark => EDGES_TO_ALPHA_POLARITY_DARK,
+    };
+
+    unsafe {
+        let value = polarity.to_le_bytes();
+        effect.SetValue(
+            EDGES_TO_ALPHA_PROP_POLARITY,
+            D2D1_PROPERTY_TYPE_ENUM,
+            &value,
+        )?;
+    }
+
+    Ok(effect)
+}
+
+pub fn create_colorize(
+    d2d_context: &ID2D1DeviceContext,
+    polarity: EdgePolarity,
+    gradient: &Gradient,
+) -> Result<ID2D1Effect> {
+    let effect = unsafe { d2d_context.CreateEffect(&COLORIZE_EFFECT_CLSID)? };
+
+    let polarity = match polarity {
+        EdgePolarity::Light => COLORIZE_POLARITY_LIGHT,
+        EdgePolarity::Dark => COLORIZE_POLARITY_DARK,
+    };
+    // The effect always takes the full arrays
+    let mut colors = [0u8; COLORIZE_MAX_STOPS * 16];
+    let mut positions = [0u8; COLORIZE_MAX_STOPS * 4];
+    for (index, stop) in gradient.stops().iter().enumerate() {
+        for (c, value) in stop.color.iter().enumerate() {
+            let offset = index * 16 + c * 4;
+            colors[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+        }
+        positions[index * 4..index * 4 + 4].copy_from_slice(&stop.position.to_le_bytes());
+    }
+
+    unsafe {
+        let value = polarity.to_le_bytes();
+        effect.SetValue(COLORIZE_PROP_POLARITY, D2D1_PROPERTY_TYPE_ENUM, &value)?;
+        effect.SetValue(COLORIZE_PROP_COLORS, D2D1_PROPERTY_TYPE_BLOB, &colors)?;
+        effect.SetValue(COLORIZE_PROP_POSITIONS, D2D1_PROPERTY_TYPE_BLOB, &positions)?;
+        let value = (gradient.stops().len() as u32).to_le_bytes();
+        effect.SetValue(COLORIZE_PROP_STOP_COUNT, D2D1_PROPERTY_TYPE_UINT32, &value)?;
+    }
+
+    Ok(effect)
+}
+
+pub fn create_structure_tensor(d2d_context: &ID2D1DeviceContext) -> Result<ID2D1Effect> {
+    let effect = unsafe { d2d_context.CreateEffect(&STRUCTURE_TENSOR_EFFECT_CLSID)? };
+
+    Ok(effect)
+}
+
+pub fn create_flow_dog(
+    d2d_context: &ID2D1DeviceContext,
+    sigma_1: f32,
+    sigma_2: f32,
+) -> Result<ID2D1Effect> {
+    let effect = unsafe { d2d_context.CreateEffect(&FLOW_DOG_EFFECT_CLSID)? };
+
+    unsafe {
+        let value = sigma_1.to_le_bytes();
+        effect.SetValue(FLOW_DOG_PROP_SIGMA_1, D2D1_PROPERTY_TYPE_FLOAT, &value)?;
+        let value = sigma_2.to_le_bytes();
+        effect.SetValue(FLOW_DOG_PROP_SIGMA_2, D2D1_PROPERTY_TYPE_FLOAT, &value)?;
+    }
+
+    Ok(effect)
+}
+
+pub fn create_flow_blur(
+    d2d_context: &ID2D1DeviceContext,
+    standard_deviation: f32,
+) -> Result<ID2D1Effect> {
+    let effect = unsafe { d2d_context.CreateEffect(&FLOW_BLUR_EFFECT_CLSID)? };
+
+    unsafe {
+        let value = standard_deviation.to_le_bytes();
+        effect.SetValue(
+            FLOW_BLUR_PROP_STANDARD_DEVIATION,
+            D2D1_PROPERTY_TYPE_FLOAT,
+            &value,
+        )?;
+    }
+
+    Ok(effect)
+}
+
+pub fn create_flow_tangent(
+    d2d_context: &ID2D1DeviceContext,
+    encoding: TangentEncoding,
+) -> Result<ID2D1Effect> {
+    let effect = unsafe { d2d_context.CreateEffect(&FLOW_TANGENT_EFFECT_CLSID)? };
+
+    let encoding = match encoding {
+        TangentEncoding::Hue => FLOW_TANGENT_ENCODING_HUE,
+        TangentEncoding::Vector => FLOW_TANGENT_ENCODING_VECTOR,
+    };
+
+    unsafe {
+        let value = encoding.to_le_bytes();
+        effect.SetValue(FLOW_TANGENT_PROP_ENCODING, D2D1_PROPERTY_TYPE_ENUM, &value)?;
+    }
+
+    Ok(effect)
+}
+
+pub fn create_coherence(d2d_context: &ID2D1DeviceContext) -> Result<ID2D1Effect> {
+    let effect = unsafe { d2d_context.CreateEffect(&COHERENCE_EFFECT_CLSID)? };
+
+    Ok(effect)
+}
@@ -0,0 +1,344 @@
+mod cpu;
+#[cfg(windows)]
+mod d2d;
+
+use crate::{
+    cpu::{
+        BlendMode, Gradient, Image, LuminanceModel, Precision, StructuringElement, ThresholdMode,
+        ThresholdSelection,
+    },
+    graph::Graph,
+    Result,
+};
+
+/// Which implementation of the effect graph a [`DogPipeline`] runs on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Backend {
+    #[cfg(windows)]
+    #[default]
+    D2D,
+    #[cfg_attr(not(windows), default)]
+    Cpu,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Mode {
+    /// Plain Difference of Gaussians followed by the threshold effect
+    #[default]
+    Dog,
+    /// Extended Difference of Gaussians with a soft threshold
+    Xdog,
+    /// Flow-based Difference of Gaussians, which follows the edge tangent
+    /// flow to produce coherent lines
+    Fdog,
+    /// Marr–Hildreth edges where the signed DoG response crosses zero, one
+    /// pixel thin. Always runs with at least [`Precision::Float16`].
+    ZeroCrossing,
+}
+
+/// Which channels the graph filters.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorMode {
+    /// Filters red, green and blue separately and converts the edges to
+    /// grayscale at the end, so edges can depend on the color of a region
+    #[default]
+    Grayscale,
+    /// Converts the image to luminance before blurring, so every color
+    /// contributes to one gray response
+    Luminance(LuminanceModel),
+    /// Filters red, green and blue separately and keeps the colored edges
+    Color,
+}
+
+/// What the alpha channel of the output holds.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AlphaMode {
+    /// Fully opaque
+    #[default]
+    Opaque,
+    /// The alpha of the source image, so transparent areas stay transparent
+    Preserve,
+    /// Black lines on a transparent background, for compositing
+    Transparent,
+}
+
+/// How the lines are drawn over the source image.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Composite {
+    /// Blend of the lines, the source, over the image, the destination
+    pub mode: BlendMode,
+    /// From 0 for the image alone to 1 for the fully blended result
+    pub opacity: f32,
+}
+
+impl Default for Composite {
+    fn default() -> Self {
+        Self {
+            mode: BlendMode::Multiply,
+            opacity: 1.0,
+        }
+    }
+}
+
+/// Soft luminance quantization of the source image, giving the flat color
+/// regions the lines are drawn over.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quantize {
+    /// Number of luma bands, at least 2
+    pub levels: u32,
+    /// Steepness of the steps between bands, 0 leaves the image as it is
+    pub sharpness: f32,
+}
+
+impl Default for Quantize {
+    fn default() -> Self {
+        Self {
+            levels: 4,
+            sharpness: 8.0,
+        }
+    }
+}
+
+/// Edge-preserving smoothing applied to the source image before the DoG.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Prefilter {
+    /// Bilateral filter, weighting neighbours by distance and by how close
+    /// their color is
+    Bilateral {
+        spatial_sigma: f32,
+        /// Color difference, in the 0 to 1 range of a channel, at which
+        /// neighbours start to stop counting
+        range_sigma: f32,
+    },
+    /// Anisotropic Kuwahara filter, which follows the edge tangent flow
+    /// smoothed by `tensor_sigma` for a painterly look
+    AnisotropicKuwahara {
+        /// Radius of the filter in flat areas. Along clear edges it
+        /// stretches to up to twice as long.
+        radius: f32,
+        /// How strongly the least varying parts of the filter win
+        sharpness: f32,
+        /// How readily the filter stretches along edges, smaller values
+        /// stretching it more
+        alpha: f32,
+    },
+}
+
+/// Morphological operation on the lines. The operations are named for what
+/// they do to the lines, so for XDoG's dark lines erosion takes the maximum.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MorphologyMode {
+    /// Thins the lines
+    Erode,
+    /// Thickens the lines
+    Dilate,
+    /// Erosion followed by dilation, which removes specks and spurs smaller
+    /// than the structuring element
+    #[default]
+    Open,
+    /// Dilation followed by erosion, which bridges gaps smaller than the
+    /// structuring element
+    Close,
+}
+
+/// Morphological cleanup of the thresholded lines.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Morphology {
+    pub mode: MorphologyMode,
+    pub shape: StructuringElement,
+    /// Radius of the structuring element in pixels
+    pub radius: u32,
+}
+
+impl Default for Morphology {
+    fn default() -> Self {
+        Self {
+            mode: MorphologyMode::Open,
+            shape: StructuringElement::Square,
+            radius: 1,
+        }
+    }
+}
+
+/// Parameters of the Difference of Gaussians graph.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DogParams {
+    pub mode: Mode,
+    pub color_mode: ColorMode,
+    pub alpha_mode: AlphaMode,
+    /// Gradient the final lines are mapped through, from paper to ink, in
+    /// place of white lines on black
+    pub colorize: Option<Gradient>,
+    /// Blend the lines over the source image for a toon look. Without
+    /// `colorize` the lines are black on white for the darkening blend modes
+    /// and white on black for the others.
+    pub composite: Option<Composite>,
+    /// Flatten the source image into bands before the lines are blended
+    /// over it. The lines are blended with `composite` if set, and with the
+    /// default multiply otherwise.
+    pub quantize: Option<Quantize>,
+    /// Smoothing that keeps noise from turning into speckled lines. The
+    /// smoothed image also feeds `quantize`, but without that the lines are
+    /// composited over the unfiltered source.
+    pub prefilter: Option<Prefilter>,
+    /// How many times `prefilter` runs, each pass on the previous result
+    pub prefilter_iterations: u32,
+    /// Cleanup of the lines right after they are found
+    pub morphology: Option<Morphology>,
+    /// Lines made of fewer connected pixels than this are removed after
+    /// `morphology`. 0 keeps all of them.
+    pub min_component_area: u32,
+    /// Thin the lines to a single pixel after the other cleanup
+    pub skeletonize: bool,
+    /// Filter the straight color of translucent pixels instead of the
+    /// premultiplied one, which is darkened by low alpha
+    pub unpremultiply: bool,
+    /// Standard deviation of the narrow Gaussian
+    pub sigma_1: f32,
+    /// Standard deviation of the wide Gaussian. For XDoG this is kσ.
+    pub sigma_2: f32,
+    pub threshold: f32,
+    pub threshold_mode: ThresholdMode,
+    /// Switches DoG and FDoG to hysteresis thresholding, keeping responses
+    /// down to this value where they connect to one above `threshold`.
+    /// `threshold_mode` doesn't apply then.
+    pub low_threshold: Option<f32>,
+    /// Picks `threshold` for each image from the histogram of its DoG
+    /// response instead. With `low_threshold` the hysteresis keeps the same
+    /// ratio between the two thresholds.
+    pub auto_threshold: Option<ThresholdSelection>,
+    /// XDoG sharpening weight p
+    pub sharpness: f32,
+    pub epsilon: f32,
+    pub phi: f32,
+    /// FDoG and Kuwahara smoothing of the structure tensor that gives the
+    /// edge tangent flow
+    pub tensor_sigma: f32,
+    /// FDoG standard deviation of the blur along the flow
+    pub flow_sigma: f32,
+    /// Smallest change of the DoG response across a zero crossing that still
+    /// counts as an edge
+    pub min_slope: f32,
+    /// Precision of intermediate images. With float precision the DoG
+    /// difference keeps its sign.
+    pub precision: Precision,
+}
+
+impl Default for DogParams {
+    fn default() -> Self {
+        Self {
+            mode: Mode::Dog,
+            color_mode: ColorMode::Grayscale,
+            alpha_mode: AlphaMode::Opaque,
+            colorize: None,
+            composite: None,
+            quantize: None,
+            prefilter: None,
+            prefilter_iterations: 1,
+            morphology: None,
+            min_component_area: 0,
+            skeletonize: false,
+            unpremultiply: false,
+            sigma_1: 3.0,
+            sigma_2: 5.0,
+            threshold: 0.01,
+            threshold_mode: ThresholdMode::Hard,
+            low_threshold: None,
+            auto_threshold: None,
+            sharpness: 20.0,
+            epsilon: 0.5,
+            phi: 10.0,
+            tensor_sigma: 2.0,
+            flow_sigma: 3.0,
+            min_slope: 0.002,
+            precision: Precision::Unorm8,
+        }
+    }
+}
+
+/// The threshold an `auto_threshold` node picked for an image.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PickedThreshold {
+    /// Index of the node in [`Graph::nodes`]
+    pub node: usize,
+    pub threshold: f32,
+}
+
+enum PipelineImpl {
+    #[cfg(windows)]
+    D2D(d2d::D2DPipeline),
+    Cpu(cpu::CpuPipeline),
+}
+
+/// A Difference of Gaussians effect graph that is built once and can then
+/// process any number of images.
+///
+/// ```no_run
+/// use dogfun::{io, Backend, DogParams, DogPipeline};
+///
+/// let pipeline = DogPipeline::new(Backend::default(), &DogParams::default())?;
+/// let image = io::load_image_from_path("photo.png")?;
+/// let edges = pipeline.process(&image)?;
+/// io::save_image_to_path(&edges, "edges.png", &io::SaveOptions::default())?;
+/// # Ok::<(), dogfun::Error>(())
+/// ```
+pub struct DogPipeline {
+    inner: PipelineImpl,
+}
+
+impl DogPipeline {
+    pub fn new(backend: Backend, params: &DogParams) -> Result<Self> {
+        Self::from_graph(backend, &Graph::from_params(params))
+    }
+
+    /// Builds a pipeline that runs a custom effect graph, for example one
+    /// loaded with [`Graph::load`].
+    pub fn from_graph(backend: Backend, graph: &Graph) -> Result<Self> {
+        let inner = match backend {
+            #[cfg(windows)]
+            Backend::D2D => PipelineImpl::D2D(d2d::D2DPipeline::new(graph)?),
+            Backend::Cpu => PipelineImpl::Cpu(cpu::CpuPipeline::new(graph)),
+        };
+        Ok(Self { inner })
+    }
+
+    pub fn process(&self, image: &Image) -> Result<Image> {
+        match &self.inner {
+            #[cfg(windows)]
+            PipelineImpl::D2D(pipeline) => Ok(pipeline.process(image)?),
+            PipelineImpl::Cpu(pipeline) => Ok(pipeline.process(image)),
+        }
+    }
+
+    /// Processes `image` and returns the results of the given nodes, indexed
+    /// as in [`Graph::nodes`], instead of just the output.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an index is not a node of the graph.
+    pub fn process_nodes(&self, image: &Image, nodes: &[usize]) -> Result<Vec<Image>> {
+        let (images, _) = self.process_nodes_with_thresholds(image, nodes)?;
+        Ok(images)
+    }
+
+    /// Like [`DogPipeline::process_nodes`], but also returns the threshold
+    /// every `auto_threshold` node of the graph picked for this image, in
+    /// graph order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an index is not a node of the graph.
+    pub fn process_nodes_with_thresholds(
+        &self,
+        image: &Image,
+        nodes: &[usize],
+    ) -> Result<(Vec<Image>, Vec<PickedThreshold>)> {
+        match &self.inner {
+            #[cfg(windows)]
+            PipelineImpl::D2D(pipeline) => {
+                Ok(pipeline.process_nodes_with_thresholds(image, nodes)?)
+            }
+            PipelineImpl::Cpu(pipeline) => Ok(pipeline.process_nodes_with_thresholds(image, nodes)),
+        }
+    }
+}